@@ -6,7 +6,7 @@ fn benchmark_pure(c: &mut Criterion) {
     let x = (0..20).map(|x| x as f32).collect::<Vec<_>>();
     let y = (0..20).map(f64::from).collect::<Vec<_>>();
 
-    c.bench_function("pure single", |b| b.iter(|| (black_box(x.clone()))));
+    c.bench_function("pure single", |b| b.iter(|| black_box(x.clone())));
     c.bench_function("pure double", |b| {
         b.iter(|| (black_box(x.clone()), black_box(y.clone())))
     });
@@ -52,7 +52,7 @@ fn benchmark_map(c: &mut Criterion) {
         }
     }
 
-    let x = (0..20).map(|x| x).collect::<Vec<_>>();
+    let x = (0..20).collect::<Vec<_>>();
 
     c.bench_function("map", |b| b.iter(|| black_box(x.clone().map(fib))));
     c.bench_function("map macro", |b| {
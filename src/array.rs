@@ -0,0 +1,41 @@
+use std::mem::MaybeUninit;
+
+use crate::vec::SliceGuard;
+
+fn fill_array<U, const N: usize, I: Iterator<Item = U>>(iter: I) -> [U; N] {
+    let mut output = [const { MaybeUninit::<U>::uninit() }; N];
+    let mut guard = SliceGuard {
+        slice: &mut output,
+        written: 0,
+    };
+
+    // the iterator yields exactly `N` elements
+    for value in iter {
+        unsafe { guard.push(value) }
+    }
+
+    guard.finish();
+
+    // `[MaybeUninit<U>; N]` has the same layout as `[U; N]`
+    unsafe { (&output as *const [MaybeUninit<U>; N] as *const [U; N]).read() }
+}
+
+/// Map each element of the array by value
+///
+/// If `f` panics, then all of the elements of the input
+/// and all of the mapped elements are dropped
+pub fn map_array<T, U, F: FnMut(T) -> U, const N: usize>(array: [T; N], f: F) -> [U; N] {
+    fill_array(IntoIterator::into_iter(array).map(f))
+}
+
+/// Combine the elements of two arrays by value
+///
+/// If `f` panics, then all of the elements of the inputs
+/// and all of the combined elements are dropped
+pub fn zip_arrays<T, U, V, F: FnMut(T, U) -> V, const N: usize>(
+    a: [T; N],
+    b: [U; N],
+    mut f: F,
+) -> [V; N] {
+    fill_array(IntoIterator::into_iter(a).zip(b).map(move |(x, y)| f(x, y)))
+}
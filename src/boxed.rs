@@ -62,6 +62,14 @@ pub struct UninitBox {
 }
 
 impl UninitBox {
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by the global allocator with the given `layout`,
+    /// and `layout` must not be zero-sized
+    pub(crate) unsafe fn from_raw_parts(ptr: NonNull<u8>, layout: Layout) -> Self {
+        Self { ptr, layout }
+    }
+
     /// The layout of the allocation
     #[inline]
     pub fn layout(&self) -> Layout {
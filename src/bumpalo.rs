@@ -0,0 +1,51 @@
+use ::bumpalo::Bump;
+
+use super::RawParts;
+
+/// Extension methods for mapping a `Vec<T>` into a `bumpalo::Bump` arena
+pub trait ArenaExt: Sized {
+    /// The type that the `Vec<T>` stores
+    type T;
+
+    /// Map a vector into a slice that is allocated in the arena
+    ///
+    /// The allocation of the vector is freed afterwards. Note that the arena
+    /// never runs the destructors of the outputs
+    #[allow(clippy::mut_from_ref)]
+    fn map_into_arena<U, F: FnMut(Self::T) -> U>(self, bump: &Bump, f: F) -> &mut [U];
+
+    /// Zip a vector with another vector and combine them into a slice that is allocated
+    /// in the arena, the output will have the length of the shorter vector
+    ///
+    /// The allocations of the vectors are freed afterwards. Note that the arena
+    /// never runs the destructors of the outputs
+    #[allow(clippy::mut_from_ref)]
+    fn zip_with_into_arena<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        bump: &Bump,
+        f: F,
+    ) -> &mut [V];
+}
+
+impl<T, C: RawParts<Item = T>> ArenaExt for C {
+    type T = T;
+
+    #[allow(clippy::mut_from_ref)]
+    fn map_into_arena<U, F: FnMut(T) -> U>(self, bump: &Bump, f: F) -> &mut [U] {
+        // `vec::IntoIter` will drop any remaining elements if `f` panics
+        bump.alloc_slice_fill_iter(self.into_vec().into_iter().map(f))
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn zip_with_into_arena<U, V, F: FnMut(T, U) -> V>(
+        self,
+        other: Vec<U>,
+        bump: &Bump,
+        mut f: F,
+    ) -> &mut [V] {
+        let iter = self.into_vec().into_iter().zip(other);
+
+        bump.alloc_slice_fill_iter(iter.map(move |(x, y)| f(x, y)))
+    }
+}
@@ -0,0 +1,72 @@
+use ::bytemuck::TransparentWrapper;
+
+use crate::raw::cast_vec;
+
+/// Wrap all of the elements of the vector in place
+///
+/// ```rust
+/// use bytemuck::TransparentWrapper;
+/// use vec_utils::{unwrap_vec, wrap_vec};
+///
+/// #[derive(Debug, PartialEq)]
+/// #[repr(transparent)]
+/// struct Meters(f32);
+///
+/// unsafe impl TransparentWrapper<f32> for Meters {}
+///
+/// let meters: Vec<Meters> = wrap_vec(vec![1.0, 2.5]);
+/// assert_eq!(meters, [Meters(1.0), Meters(2.5)]);
+///
+/// let floats = unwrap_vec::<Meters, _>(meters);
+/// assert_eq!(floats, [1.0, 2.5]);
+/// ```
+pub fn wrap_vec<W: TransparentWrapper<T>, T>(vec: Vec<T>) -> Vec<W> {
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { cast_vec(vec) }
+}
+
+/// Unwrap all of the elements of the vector in place
+pub fn unwrap_vec<W: TransparentWrapper<T>, T>(vec: Vec<W>) -> Vec<T> {
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { cast_vec(vec) }
+}
+
+/// Wrap the value in the box in place
+pub fn wrap_box<W: TransparentWrapper<T>, T>(bx: Box<T>) -> Box<W> {
+    const { assert_same_layout::<T, W>() }
+
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { Box::from_raw(Box::into_raw(bx) as *mut W) }
+}
+
+/// Unwrap the value in the box in place
+pub fn unwrap_box<W: TransparentWrapper<T>, T>(bx: Box<W>) -> Box<T> {
+    const { assert_same_layout::<W, T>() }
+
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { Box::from_raw(Box::into_raw(bx) as *mut T) }
+}
+
+/// Wrap all of the elements of the boxed slice in place
+pub fn wrap_boxed_slice<W: TransparentWrapper<T>, T>(bx: Box<[T]>) -> Box<[W]> {
+    const { assert_same_layout::<T, W>() }
+
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { Box::from_raw(Box::into_raw(bx) as *mut [W]) }
+}
+
+/// Unwrap all of the elements of the boxed slice in place
+pub fn unwrap_boxed_slice<W: TransparentWrapper<T>, T>(bx: Box<[W]>) -> Box<[T]> {
+    const { assert_same_layout::<W, T>() }
+
+    // `W` is a `repr(transparent)` wrapper around `T`
+    unsafe { Box::from_raw(Box::into_raw(bx) as *mut [T]) }
+}
+
+const fn assert_same_layout<T, U>() {
+    assert!(
+        std::mem::size_of::<T>() == std::mem::size_of::<U>()
+            && std::mem::align_of::<T>() == std::mem::align_of::<U>(),
+        "`TransparentWrapper` must be implemented for a `repr(transparent)` wrapper"
+    )
+}
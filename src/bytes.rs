@@ -0,0 +1,58 @@
+use ::bytes::{Bytes, BytesMut};
+
+/// Extension methods for `BytesMut`
+pub trait BytesMutExt: Sized {
+    /// Map each byte of the buffer in place
+    fn map_bytes<F: FnMut(u8) -> u8>(self, f: F) -> BytesMut;
+
+    /// Zip a buffer with another buffer and combine them, the result will be returned,
+    /// the buffer with the larger capacity will be reused to write the output
+    ///
+    /// The output will have the length of the shorter buffer
+    fn zip_with<F: FnMut(u8, u8) -> u8>(self, other: BytesMut, f: F) -> BytesMut;
+}
+
+impl BytesMutExt for BytesMut {
+    fn map_bytes<F: FnMut(u8) -> u8>(mut self, mut f: F) -> BytesMut {
+        for byte in self.iter_mut() {
+            *byte = f(*byte);
+        }
+
+        self
+    }
+
+    fn zip_with<F: FnMut(u8, u8) -> u8>(self, other: BytesMut, mut f: F) -> BytesMut {
+        let len = self.len().min(other.len());
+
+        let (mut output, input, flip) = if self.capacity() >= other.capacity() {
+            (self, other, false)
+        } else {
+            (other, self, true)
+        };
+
+        output.truncate(len);
+
+        for (out, &x) in output.iter_mut().zip(input.iter()) {
+            *out = if flip { f(x, *out) } else { f(*out, x) };
+        }
+
+        output
+    }
+}
+
+/// Extension methods for `Bytes`
+pub trait BytesExt: Sized {
+    /// Map each byte of the buffer, if this is the only handle to the
+    /// underlying buffer then the buffer will be reused. Otherwise
+    /// the mapped bytes will be copied into a new buffer
+    fn map_bytes<F: FnMut(u8) -> u8>(self, f: F) -> Bytes;
+}
+
+impl BytesExt for Bytes {
+    fn map_bytes<F: FnMut(u8) -> u8>(self, mut f: F) -> Bytes {
+        match self.try_into_mut() {
+            Ok(bytes) => bytes.map_bytes(f).freeze(),
+            Err(bytes) => bytes.iter().map(move |&x| f(x)).collect(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// What to do with the remaining elements when a destructor panics during cleanup
+///
+/// When a mapping function panics, the elements that were not yet processed
+/// are dropped while unwinding. If one of those destructors also panics, the
+/// process aborts. This policy lets you trade that abort for a leak.
+///
+/// The policy is process-wide, see [`set_cleanup_policy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CleanupPolicy {
+    /// Drop all of the remaining elements, a destructor that panics
+    /// while already unwinding aborts the process
+    #[default]
+    Abort,
+    /// Leak the remaining elements if we are already unwinding, otherwise
+    /// drop them in order, and leak the rest after the first destructor panics
+    Leak,
+    /// Leak the remaining elements if we are already unwinding, otherwise
+    /// drop all of them, even if some destructors panic. The first panic is
+    /// resumed after all of the elements are dropped
+    Continue,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(CleanupPolicy::Abort as u8);
+
+/// Set the process-wide [`CleanupPolicy`]
+pub fn set_cleanup_policy(policy: CleanupPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed)
+}
+
+/// Get the process-wide [`CleanupPolicy`]
+pub fn cleanup_policy() -> CleanupPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        1 => CleanupPolicy::Leak,
+        2 => CleanupPolicy::Continue,
+        _ => CleanupPolicy::Abort,
+    }
+}
+
+/// Drop `len` elements starting at `ptr` according to the [`CleanupPolicy`]
+///
+/// # Safety
+///
+/// same as `std::ptr::drop_in_place` on the slice
+pub(crate) unsafe fn drop_slice<T>(ptr: *mut T, len: usize) {
+    if !std::mem::needs_drop::<T>() {
+        return;
+    }
+
+    #[cfg(feature = "test-faults")]
+    if let Some(offset) = crate::faults::drop_fault(len) {
+        // the destructor at `offset` panics after dropping its element
+        let len = match cleanup_policy() {
+            CleanupPolicy::Leak => offset + 1,
+            _ => len,
+        };
+
+        std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(ptr, len));
+        crate::faults::drop_panic()
+    }
+
+    match cleanup_policy() {
+        CleanupPolicy::Abort => {
+            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(ptr, len))
+        }
+        _ if std::thread::panicking() => (),
+        CleanupPolicy::Leak => {
+            for i in 0..len {
+                ptr.add(i).drop_in_place();
+            }
+        }
+        CleanupPolicy::Continue => {
+            let mut payload = None;
+
+            for i in 0..len {
+                if let Err(p) = catch_unwind(AssertUnwindSafe(|| ptr.add(i).drop_in_place())) {
+                    payload.get_or_insert(p);
+                }
+            }
+
+            if let Some(payload) = payload {
+                resume_unwind(payload)
+            }
+        }
+    }
+}
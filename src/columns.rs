@@ -0,0 +1,249 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use crate::raw::scale;
+
+/// Conversions between an array of structs (`Vec<Self>`) and
+/// a struct of arrays (a tuple of field vectors)
+///
+/// This is implemented for tuples of up to 6 elements, and can be
+/// implemented for your own structs with [`impl_columns`]
+///
+/// If the layouts permit, the allocation of the input is reused for one of
+/// the outputs. When splitting, the allocation of the `Vec<Self>` is reused
+/// for the column with the largest field. When joining, the allocation of the
+/// column with the largest field is reused for the `Vec<Self>`, if it has
+/// enough capacity
+pub trait Columns: Sized {
+    /// A tuple of field vectors
+    type Columns;
+
+    /// Split the vector into a vector for each field
+    fn into_columns(vec: Vec<Self>) -> Self::Columns;
+
+    /// Join the field vectors into a single vector
+    ///
+    /// # Panic
+    ///
+    /// If the columns don't all have the same length
+    fn from_columns(columns: Self::Columns) -> Vec<Self>;
+}
+
+/// Implement [`Columns`] for a struct with named fields
+///
+/// ```rust
+/// use vec_utils::{impl_columns, Columns};
+///
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// impl_columns!(Point { x: f32, y: f32 });
+///
+/// let points = vec![Point { x: 0.0, y: 1.0 }, Point { x: 2.0, y: 3.0 }];
+/// let (x, y) = Point::into_columns(points);
+///
+/// assert_eq!(x, [0.0, 2.0]);
+/// assert_eq!(y, [1.0, 3.0]);
+/// ```
+#[macro_export]
+macro_rules! impl_columns {
+    ($name:ident { $($field:ident: $type:ty),+ $(,)? }) => {
+        impl $crate::Columns for $name {
+            type Columns = ($(Vec<$type>,)+);
+
+            fn into_columns(vec: Vec<Self>) -> Self::Columns {
+                unsafe {
+                    <($($type,)+) as $crate::Fields>::split(
+                        vec,
+                        |$name { $($field),+ }| ($($field,)+),
+                    )
+                }
+            }
+
+            fn from_columns(columns: Self::Columns) -> Vec<Self> {
+                unsafe {
+                    <($($type,)+) as $crate::Fields>::gather(
+                        columns,
+                        |($($field,)+)| $name { $($field),+ },
+                    )
+                }
+            }
+        }
+    };
+}
+
+/// The machinery behind [`Columns`], implemented for tuples of fields
+///
+/// # Safety
+///
+/// `split` and `gather` must move each field between the
+/// struct and the corresponding column
+#[doc(hidden)]
+pub unsafe trait Fields: Sized {
+    type Columns;
+
+    /// # Safety
+    ///
+    /// `f` must not panic, and `Self` must be the fields of `T`
+    unsafe fn split<T, Func: FnMut(T) -> Self>(vec: Vec<T>, f: Func) -> Self::Columns;
+
+    /// # Safety
+    ///
+    /// `f` must not panic, and `Self` must be the fields of `T`
+    unsafe fn gather<T, Func: FnMut(Self) -> T>(columns: Self::Columns, f: Func) -> Vec<T>;
+}
+
+/// Pick the largest field that can take over the allocation of a `Vec<T>`
+///
+/// Fields are written front-to-back, and field `i` ends before element `i + 1`
+/// starts, so the output never overwrites any unread input
+fn split_target(t: Layout, cap: usize, fields: &[Layout]) -> Option<usize> {
+    fields
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.size() <= t.size() && scale(cap, t, **f).is_ok())
+        .max_by_key(|&(i, f)| (f.size(), std::cmp::Reverse(i)))
+        .map(|(i, _)| i)
+}
+
+/// Pick the largest field column that can hold `len` elements of `T`
+///
+/// Elements are written back-to-front, and element `i` starts after field `i - 1`
+/// ends, so the output never overwrites any unread input
+fn gather_target(t: Layout, len: usize, columns: &[(Layout, usize)]) -> Option<usize> {
+    columns
+        .iter()
+        .enumerate()
+        .filter(|(_, (f, cap))| {
+            f.size() <= t.size() && matches!(scale(*cap, *f, t), Ok(cap) if cap >= len)
+        })
+        .max_by_key(|&(i, (f, _))| (f.size(), std::cmp::Reverse(i)))
+        .map(|(i, _)| i)
+}
+
+enum Column<F> {
+    Fresh(Vec<F>),
+    Reused(*mut F, usize),
+}
+
+impl<F> Column<F> {
+    fn new(len: usize, reuse: bool, ptr: *mut u8, bytes: usize) -> Self {
+        if reuse {
+            Column::Reused(ptr as *mut F, bytes / std::mem::size_of::<F>())
+        } else {
+            Column::Fresh(Vec::with_capacity(len))
+        }
+    }
+
+    unsafe fn write(&mut self, index: usize, value: F) {
+        let ptr = match self {
+            Column::Fresh(vec) => vec.as_mut_ptr(),
+            Column::Reused(ptr, _) => *ptr,
+        };
+
+        ptr.add(index).write(value)
+    }
+
+    unsafe fn finish(self, len: usize) -> Vec<F> {
+        match self {
+            Column::Fresh(mut vec) => {
+                vec.set_len(len);
+                vec
+            }
+            Column::Reused(ptr, cap) => Vec::from_raw_parts(ptr, len, cap),
+        }
+    }
+}
+
+macro_rules! fields {
+    ($($T:ident $i:tt),+) => {
+        unsafe impl<$($T),+> Fields for ($($T,)+) {
+            type Columns = ($(Vec<$T>,)+);
+
+            unsafe fn split<T, Func: FnMut(T) -> Self>(mut vec: Vec<T>, mut f: Func) -> Self::Columns {
+                let len = vec.len();
+                let bytes = vec.capacity() * std::mem::size_of::<T>();
+                let ptr = vec.as_mut_ptr();
+
+                let reuse = split_target(Layout::new::<T>(), vec.capacity(), &[$(Layout::new::<$T>()),+]);
+
+                let mut columns = ($(Column::<$T>::new(len, reuse == Some($i), ptr as *mut u8, bytes),)+);
+
+                for i in 0..len {
+                    let value = f(ptr.add(i).read());
+                    $(columns.$i.write(i, value.$i);)+
+                }
+
+                vec.set_len(0);
+
+                if reuse.is_some() {
+                    std::mem::forget(vec);
+                }
+
+                ($(columns.$i.finish(len),)+)
+            }
+
+            unsafe fn gather<T, Func: FnMut(Self) -> T>(columns: Self::Columns, mut f: Func) -> Vec<T> {
+                let len = columns.0.len();
+
+                $(assert_eq!(columns.$i.len(), len, "all columns must have the same length");)+
+
+                let reuse = gather_target(
+                    Layout::new::<T>(),
+                    len,
+                    &[$((Layout::new::<$T>(), columns.$i.capacity())),+],
+                );
+
+                let mut output = ManuallyDrop::new(match reuse {
+                    Some(_) => Vec::new(),
+                    None => Vec::with_capacity(len),
+                });
+
+                let mut columns = ($(ManuallyDrop::new(columns.$i),)+);
+
+                let (ptr, cap) = match reuse {
+                    $(Some($i) => (
+                        columns.$i.as_mut_ptr() as *mut T,
+                        columns.$i.capacity() * std::mem::size_of::<$T>() / std::mem::size_of::<T>(),
+                    ),)+
+                    _ => (output.as_mut_ptr(), output.capacity()),
+                };
+
+                for i in (0..len).rev() {
+                    let value = ($(columns.$i.as_ptr().add(i).read(),)+);
+                    ptr.add(i).write(f(value));
+                }
+
+                $(
+                    if reuse != Some($i) {
+                        columns.$i.set_len(0);
+                        ManuallyDrop::drop(&mut columns.$i);
+                    }
+                )+
+
+                Vec::from_raw_parts(ptr, len, cap)
+            }
+        }
+
+        impl<$($T),+> Columns for ($($T,)+) {
+            type Columns = ($(Vec<$T>,)+);
+
+            fn into_columns(vec: Vec<Self>) -> Self::Columns {
+                unsafe { Fields::split(vec, |x| x) }
+            }
+
+            fn from_columns(columns: Self::Columns) -> Vec<Self> {
+                unsafe { Fields::gather(columns, |x| x) }
+            }
+        }
+    };
+}
+
+fields!(A 0);
+fields!(A 0, B 1);
+fields!(A 0, B 1, C 2);
+fields!(A 0, B 1, C 2, D 3);
+fields!(A 0, B 1, C 2, D 3, E 4);
+fields!(A 0, B 1, C 2, D 3, E 4, F 5);
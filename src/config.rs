@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+use crate::{trace, RecycleBin};
+
+/// Where the output is allocated when the allocation of an input isn't reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FallbackStrategy {
+    /// Take the allocation from the thread-local [`RecycleBin`] if possible,
+    /// otherwise allocate a new one
+    #[default]
+    RecycleBin,
+    /// Always allocate a new allocation
+    Allocate,
+}
+
+/// The heuristics that decide if and how the allocation of an input is reused
+///
+/// This is used by the `_with_config` variants of [`VecExt::map`](crate::VecExt::map),
+/// [`VecExt::zip_with`](crate::VecExt::zip_with),
+/// [`VecExt::try_filter_map`](crate::VecExt::try_filter_map), and
+/// [`ReuseIter::collect_reuse`](crate::ReuseIter::collect_reuse), the other variants use the
+/// process-wide config, see [`set_reuse_config`]
+///
+/// The process-wide config is also used by [`zip_with!`](crate::zip_with),
+/// [`try_zip_with!`](crate::try_zip_with), [`VecExt::take_map`](crate::VecExt::take_map),
+/// [`VecExt::flat_map_reuse`](crate::VecExt::flat_map_reuse),
+/// [`ReuseIter::partition_result`](crate::ReuseIter::partition_result), and
+/// [`ReuseIter::collect_string`](crate::ReuseIter::collect_string). The other transforms,
+/// like [`zip_assign!`](crate::zip_assign), reuse an allocation whenever the layouts allow it
+///
+/// The default config reuses an allocation whenever the layouts allow it
+///
+/// ```rust
+/// use vec_utils::{ReuseConfig, VecExt};
+///
+/// let config = ReuseConfig {
+///     max_waste: Some(16),
+///     ..ReuseConfig::default()
+/// };
+///
+/// let mut vec = Vec::<u32>::with_capacity(1024);
+/// vec.extend([1, 2, 3]);
+/// let ptr = vec.as_ptr();
+///
+/// let vec = vec.map_with_config(config, |x| x as f32);
+/// assert_ne!(vec.as_ptr() as usize, ptr as usize);
+/// assert_eq!(vec, [1.0, 2.0, 3.0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ReuseConfig {
+    /// Don't reuse an allocation that would have more than this many unused
+    /// elements, a smaller allocation is used for the output instead
+    ///
+    /// The length of the output is predicted from the length of the input,
+    /// or from [`ReuseIter::with_capacity_hint`](crate::ReuseIter::with_capacity_hint)
+    pub max_waste: Option<usize>,
+    /// Shrink the output to fit once it's finished
+    pub shrink_on_finish: bool,
+    /// Where the output is allocated when no allocation is reused
+    pub fallback: FallbackStrategy,
+    /// Don't reuse the allocation of inputs that are shorter than this
+    pub small_input: usize,
+}
+
+// `usize::MAX` is `None`
+static MAX_WASTE: AtomicUsize = AtomicUsize::new(usize::MAX);
+static SHRINK_ON_FINISH: AtomicBool = AtomicBool::new(false);
+static FALLBACK: AtomicU8 = AtomicU8::new(FallbackStrategy::RecycleBin as u8);
+static SMALL_INPUT: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the process-wide [`ReuseConfig`]
+///
+/// The fields are stored separately, so a config that is set while another
+/// thread reads it may be observed partially
+pub fn set_reuse_config(config: ReuseConfig) {
+    let max_waste = config.max_waste.unwrap_or(usize::MAX);
+
+    MAX_WASTE.store(max_waste, Ordering::Relaxed);
+    SHRINK_ON_FINISH.store(config.shrink_on_finish, Ordering::Relaxed);
+    FALLBACK.store(config.fallback as u8, Ordering::Relaxed);
+    SMALL_INPUT.store(config.small_input, Ordering::Relaxed);
+}
+
+/// Get the process-wide [`ReuseConfig`]
+pub fn reuse_config() -> ReuseConfig {
+    ReuseConfig {
+        max_waste: match MAX_WASTE.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            max_waste => Some(max_waste),
+        },
+        shrink_on_finish: SHRINK_ON_FINISH.load(Ordering::Relaxed),
+        fallback: match FALLBACK.load(Ordering::Relaxed) {
+            1 => FallbackStrategy::Allocate,
+            _ => FallbackStrategy::RecycleBin,
+        },
+        small_input: SMALL_INPUT.load(Ordering::Relaxed),
+    }
+}
+
+impl ReuseConfig {
+    /// Check if an allocation with the given capacity should be reused
+    /// for an output of `len` elements
+    pub(crate) fn reuses(&self, len: usize, capacity: usize) -> bool {
+        len >= self.small_input
+            && self
+                .max_waste
+                .is_none_or(|max| capacity.saturating_sub(len) <= max)
+    }
+
+    /// The capacity of a reused allocation once the output is finished
+    pub(crate) fn finished_capacity(&self, len: usize, capacity: usize) -> usize {
+        if self.shrink_on_finish {
+            len
+        } else {
+            capacity
+        }
+    }
+
+    /// Apply the finishing touches to the output
+    pub(crate) fn finish<T>(&self, mut vec: Vec<T>) -> Vec<T> {
+        if self.shrink_on_finish {
+            vec.shrink_to_fit();
+        }
+
+        vec
+    }
+
+    /// Take an empty vector with at least `capacity` elements from the thread-local
+    /// [`RecycleBin`], if the fallback strategy allows it
+    fn recycled<T>(&self, capacity: usize) -> Option<Vec<T>> {
+        let vec = match self.fallback {
+            FallbackStrategy::RecycleBin => RecycleBin::take_local(capacity),
+            FallbackStrategy::Allocate => None,
+        };
+
+        trace::fallback(capacity, vec.is_some());
+
+        vec
+    }
+
+    /// Allocate an empty vector with at least `capacity` elements
+    /// according to the fallback strategy
+    pub(crate) fn allocate<T>(&self, capacity: usize) -> Vec<T> {
+        self.recycled(capacity)
+            .unwrap_or_else(|| Vec::with_capacity(capacity))
+    }
+
+    /// Collect the iterator into a new vector with at least `capacity` elements,
+    /// allocated according to the fallback strategy
+    pub(crate) fn try_collect<T, E, I: Iterator<Item = Result<T, E>>>(
+        &self,
+        iter: I,
+        capacity: usize,
+    ) -> Result<Vec<T>, E> {
+        let mut vec = match self.recycled(capacity) {
+            Some(vec) => vec,
+            // `collect` may reuse the allocation of the source in place, which
+            // the config only allows if it never declines an allocation
+            None if self.max_waste.is_none()
+                && self.small_input == 0
+                && capacity <= iter.size_hint().0 =>
+            {
+                return iter.collect::<Result<_, _>>().map(|vec| self.finish(vec));
+            }
+            None => Vec::with_capacity(capacity),
+        };
+
+        let mut error = None;
+        vec.extend(iter.map_while(|value| value.map_err(|e| error = Some(e)).ok()));
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(self.finish(vec)),
+        }
+    }
+
+    /// The same as `ReuseConfig::try_collect`, for infallible iterators
+    pub(crate) fn collect<T, I: Iterator<Item = T>>(&self, iter: I, capacity: usize) -> Vec<T> {
+        match self.try_collect(iter.map(Ok::<_, std::convert::Infallible>), capacity) {
+            Ok(vec) => vec,
+            Err(x) => match x {},
+        }
+    }
+}
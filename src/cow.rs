@@ -0,0 +1,33 @@
+use std::borrow::Cow;
+
+use super::VecExt;
+
+/// Extension methods for `Cow<[T]>`
+pub trait CowExt<'a>: Sized {
+    /// The type that the `Cow<[T]>` stores
+    type T;
+
+    /// Map the slice to a vector, if the `Cow` is `Owned` this will try and
+    /// reuse the allocation if the allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused
+    ///
+    /// If the `Cow` is `Borrowed`, the slice is cloned into a new vector first
+    fn map<U, F: FnMut(Self::T) -> U>(self, f: F) -> Vec<U>;
+
+    /// The same as `CowExt::map`, but wraps the result back into a `Cow`
+    fn map_in_place<U: Clone, F: FnMut(Self::T) -> U>(self, f: F) -> Cow<'a, [U]>;
+}
+
+impl<'a, T: Clone> CowExt<'a> for Cow<'a, [T]> {
+    type T = T;
+
+    fn map<U, F: FnMut(T) -> U>(self, f: F) -> Vec<U> {
+        // `into_owned` is free for `Cow::Owned`
+        self.into_owned().map(f)
+    }
+
+    fn map_in_place<U: Clone, F: FnMut(T) -> U>(self, f: F) -> Cow<'a, [U]> {
+        Cow::Owned(CowExt::map(self, f))
+    }
+}
@@ -0,0 +1,154 @@
+//! Hooks that simulate panics inside of the internal loops, so that the cleanup
+//! paths of code built on top of this crate can be tested deterministically
+//!
+//! The hooks do nothing unless the `test-faults` feature is enabled
+
+#[cfg(feature = "test-faults")]
+use std::cell::Cell;
+
+/// The faults to inject on the current thread, see [`Faults::inject`]
+///
+/// The closure faults apply to the mapping functions of `VecExt::map`, `VecExt::zip_with`,
+/// their fallible variants, and the `zip_with` family of macros. The drop faults apply to the
+/// elements that are dropped by this crate while cleaning up, for example the rest of the
+/// input after a mapping function returns an error
+#[cfg(feature = "test-faults")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Faults {
+    /// Panic instead of calling the mapping function for the `n`th time, counting from zero.
+    /// The input of the mapping function is dropped first, as if the mapping function panicked
+    pub closure_panic: Option<usize>,
+    /// Panic after dropping the `n`th element during cleanup, counting from zero.
+    /// The rest of the elements are then handled according to the
+    /// [`CleanupPolicy`](crate::CleanupPolicy), as if the destructor panicked
+    ///
+    /// Only elements that need to be dropped are counted, and faults are not
+    /// injected while the thread is already panicking, as that would abort
+    pub drop_panic: Option<usize>,
+}
+
+/// The payload of an injected panic
+#[cfg(feature = "test-faults")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InjectedFault {
+    /// A simulated panic in a mapping function
+    Closure {
+        /// The number of times that the mapping functions were called before
+        index: usize,
+    },
+    /// A simulated panic in a destructor
+    Drop {
+        /// The number of elements that were dropped before
+        index: usize,
+    },
+}
+
+#[cfg(feature = "test-faults")]
+#[derive(Clone, Copy)]
+struct State {
+    faults: Faults,
+    calls: usize,
+    drops: usize,
+}
+
+#[cfg(feature = "test-faults")]
+thread_local! {
+    static STATE: Cell<Option<State>> = const { Cell::new(None) };
+}
+
+#[cfg(feature = "test-faults")]
+impl Faults {
+    /// Run `f` with the faults injected on the current thread, the counts start
+    /// at zero and the previous faults are restored afterwards, even if `f` panics
+    ///
+    /// ```rust
+    /// use vec_utils::{Faults, InjectedFault, VecExt};
+    ///
+    /// let faults = Faults {
+    ///     closure_panic: Some(2),
+    ///     ..Faults::default()
+    /// };
+    ///
+    /// let result = std::panic::catch_unwind(|| faults.inject(|| vec![1, 2, 3].map(|x| x + 1)));
+    ///
+    /// let payload = result.unwrap_err();
+    /// let fault = payload.downcast_ref::<InjectedFault>();
+    /// assert_eq!(fault, Some(&InjectedFault::Closure { index: 2 }));
+    /// ```
+    pub fn inject<R, F: FnOnce() -> R>(self, f: F) -> R {
+        let state = State {
+            faults: self,
+            calls: 0,
+            drops: 0,
+        };
+
+        let prev = STATE.with(move |state_cell| state_cell.replace(Some(state)));
+
+        defer! {
+            STATE.with(move |state| state.set(prev));
+        }
+
+        f()
+    }
+}
+
+/// Called right before a mapping function is called
+#[inline(always)]
+pub(crate) fn closure() {
+    #[cfg(feature = "test-faults")]
+    {
+        let fault = STATE.with(|state| {
+            let mut current = state.get()?;
+            let index = current.calls;
+            current.calls += 1;
+            state.set(Some(current));
+
+            current.faults.closure_panic.filter(|&n| n == index)
+        });
+
+        if let Some(index) = fault {
+            std::panic::panic_any(InjectedFault::Closure { index })
+        }
+    }
+}
+
+/// Call [`closure`] before each call of `f`
+#[inline(always)]
+pub(crate) fn hook<T, R>(mut f: impl FnMut(T) -> R) -> impl FnMut(T) -> R {
+    move |x| {
+        closure();
+        f(x)
+    }
+}
+
+/// Called before `len` elements are dropped during cleanup, returns
+/// the offset of the element whose destructor should panic
+#[cfg(feature = "test-faults")]
+pub(crate) fn drop_fault(len: usize) -> Option<usize> {
+    if std::thread::panicking() {
+        return None;
+    }
+
+    STATE.with(|state| {
+        let mut current = state.get()?;
+        let start = current.drops;
+        current.drops += len;
+        state.set(Some(current));
+
+        current
+            .faults
+            .drop_panic
+            .filter(|n| (start..start + len).contains(n))
+            .map(|n| n - start)
+    })
+}
+
+/// Simulate the panic of the destructor that was picked by [`drop_fault`]
+#[cfg(feature = "test-faults")]
+pub(crate) fn drop_panic() -> ! {
+    let index = STATE.with(|state| state.get().and_then(|state| state.faults.drop_panic));
+
+    std::panic::panic_any(InjectedFault::Drop {
+        index: index.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,498 @@
+//! Free functions mirroring [`VecExt`], for when method syntax is inconvenient
+//!
+//! ```rust
+//! let vec = vec_utils::fns::map(vec![1.0f32, 2.0], f32::to_bits);
+//! let sum = vec_utils::fns::zip_with(vec, vec![1, 2], |x, y| x + y);
+//!
+//! assert_eq!(sum, [1.0f32.to_bits() + 1, 2.0f32.to_bits() + 2]);
+//! ```
+
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::mem::MaybeUninit;
+use std::ops::{Add, RangeBounds};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{
+    AllocError, Drain, EitherOrBoth, GatherError, OddPolicy, RecycledAlloc, ReuseConfig, ReuseIter,
+    Source, Try, VecExt,
+};
+
+/// See [`VecExt::map`]
+pub fn map<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, f: F) -> Vec<U> {
+    vec.map(f)
+}
+
+/// See [`VecExt::try_map`]
+pub fn try_map<C: VecExt, U, R: Try<Ok = U>, F: FnMut(C::T) -> R>(
+    vec: C,
+    f: F,
+) -> Result<Vec<U>, R::Error> {
+    vec.try_map(f)
+}
+
+/// See [`VecExt::map_ref`]
+pub fn map_ref<C: VecExt, U, F: FnMut(&C::T) -> U>(vec: C, f: F) -> Vec<U> {
+    vec.map_ref(f)
+}
+
+/// See [`VecExt::try_map_ref`]
+pub fn try_map_ref<C: VecExt, U, R: Try<Ok = U>, F: FnMut(&C::T) -> R>(
+    vec: C,
+    f: F,
+) -> Result<Vec<U>, R::Error> {
+    vec.try_map_ref(f)
+}
+
+/// See [`VecExt::map_inspect`]
+pub fn map_inspect<C: VecExt, U, F: FnMut(&C::T) -> U, I: FnMut(usize, &C::T, &U)>(
+    vec: C,
+    f: F,
+    inspect: I,
+) -> Vec<U> {
+    vec.map_inspect(f, inspect)
+}
+
+/// See [`VecExt::zip_inspect`]
+pub fn zip_inspect<C: VecExt, U, V, F, I>(vec: C, other: Vec<U>, f: F, inspect: I) -> Vec<V>
+where
+    F: FnMut(&C::T, &U) -> V,
+    I: FnMut(usize, (&C::T, &U), &V),
+{
+    vec.zip_inspect(other, f, inspect)
+}
+
+/// See [`VecExt::zip_with`]
+pub fn zip_with<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(vec: C, other: Vec<U>, f: F) -> Vec<V> {
+    vec.zip_with(other, f)
+}
+
+/// See [`VecExt::try_zip_with`]
+pub fn try_zip_with<C: VecExt, U, V, R: Try<Ok = V>, F: FnMut(C::T, U) -> R>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Result<Vec<V>, R::Error> {
+    vec.try_zip_with(other, f)
+}
+
+/// See [`VecExt::drop_and_reuse`]
+pub fn drop_and_reuse<C: VecExt, U>(vec: C) -> Vec<U> {
+    vec.drop_and_reuse()
+}
+
+/// See [`VecExt::map_range`]
+pub fn map_range<C: VecExt, R: RangeBounds<usize>, F: FnMut(C::T) -> C::T>(
+    vec: &mut C,
+    range: R,
+    f: F,
+) {
+    vec.map_range(range, f)
+}
+
+/// See [`VecExt::splice_map`]
+pub fn splice_map<C: VecExt, U, R: RangeBounds<usize>, F: FnMut(C::T) -> U>(
+    vec: C,
+    range: R,
+    replacement: Vec<U>,
+    f: F,
+) -> Vec<U> {
+    vec.splice_map(range, replacement, f)
+}
+
+/// See [`VecExt::retain_map`]
+pub fn retain_map<C: VecExt, F: FnMut(C::T) -> Option<C::T>>(vec: &mut C, f: F) {
+    vec.retain_map(f)
+}
+
+/// See [`VecExt::into_reuse_iter`]
+pub fn into_reuse_iter<C: VecExt>(vec: C) -> ReuseIter<Source<C::T>> {
+    vec.into_reuse_iter()
+}
+
+/// See [`VecExt::retire`]
+pub fn retire<C: VecExt>(vec: C) {
+    vec.retire()
+}
+
+/// See [`VecExt::transpose_in_place`]
+pub fn transpose_in_place<C: VecExt>(vec: &mut C, rows: usize, cols: usize) {
+    vec.transpose_in_place(rows, cols)
+}
+
+/// See [`VecExt::transpose_with`]
+pub fn transpose_with<C: VecExt>(vec: &mut C, rows: usize, cols: usize, scratch: &mut Vec<C::T>) {
+    vec.transpose_with(rows, cols, scratch)
+}
+
+/// See [`VecExt::interleave`]
+pub fn interleave<C: VecExt>(vec: C, other: Vec<C::T>) -> Vec<C::T> {
+    vec.interleave(other)
+}
+
+/// See [`VecExt::map_into_boxed_slice`]
+pub fn map_into_boxed_slice<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, f: F) -> Box<[U]> {
+    vec.map_into_boxed_slice(f)
+}
+
+/// See [`VecExt::map_into_shared`]
+pub fn map_into_shared<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, f: F) -> Arc<[U]> {
+    vec.map_into_shared(f)
+}
+
+/// See [`VecExt::map_into_rc`]
+pub fn map_into_rc<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, f: F) -> Rc<[U]> {
+    vec.map_into_rc(f)
+}
+
+/// See [`VecExt::zip_with_into_slice`]
+pub fn zip_with_into_slice<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    out: &mut [MaybeUninit<V>],
+    f: F,
+) -> usize {
+    vec.zip_with_into_slice(other, out, f)
+}
+
+/// See [`VecExt::zip_with_swap`]
+pub fn zip_with_swap<C: VecExt, U, F: FnMut(C::T, U) -> (C::T, U)>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> (Vec<C::T>, Vec<U>) {
+    vec.zip_with_swap(other, f)
+}
+
+/// See [`VecExt::map_expand`]
+pub fn map_expand<C: VecExt, U, F: FnMut(C::T) -> [U; K], const K: usize>(vec: C, f: F) -> Vec<U> {
+    vec.map_expand(f)
+}
+
+/// See [`VecExt::try_map_mut`]
+pub fn try_map_mut<C: VecExt, R: Try<Ok = ()>, F: FnMut(&mut C::T) -> R, G: FnMut(&mut C::T)>(
+    vec: &mut C,
+    f: F,
+    undo: G,
+) -> Result<(), R::Error> {
+    vec.try_map_mut(f, undo)
+}
+
+/// See [`VecExt::try_map_falloc`]
+pub fn try_map_falloc<C: VecExt, U, F: FnMut(C::T) -> U>(
+    vec: C,
+    f: F,
+) -> Result<Vec<U>, AllocError> {
+    vec.try_map_falloc(f)
+}
+
+/// See [`VecExt::try_zip_with_falloc`]
+pub fn try_zip_with_falloc<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Result<Vec<V>, AllocError> {
+    vec.try_zip_with_falloc(other, f)
+}
+
+/// See [`VecExt::map_rev`]
+pub fn map_rev<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, f: F) -> Vec<U> {
+    vec.map_rev(f)
+}
+
+/// See [`VecExt::zip_with_rev`]
+pub fn zip_with_rev<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Vec<V> {
+    vec.zip_with_rev(other, f)
+}
+
+/// See [`VecExt::take_map`]
+pub fn take_map<C: VecExt, U, F: FnMut(C::T) -> U>(vec: C, n: usize, f: F) -> (Vec<U>, Vec<C::T>) {
+    vec.take_map(n, f)
+}
+
+/// See [`VecExt::zip_with_threads`]
+pub fn zip_with_threads<C: VecExt, U: Send, V: Send, F: Fn(C::T, U) -> V + Sync>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+    num_threads: usize,
+) -> Vec<V>
+where
+    C::T: Send,
+{
+    vec.zip_with_threads(other, f, num_threads)
+}
+
+/// See [`VecExt::zip_longest_with`]
+pub fn zip_longest_with<C: VecExt, U, V, F: FnMut(EitherOrBoth<C::T, U>) -> V>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Vec<V> {
+    vec.zip_longest_with(other, f)
+}
+
+/// See [`VecExt::merge_with`]
+pub fn merge_with<C: VecExt, F: FnMut(&C::T, &C::T) -> Ordering>(
+    vec: C,
+    other: Vec<C::T>,
+    cmp: F,
+) -> Vec<C::T> {
+    vec.merge_with(other, cmp)
+}
+
+/// See [`VecExt::merge_by_key`]
+pub fn merge_by_key<C: VecExt, K: Ord, F: FnMut(&C::T) -> K>(
+    vec: C,
+    other: Vec<C::T>,
+    f: F,
+) -> Vec<C::T> {
+    vec.merge_by_key(other, f)
+}
+
+/// See [`VecExt::intersperse_map`]
+pub fn intersperse_map<C: VecExt, U, S: FnMut() -> U, F: FnMut(C::T) -> U>(
+    vec: C,
+    sep_fn: S,
+    f: F,
+) -> Vec<U> {
+    vec.intersperse_map(sep_fn, f)
+}
+
+/// See [`VecExt::zip_column_with`]
+pub fn zip_column_with<C: VecExt, U, F: FnMut(C::T, U) -> C::T>(
+    vec: C,
+    stride: usize,
+    col: usize,
+    other: Vec<U>,
+    f: F,
+) -> Vec<C::T> {
+    vec.zip_column_with(stride, col, other, f)
+}
+
+/// See [`VecExt::map_column`]
+pub fn map_column<C: VecExt, F: FnMut(C::T) -> C::T>(
+    vec: C,
+    stride: usize,
+    col: usize,
+    f: F,
+) -> Vec<C::T> {
+    vec.map_column(stride, col, f)
+}
+
+/// See [`VecExt::gather_map`]
+pub fn gather_map<C: VecExt, U, F: FnMut(C::T) -> U>(
+    vec: C,
+    indices: &[usize],
+    f: F,
+) -> Result<Vec<U>, GatherError> {
+    vec.gather_map(indices, f)
+}
+
+/// See [`VecExt::join_with`]
+pub fn join_with<C: VecExt, U, K: Hash + Eq, V, KA, KB, F>(
+    vec: C,
+    other: Vec<U>,
+    key_a: KA,
+    key_b: KB,
+    f: F,
+) -> Vec<V>
+where
+    KA: FnMut(&C::T) -> K,
+    KB: FnMut(&U) -> K,
+    F: FnMut(C::T, U) -> V,
+{
+    vec.join_with(other, key_a, key_b, f)
+}
+
+/// See [`VecExt::cartesian_with`]
+pub fn cartesian_with<C: VecExt, U, V, F: FnMut(&C::T, &U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Vec<V> {
+    vec.cartesian_with(other, f)
+}
+
+/// See [`VecExt::rle_encode`]
+pub fn rle_encode<C: VecExt>(vec: C) -> Vec<(C::T, usize)>
+where
+    C::T: PartialEq,
+{
+    vec.rle_encode()
+}
+
+/// See [`VecExt::reduce_pairs`]
+pub fn reduce_pairs<C: VecExt, F: FnMut(C::T, C::T) -> C::T>(
+    vec: C,
+    odd: OddPolicy,
+    f: F,
+) -> Vec<C::T> {
+    vec.reduce_pairs(odd, f)
+}
+
+/// See [`VecExt::scan_in_place`]
+pub fn scan_in_place<C: VecExt, U, Acc, F: FnMut(&Acc, C::T) -> (Acc, U)>(
+    vec: C,
+    init: Acc,
+    f: F,
+) -> Vec<U> {
+    vec.scan_in_place(init, f)
+}
+
+/// See [`VecExt::prefix_sum`]
+pub fn prefix_sum<C: VecExt>(vec: C) -> Vec<C::T>
+where
+    C::T: Copy + Add<Output = C::T>,
+{
+    vec.prefix_sum()
+}
+
+/// See [`VecExt::exclusive_prefix_sum`]
+pub fn exclusive_prefix_sum<C: VecExt>(vec: C) -> Vec<C::T>
+where
+    C::T: Copy + Default + Add<Output = C::T>,
+{
+    vec.exclusive_prefix_sum()
+}
+
+/// See [`VecExt::histogram`]
+pub fn histogram<C: VecExt, F: FnMut(&C::T) -> usize>(vec: C, buckets: usize, f: F) -> Vec<usize> {
+    vec.histogram(buckets, f)
+}
+
+/// See [`VecExt::count_into`]
+pub fn count_into<C: VecExt, F: FnMut(&C::T) -> usize>(vec: C, counts: &mut [usize], f: F) {
+    vec.count_into(counts, f)
+}
+
+/// See [`VecExt::chunk_exact_map`]
+pub fn chunk_exact_map<C: VecExt, const N: usize, U, F, R>(vec: C, f: F, rem: R) -> Vec<U>
+where
+    F: FnMut([C::T; N]) -> U,
+    R: FnMut(Vec<C::T>) -> Option<U>,
+{
+    vec.chunk_exact_map(f, rem)
+}
+
+/// See [`VecExt::zip_fold`]
+pub fn zip_fold<C: VecExt, U, V, Acc, F: FnMut(&mut Acc, C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    acc: Acc,
+    f: F,
+) -> (Vec<V>, Acc) {
+    vec.zip_fold(other, acc, f)
+}
+
+/// See [`VecExt::group_adjacent_map`]
+pub fn group_adjacent_map<C: VecExt, K, U, F, G>(vec: C, key: F, fold: G) -> Vec<U>
+where
+    K: PartialEq,
+    F: FnMut(&C::T) -> K,
+    G: FnMut(K, Drain<'_, C::T>) -> U,
+{
+    vec.group_adjacent_map(key, fold)
+}
+
+/// See [`VecExt::map_recycle`]
+pub fn map_recycle<C: VecExt, U, F: FnMut(C::T) -> U>(
+    vec: C,
+    f: F,
+) -> (Vec<U>, Option<RecycledAlloc>) {
+    vec.map_recycle(f)
+}
+
+/// See [`VecExt::try_map_recycle`]
+pub fn try_map_recycle<C: VecExt, U, R: Try<Ok = U>, F: FnMut(C::T) -> R>(
+    vec: C,
+    f: F,
+) -> Result<(Vec<U>, Option<RecycledAlloc>), R::Error> {
+    vec.try_map_recycle(f)
+}
+
+/// See [`VecExt::zip_with_recycle`]
+pub fn zip_with_recycle<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> (Vec<V>, Option<RecycledAlloc>) {
+    vec.zip_with_recycle(other, f)
+}
+
+/// See [`VecExt::try_zip_with_recycle`]
+pub fn try_zip_with_recycle<C: VecExt, U, V, R: Try<Ok = V>, F: FnMut(C::T, U) -> R>(
+    vec: C,
+    other: Vec<U>,
+    f: F,
+) -> Result<(Vec<V>, Option<RecycledAlloc>), R::Error> {
+    vec.try_zip_with_recycle(other, f)
+}
+
+/// See [`VecExt::map_with_config`]
+pub fn map_with_config<C: VecExt, U, F: FnMut(C::T) -> U>(
+    vec: C,
+    config: ReuseConfig,
+    f: F,
+) -> Vec<U> {
+    vec.map_with_config(config, f)
+}
+
+/// See [`VecExt::try_map_with_config`]
+pub fn try_map_with_config<C: VecExt, U, R: Try<Ok = U>, F: FnMut(C::T) -> R>(
+    vec: C,
+    config: ReuseConfig,
+    f: F,
+) -> Result<Vec<U>, R::Error> {
+    vec.try_map_with_config(config, f)
+}
+
+/// See [`VecExt::zip_with_with_config`]
+pub fn zip_with_with_config<C: VecExt, U, V, F: FnMut(C::T, U) -> V>(
+    vec: C,
+    other: Vec<U>,
+    config: ReuseConfig,
+    f: F,
+) -> Vec<V> {
+    vec.zip_with_with_config(other, config, f)
+}
+
+/// See [`VecExt::try_zip_with_with_config`]
+pub fn try_zip_with_with_config<C: VecExt, U, V, R: Try<Ok = V>, F: FnMut(C::T, U) -> R>(
+    vec: C,
+    other: Vec<U>,
+    config: ReuseConfig,
+    f: F,
+) -> Result<Vec<V>, R::Error> {
+    vec.try_zip_with_with_config(other, config, f)
+}
+
+/// See [`VecExt::try_filter_map`]
+pub fn try_filter_map<C: VecExt, U, R: Try<Ok = Option<U>>, F: FnMut(C::T) -> R>(
+    vec: C,
+    f: F,
+) -> Result<Vec<U>, R::Error> {
+    vec.try_filter_map(f)
+}
+
+/// See [`VecExt::try_filter_map_with_config`]
+pub fn try_filter_map_with_config<C: VecExt, U, R: Try<Ok = Option<U>>, F: FnMut(C::T) -> R>(
+    vec: C,
+    config: ReuseConfig,
+    f: F,
+) -> Result<Vec<U>, R::Error> {
+    vec.try_filter_map_with_config(config, f)
+}
+
+/// See [`VecExt::flat_map_reuse`]
+pub fn flat_map_reuse<C: VecExt, U, I: IntoIterator<Item = U>, F: FnMut(C::T) -> I>(
+    vec: C,
+    f: F,
+) -> Vec<U> {
+    vec.flat_map_reuse(f)
+}
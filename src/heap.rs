@@ -0,0 +1,26 @@
+use std::collections::BinaryHeap;
+
+use super::VecExt;
+
+/// Extension methods for `BinaryHeap<T>`
+pub trait BinaryHeapExt: Sized {
+    /// The type that the `BinaryHeap<T>` stores
+    type T;
+
+    /// Map the elements of the heap in ascending order (the same order as
+    /// `BinaryHeap::into_sorted_vec`), will try and reuse the heap's backing
+    /// allocation if the allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused
+    fn into_sorted_map<U, F: FnMut(Self::T) -> U>(self, f: F) -> Vec<U>;
+}
+
+impl<T: Ord> BinaryHeapExt for BinaryHeap<T> {
+    type T = T;
+
+    fn into_sorted_map<U, F: FnMut(T) -> U>(self, f: F) -> Vec<U> {
+        // `into_sorted_vec` sorts the backing vector in place,
+        // so the allocation is still the heap's
+        self.into_sorted_vec().map(f)
+    }
+}
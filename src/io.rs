@@ -0,0 +1,123 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Streams bytes from a reader to a writer through a single reused buffer,
+/// transforming them in place along the way
+///
+/// The buffer is kept between calls, so a `BufTransform` can be used to
+/// process many streams without allocating again
+///
+/// ```rust
+/// use vec_utils::BufTransform;
+///
+/// let mut transform = BufTransform::new();
+/// let mut output = Vec::new();
+///
+/// transform.map(&b"hello"[..], &mut output, |b| b.to_ascii_uppercase()).unwrap();
+/// transform.filter_map(&b"a-b-c"[..], &mut output, |b| Some(b).filter(|&b| b != b'-')).unwrap();
+///
+/// assert_eq!(output, b"HELLOabc");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BufTransform {
+    buf: Vec<u8>,
+}
+
+impl BufTransform {
+    /// Create a transform with an 8 KiB buffer, the buffer isn't
+    /// allocated until the first transform
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a transform with a buffer of at least `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_vec(Vec::with_capacity(capacity))
+    }
+
+    /// Create a transform that uses the allocation of `buf` as it's buffer,
+    /// the contents of `buf` are discarded
+    pub fn from_vec(mut buf: Vec<u8>) -> Self {
+        buf.clear();
+        Self { buf }
+    }
+
+    /// The size of the buffer
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Take the buffer out of the transform
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// Map each byte read from `reader`, and write it to `writer`
+    ///
+    /// Returns the number of bytes written
+    pub fn map<R: Read, W: Write, F: FnMut(u8) -> u8>(
+        &mut self,
+        reader: R,
+        writer: W,
+        mut f: F,
+    ) -> io::Result<u64> {
+        self.filter_map(reader, writer, move |b| Some(f(b)))
+    }
+
+    /// Only write the bytes read from `reader` that match the predicate to `writer`
+    ///
+    /// Returns the number of bytes written
+    pub fn filter<R: Read, W: Write, F: FnMut(u8) -> bool>(
+        &mut self,
+        reader: R,
+        writer: W,
+        mut f: F,
+    ) -> io::Result<u64> {
+        self.filter_map(reader, writer, move |b| Some(b).filter(|&b| f(b)))
+    }
+
+    /// Map each byte read from `reader`, and write the `Some` values to `writer`
+    ///
+    /// Returns the number of bytes written
+    pub fn filter_map<R: Read, W: Write, F: FnMut(u8) -> Option<u8>>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        mut f: F,
+    ) -> io::Result<u64> {
+        if self.buf.capacity() == 0 {
+            self.buf.reserve_exact(DEFAULT_CAPACITY);
+        }
+
+        // the buffer is only zeroed once, after that it keeps it's length
+        let capacity = self.buf.capacity();
+        self.buf.resize(capacity, 0);
+
+        let mut total = 0;
+
+        loop {
+            let len = match reader.read(&mut self.buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            // the write position never passes the read position
+            let mut written = 0;
+
+            for i in 0..len {
+                if let Some(b) = f(self.buf[i]) {
+                    self.buf[written] = b;
+                    written += 1;
+                }
+            }
+
+            writer.write_all(&self.buf[..written])?;
+            total += written as u64;
+        }
+
+        Ok(total)
+    }
+}
@@ -0,0 +1,41 @@
+use super::raw::convert_alloc;
+use super::{RecycleBin, ScaleError};
+
+mod reuse;
+
+pub use reuse::*;
+
+/// Extension methods for all `Iterator`s
+pub trait CollectWith: Iterator + Sized {
+    /// Collect the iterator into a vector, reusing the allocation of `donor`
+    ///
+    /// The elements of `donor` are dropped, and it's allocation is used if
+    /// the alignments of `T` and `Self::Item` are the same, and the size of
+    /// the allocation in bytes is a multiple of the size of `Self::Item`.
+    /// If there are more items than fit in the donor allocation, it will grow
+    /// like any other `Vec`
+    ///
+    /// If the donor allocation can't be used, then the thread-local `RecycleBin`
+    /// is checked for a compatible allocation
+    fn collect_with<T>(self, donor: Vec<T>) -> Vec<Self::Item> {
+        let mut vec = convert_alloc(donor)
+            .ok()
+            .or_else(|| RecycleBin::take_local(self.size_hint().0))
+            .unwrap_or_default();
+        vec.extend(self);
+        vec
+    }
+
+    /// Collect the iterator into a vector, reusing the allocation of `donor`
+    ///
+    /// Unlike [`CollectWith::collect_with`], this returns an error describing
+    /// why the donor allocation can't be used, instead of falling back to a
+    /// new allocation. The elements of `donor` are dropped in either case
+    fn try_collect_with<T>(self, donor: Vec<T>) -> Result<Vec<Self::Item>, ScaleError> {
+        let mut vec = convert_alloc(donor)?;
+        vec.extend(self);
+        Ok(vec)
+    }
+}
+
+impl<I: Iterator> CollectWith for I {}
@@ -0,0 +1,1033 @@
+use std::alloc::Layout;
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::string::FromUtf8Error;
+
+use crate::raw::RawAllocGuard;
+use crate::{cleanup, reuse_config, trace, Drain, ReuseConfig, Try};
+
+mod pipeline;
+#[cfg(feature = "futures")]
+mod stream;
+
+pub use pipeline::*;
+#[cfg(feature = "futures")]
+pub use stream::*;
+
+/// The start of a [`ReuseIter`] pipeline, it yields the elements of a `Vec<T>`,
+/// and keeps track of it's allocation
+pub struct Source<T> {
+    // the start of the vec data segment
+    start: *mut T,
+
+    // the current position in the vec data segment
+    ptr: *mut T,
+
+    // the end of the initialized part of the vec data segment
+    end: *mut T,
+
+    // the capacity of the vec data segment
+    cap: usize,
+
+    drop_alloc: bool,
+    drop: PhantomData<T>,
+}
+
+impl<T> From<Vec<T>> for Source<T> {
+    fn from(vec: Vec<T>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+
+        let start = vec.as_mut_ptr();
+
+        // zero sized types use `end` as a counter
+        let end = if std::mem::size_of::<T>() == 0 {
+            (start as *mut u8).wrapping_add(vec.len()) as *mut T
+        } else {
+            start.wrapping_add(vec.len())
+        };
+
+        Self {
+            start,
+            ptr: start,
+            end,
+            cap: vec.capacity(),
+            drop_alloc: true,
+            drop: PhantomData,
+        }
+    }
+}
+
+impl<T> Source<T> {
+    fn remaining(&self) -> usize {
+        if std::mem::size_of::<T>() == 0 {
+            self.end as usize - self.ptr as usize
+        } else {
+            unsafe { self.end.offset_from(self.ptr) as usize }
+        }
+    }
+}
+
+impl<T> Iterator for Source<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.ptr == self.end {
+            return None;
+        }
+
+        unsafe {
+            if std::mem::size_of::<T>() == 0 {
+                self.end = (self.end as *mut u8).wrapping_sub(1) as *mut T;
+                Some(self.ptr.read())
+            } else {
+                let ptr = self.ptr;
+                self.ptr = self.ptr.add(1);
+                Some(ptr.read())
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining();
+        (len, Some(len))
+    }
+}
+
+impl<T> Drop for Source<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let _alloc = self
+                .drop_alloc
+                .then(|| RawAllocGuard::new(self.start, self.cap));
+
+            cleanup::drop_slice(self.ptr, self.remaining());
+        }
+    }
+}
+
+/// A stage of a [`ReuseIter`] pipeline
+///
+/// # Safety
+///
+/// Until the `Source` is exhausted, the number of items yielded by `next` must not
+/// exceed the number of elements consumed from the `Source`, so that the outputs
+/// can be written behind the read position
+pub unsafe trait Stage: Iterator {
+    /// The type of the elements of the source vector
+    type Source;
+
+    /// Get the source of the pipeline
+    fn source(&mut self) -> &mut Source<Self::Source>;
+
+    /// The expected length of the output, set by [`ReuseIter::with_capacity_hint`]
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        None
+    }
+}
+
+unsafe impl<T> Stage for Source<T> {
+    type Source = T;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<T> {
+        self
+    }
+}
+
+/// The stage created by [`ReuseIter::map`]
+pub struct Map<S, F> {
+    stage: S,
+    f: F,
+}
+
+impl<S: Stage, U, F: FnMut(S::Item) -> U> Iterator for Map<S, F> {
+    type Item = U;
+
+    #[inline]
+    fn next(&mut self) -> Option<U> {
+        self.stage.next().map(&mut self.f)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stage.size_hint()
+    }
+}
+
+unsafe impl<S: Stage, U, F: FnMut(S::Item) -> U> Stage for Map<S, F> {
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The stage created by [`ReuseIter::filter`]
+pub struct Filter<S, F> {
+    stage: S,
+    f: F,
+}
+
+impl<S: Stage, F: FnMut(&S::Item) -> bool> Iterator for Filter<S, F> {
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        let f = &mut self.f;
+        self.stage.find(move |x| f(x))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.stage.size_hint().1)
+    }
+}
+
+unsafe impl<S: Stage, F: FnMut(&S::Item) -> bool> Stage for Filter<S, F> {
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The stage created by [`ReuseIter::zip`]
+pub struct Zip<S, I> {
+    stage: S,
+    other: I,
+}
+
+impl<S: Stage, I: Iterator> Iterator for Zip<S, I> {
+    type Item = (S::Item, I::Item);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.stage.next()?;
+        let y = self.other.next()?;
+        Some((x, y))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lo, a_hi) = self.stage.size_hint();
+        let (b_lo, b_hi) = self.other.size_hint();
+
+        let hi = match (a_hi, b_hi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        (a_lo.min(b_lo), hi)
+    }
+}
+
+unsafe impl<S: Stage, I: Iterator> Stage for Zip<S, I> {
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The stage created by [`ReuseIter::zip_eq`]
+pub struct ZipEq<S, I> {
+    stage: S,
+    other: I,
+    len: usize,
+    done: bool,
+}
+
+impl<S: Stage, I: Iterator> Iterator for ZipEq<S, I> {
+    type Item = Result<(S::Item, I::Item), LengthMismatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (x, y) = match (self.stage.next(), self.other.next()) {
+            (Some(x), Some(y)) => (x, y),
+            (None, None) => {
+                self.done = true;
+                return None;
+            }
+            (x, y) => {
+                self.done = true;
+
+                let left = self.len + usize::from(x.is_some()) + self.stage.by_ref().count();
+                let right = self.len + usize::from(y.is_some()) + self.other.by_ref().count();
+
+                return Some(Err(LengthMismatch { left, right }));
+            }
+        };
+
+        self.len += 1;
+        Some(Ok((x, y)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+
+        let (a_lo, a_hi) = self.stage.size_hint();
+        let (b_lo, b_hi) = self.other.size_hint();
+
+        // if the lengths differ, then the error is yielded after the shorter side
+        let hi = match (a_hi, b_hi) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+
+        (a_lo.min(b_lo), hi)
+    }
+}
+
+unsafe impl<S: Stage, I: Iterator> Stage for ZipEq<S, I> {
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The error yielded by [`ReuseIter::zip_eq`] if the pipeline and the
+/// other iterator have different lengths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LengthMismatch {
+    /// The length of the pipeline
+    pub left: usize,
+    /// The length of the other iterator
+    pub right: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can't zip a pipeline of length {} with an iterator of length {}",
+            self.left, self.right
+        )
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
+
+/// The stage created by [`ReuseIter::map_err`] and [`ReuseIter::err_into`]
+pub struct MapErr<S, F> {
+    stage: S,
+    f: F,
+}
+
+impl<S: Stage, E, F: FnMut(<S::Item as Try>::Error) -> E> Iterator for MapErr<S, F>
+where
+    S::Item: Try,
+{
+    type Item = Result<<S::Item as Try>::Ok, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.stage.next()?;
+        Some(value.into_result().map_err(&mut self.f))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stage.size_hint()
+    }
+}
+
+unsafe impl<S: Stage, E, F: FnMut(<S::Item as Try>::Error) -> E> Stage for MapErr<S, F>
+where
+    S::Item: Try,
+{
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The stage created by [`ReuseIter::or_else`]
+pub struct OrElse<S, F> {
+    stage: S,
+    f: F,
+}
+
+impl<S: Stage, E, F> Iterator for OrElse<S, F>
+where
+    S::Item: Try,
+    F: FnMut(<S::Item as Try>::Error) -> Result<<S::Item as Try>::Ok, E>,
+{
+    type Item = Result<<S::Item as Try>::Ok, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.stage.next()?;
+        Some(value.into_result().or_else(&mut self.f))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stage.size_hint()
+    }
+}
+
+unsafe impl<S: Stage, E, F> Stage for OrElse<S, F>
+where
+    S::Item: Try,
+    F: FnMut(<S::Item as Try>::Error) -> Result<<S::Item as Try>::Ok, E>,
+{
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        self.stage.capacity_hint()
+    }
+}
+
+/// The stage created by [`ReuseIter::with_capacity_hint`]
+pub struct CapacityHint<S> {
+    stage: S,
+    hint: usize,
+}
+
+impl<S: Stage> Iterator for CapacityHint<S> {
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        self.stage.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stage.size_hint()
+    }
+}
+
+unsafe impl<S: Stage> Stage for CapacityHint<S> {
+    type Source = S::Source;
+
+    #[inline]
+    fn source(&mut self) -> &mut Source<S::Source> {
+        self.stage.source()
+    }
+
+    #[inline]
+    fn capacity_hint(&self) -> Option<usize> {
+        Some(self.hint)
+    }
+}
+
+/// An iterator over the elements of a vector that remembers the vector's allocation,
+/// created by [`VecExt::into_reuse_iter`](crate::VecExt::into_reuse_iter)
+///
+/// The `map`, `filter`, and `zip` combinators on this type keep track of the
+/// allocation, so that `collect_reuse` can write the output into it
+///
+/// This is also an `Iterator`, so a pipeline can end in a reduction like `sum`,
+/// `product`, `reduce`, or `max_by`, a search like `find`, `position`, or `any`,
+/// or in `for_each`, without building an output vector. The rest of the source
+/// elements and the allocation are freed when the pipeline is dropped. A search
+/// can also go through `by_ref`, and the rest of the pipeline collected afterwards
+///
+/// ```rust
+/// use vec_utils::VecExt;
+///
+/// let vec = vec![1.0f32, -2.0, 3.0];
+///
+/// let max = vec
+///     .into_reuse_iter()
+///     .map(f32::abs)
+///     .max_by(f32::total_cmp);
+///
+/// assert_eq!(max, Some(3.0));
+/// ```
+pub struct ReuseIter<S> {
+    stage: S,
+}
+
+impl<T> ReuseIter<Source<T>> {
+    /// Create a new pipeline from a vector
+    pub fn new(vec: Vec<T>) -> Self {
+        Self {
+            stage: Source::from(vec),
+        }
+    }
+
+    /// Split off the remaining elements from `mid` onwards, so that they can be
+    /// processed differently, without moving them out of the allocation
+    ///
+    /// The pipeline keeps the allocation, and the elements before `mid`. The tail
+    /// borrows the pipeline, so it must be done before the head is processed
+    ///
+    /// ```rust
+    /// use vec_utils::VecExt;
+    ///
+    /// let mut iter = vec![1, 2, 3, 4, 5].into_reuse_iter();
+    ///
+    /// let tail = iter.split_tail(3).map(|x| x * 100).sum::<i32>();
+    /// let head = iter.map(|x| x + tail).collect_reuse();
+    ///
+    /// assert_eq!(head, [901, 902, 903]);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `mid` is larger than the number of remaining elements
+    pub fn split_tail(&mut self, mid: usize) -> Drain<'_, T> {
+        let source = &mut self.stage;
+        let len = source.remaining();
+
+        assert!(
+            mid <= len,
+            "the split point {} is out of bounds for {} remaining elements",
+            mid,
+            len
+        );
+
+        // zero sized types use `end` as a counter
+        source.end = if std::mem::size_of::<T>() == 0 {
+            (source.ptr as *mut u8).wrapping_add(mid) as *mut T
+        } else {
+            source.ptr.wrapping_add(mid)
+        };
+
+        // the tail is no longer part of the source, so it's owned by the drain
+        unsafe { Drain::new(source.ptr, mid, len) }
+    }
+}
+
+impl<S: Stage> Iterator for ReuseIter<S> {
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        self.stage.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stage.size_hint()
+    }
+}
+
+impl<S: Stage> ReuseIter<S> {
+    /// Map each item of the pipeline
+    #[allow(clippy::should_implement_trait)]
+    pub fn map<U, F: FnMut(S::Item) -> U>(self, f: F) -> ReuseIter<Map<S, F>> {
+        ReuseIter {
+            stage: Map {
+                stage: self.stage,
+                f,
+            },
+        }
+    }
+
+    /// Only keep the items of the pipeline that match the predicate
+    pub fn filter<F: FnMut(&S::Item) -> bool>(self, f: F) -> ReuseIter<Filter<S, F>> {
+        ReuseIter {
+            stage: Filter {
+                stage: self.stage,
+                f,
+            },
+        }
+    }
+
+    /// Zip the pipeline with another iterator, only the allocation of
+    /// this pipeline is tracked
+    pub fn zip<I: IntoIterator>(self, other: I) -> ReuseIter<Zip<S, I::IntoIter>> {
+        ReuseIter {
+            stage: Zip {
+                stage: self.stage,
+                other: other.into_iter(),
+            },
+        }
+    }
+
+    /// Tell `collect_reuse` how many items the pipeline is expected to yield,
+    /// instead of using the length of the source vector
+    ///
+    /// If the source allocation is reused and is larger than the hint, it's shrunk
+    /// to fit the hint (or the output, if it's longer) once the pipeline is done.
+    /// Otherwise the new allocation is created with enough capacity for the hint.
+    /// The latest hint in the pipeline is used
+    pub fn with_capacity_hint(self, hint: usize) -> ReuseIter<CapacityHint<S>> {
+        ReuseIter {
+            stage: CapacityHint {
+                stage: self.stage,
+                hint,
+            },
+        }
+    }
+
+    /// Zip the pipeline with another iterator of the same length, only the
+    /// allocation of this pipeline is tracked
+    ///
+    /// If the lengths differ, then the pairs are followed by a single [`LengthMismatch`]
+    /// error, the rest of the longer side is consumed to find its length
+    pub fn zip_eq<I: IntoIterator>(self, other: I) -> ReuseIter<ZipEq<S, I::IntoIter>> {
+        ReuseIter {
+            stage: ZipEq {
+                stage: self.stage,
+                other: other.into_iter(),
+                len: 0,
+                done: false,
+            },
+        }
+    }
+
+    /// Map the error of each fallible item of the pipeline, so that
+    /// [`ReuseIter::try_collect_reuse`] returns a different error type
+    pub fn map_err<E, F>(self, f: F) -> ReuseIter<MapErr<S, F>>
+    where
+        S::Item: Try,
+        F: FnMut(<S::Item as Try>::Error) -> E,
+    {
+        ReuseIter {
+            stage: MapErr {
+                stage: self.stage,
+                f,
+            },
+        }
+    }
+
+    /// Convert the error of each fallible item of the pipeline with `From`
+    #[allow(clippy::type_complexity)]
+    pub fn err_into<E>(self) -> ReuseIter<MapErr<S, fn(<S::Item as Try>::Error) -> E>>
+    where
+        S::Item: Try,
+        E: From<<S::Item as Try>::Error>,
+    {
+        self.map_err(E::from)
+    }
+
+    /// Try to recover from the error of each fallible item of the pipeline,
+    /// `f` can return a replacement item, or an error to keep the item failed
+    ///
+    /// To skip the items that can't be recovered, follow this with
+    /// `filter(Result::is_ok)`
+    pub fn or_else<E, F>(self, f: F) -> ReuseIter<OrElse<S, F>>
+    where
+        S::Item: Try,
+        F: FnMut(<S::Item as Try>::Error) -> Result<<S::Item as Try>::Ok, E>,
+    {
+        ReuseIter {
+            stage: OrElse {
+                stage: self.stage,
+                f,
+            },
+        }
+    }
+
+    /// Run a fallible function on each item of the pipeline, stopping at the first error
+    ///
+    /// Use `for_each` for infallible functions. In either case, the rest of the
+    /// source elements and the allocation are freed once the pipeline is done
+    pub fn try_for_each<R: Try<Ok = ()>, F: FnMut(S::Item) -> R>(
+        mut self,
+        mut f: F,
+    ) -> Result<(), R::Error> {
+        for value in &mut self {
+            f(value).into_result()?;
+        }
+
+        Ok(())
+    }
+
+    /// Collect the pipeline into a vector, will try and reuse the allocation
+    /// of the source vector if the allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<S::Item>::new()`
+    /// then the allocation will be reused
+    pub fn collect_reuse(self) -> Vec<S::Item> {
+        match self.map(Ok::<_, Infallible>).try_collect_reuse() {
+            Ok(vec) => vec,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Collect a pipeline of fallible items into a vector, stopping at the first error
+    ///
+    /// The allocation of the source vector is reused under the same conditions
+    /// as [`ReuseIter::collect_reuse`], with the successful type of the items.
+    /// If there is an error, then all of the outputs and the rest of the source
+    /// elements are dropped
+    pub fn try_collect_reuse<U>(self) -> Result<Vec<U>, <S::Item as Try>::Error>
+    where
+        S::Item: Try<Ok = U>,
+    {
+        self.try_collect_reuse_with_config(reuse_config())
+    }
+
+    /// The same as [`ReuseIter::collect_reuse`], but the allocation is reused
+    /// according to `config` instead of the process-wide [`ReuseConfig`]
+    pub fn collect_reuse_with_config(self, config: ReuseConfig) -> Vec<S::Item> {
+        match self
+            .map(Ok::<_, Infallible>)
+            .try_collect_reuse_with_config(config)
+        {
+            Ok(vec) => vec,
+            Err(x) => match x {},
+        }
+    }
+
+    /// The same as [`ReuseIter::try_collect_reuse`], but the allocation is reused
+    /// according to `config` instead of the process-wide [`ReuseConfig`]
+    pub fn try_collect_reuse_with_config<U>(
+        mut self,
+        config: ReuseConfig,
+    ) -> Result<Vec<U>, <S::Item as Try>::Error>
+    where
+        S::Item: Try<Ok = U>,
+    {
+        let hint = self.stage.capacity_hint();
+
+        let source = self.stage.source();
+        let len = hint.unwrap_or_else(|| source.remaining());
+        let _span = trace::collect_reuse::<S::Source, U>(len, source.cap, hint, &config);
+
+        let reuse =
+            Layout::new::<S::Source>() == Layout::new::<U>() && config.reuses(len, source.cap);
+
+        if !reuse {
+            let capacity = hint.unwrap_or_else(|| self.size_hint().0);
+            let iter = Iterator::map(self, Try::into_result);
+
+            return config.try_collect(iter, capacity);
+        }
+
+        let mut writer = Writer::<U> {
+            start: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+
+        // `stage` must be dropped before `writer`, because the rest of
+        // the source elements live in `writer`'s allocation
+        let mut stage = self.stage;
+
+        let source = stage.source();
+        source.drop_alloc = false;
+        writer.start = source.start as *mut U;
+        writer.cap = source.cap;
+
+        for value in stage.by_ref() {
+            let value = value.into_result()?;
+
+            // every item consumes at least one element of the source
+            // so this can't overwrite any element that wasn't read yet
+            unsafe {
+                writer.push(value);
+            }
+        }
+
+        // drop any remaining source elements
+        drop(stage);
+
+        let writer = ManuallyDrop::new(writer);
+
+        let mut vec = unsafe { Vec::from_raw_parts(writer.start, writer.len, writer.cap) };
+
+        if let Some(hint) = hint {
+            vec.shrink_to(hint);
+        }
+
+        Ok(config.finish(vec))
+    }
+}
+
+impl<S: Stage> ReuseIter<S> {
+    /// Split a pipeline of results into the `Ok` values and the errors
+    ///
+    /// The allocation of the source vector is reused for the values if the
+    /// allocation layouts of `T` and the source elements match, otherwise for the
+    /// errors if the layouts of `E` and the source elements match. The other
+    /// side is collected into a new vector
+    ///
+    /// The allocation is only reused if the process-wide [`ReuseConfig`] allows it
+    pub fn partition_result<T, E>(mut self) -> (Vec<T>, Vec<E>)
+    where
+        S: Stage<Item = Result<T, E>>,
+    {
+        let config = reuse_config();
+        let hint = self.stage.capacity_hint();
+
+        let source = self.stage.source();
+        let reuse = config.reuses(hint.unwrap_or_else(|| source.remaining()), source.cap);
+
+        if reuse && Layout::new::<S::Source>() == Layout::new::<T>() {
+            let (values, errors) = self.partition_reuse();
+            (config.finish(values), errors)
+        } else if reuse && Layout::new::<S::Source>() == Layout::new::<E>() {
+            let (errors, values) = self
+                .map(|x| match x {
+                    Ok(value) => Err(value),
+                    Err(error) => Ok(error),
+                })
+                .partition_reuse();
+
+            (values, config.finish(errors))
+        } else {
+            let mut values = config.allocate(hint.unwrap_or(0));
+            let mut errors = Vec::new();
+
+            for value in self {
+                match value {
+                    Ok(value) => values.push(value),
+                    Err(error) => errors.push(error),
+                }
+            }
+
+            (values, errors)
+        }
+    }
+
+    // the `Ok` values are written into the source allocation, so the
+    // layout of `T` must match the layout of the source elements
+    fn partition_reuse<T, E>(self) -> (Vec<T>, Vec<E>)
+    where
+        S: Stage<Item = Result<T, E>>,
+    {
+        debug_assert_eq!(Layout::new::<S::Source>(), Layout::new::<T>());
+
+        let hint = self.stage.capacity_hint();
+
+        let mut errors = Vec::new();
+
+        let mut writer = Writer::<T> {
+            start: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+
+        // `stage` must be dropped before `writer`, because the rest of
+        // the source elements live in `writer`'s allocation
+        let mut stage = self.stage;
+
+        let source = stage.source();
+        source.drop_alloc = false;
+        writer.start = source.start as *mut T;
+        writer.cap = source.cap;
+
+        for value in stage.by_ref() {
+            match value {
+                // every item consumes at least one element of the source
+                // so this can't overwrite any element that wasn't read yet
+                Ok(value) => unsafe { writer.push(value) },
+                Err(error) => errors.push(error),
+            }
+        }
+
+        // drop any remaining source elements
+        drop(stage);
+
+        let writer = ManuallyDrop::new(writer);
+
+        let mut values = unsafe { Vec::from_raw_parts(writer.start, writer.len, writer.cap) };
+
+        if let Some(hint) = hint {
+            values.shrink_to(hint);
+        }
+
+        (values, errors)
+    }
+}
+
+impl<S: Stage<Item = char>> ReuseIter<S> {
+    /// Collect a pipeline of `char`s into a string, will try and reuse the allocation
+    /// of the source vector if the source elements have an alignment of 1
+    ///
+    /// The characters are written as UTF-8 while they fit behind the read position.
+    /// If a character doesn't fit, the string so far is moved into a new allocation
+    ///
+    /// The allocation is only reused if the process-wide [`ReuseConfig`] allows it
+    pub fn collect_string(mut self) -> String {
+        let config = reuse_config();
+        let hint = self.stage.capacity_hint();
+        let size = std::mem::size_of::<S::Source>();
+
+        let source = self.stage.source();
+        let reuse = config.reuses(hint.unwrap_or_else(|| source.remaining()), source.cap);
+
+        let mut string = if std::mem::align_of::<S::Source>() != 1 || size == 0 || !reuse {
+            let capacity = hint.unwrap_or(self.size_hint().0);
+
+            // the vector is empty, so it's valid UTF-8
+            let mut string = unsafe { String::from_utf8_unchecked(config.allocate(capacity)) };
+            string.extend(self);
+            string
+        } else {
+            self.write_string()
+        };
+
+        if config.shrink_on_finish {
+            string.shrink_to_fit();
+        }
+
+        string
+    }
+
+    // the characters are written into the source allocation, so the
+    // alignment of the source elements must be 1
+    fn write_string(self) -> String {
+        let hint = self.stage.capacity_hint();
+        let size = std::mem::size_of::<S::Source>();
+
+        debug_assert_eq!(std::mem::align_of::<S::Source>(), 1);
+
+        let mut writer = Writer::<u8> {
+            start: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+
+        // `stage` must be dropped before `writer`, because the rest of
+        // the source elements live in `writer`'s allocation
+        let mut stage = self.stage;
+
+        let source = stage.source();
+        source.drop_alloc = false;
+        writer.start = source.start as *mut u8;
+        writer.cap = source.cap * size;
+
+        let mut spill = None::<String>;
+
+        while let Some(c) = stage.next() {
+            if let Some(spill) = &mut spill {
+                spill.push(c);
+                continue;
+            }
+
+            let mut buf = [0; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+
+            let source = stage.source();
+            let read = source.ptr as usize - source.start as usize;
+
+            if writer.len + bytes.len() <= read || source.remaining() == 0 {
+                for &byte in bytes {
+                    // only read bytes are overwritten, unless the source is exhausted
+                    unsafe { writer.push(byte) }
+                }
+            } else {
+                let mut string = String::with_capacity(writer.len + source.remaining() * size);
+
+                // the writer only holds whole characters
+                unsafe {
+                    let written = std::slice::from_raw_parts(writer.start, writer.len);
+                    string.push_str(std::str::from_utf8_unchecked(written));
+                }
+
+                string.push(c);
+                spill = Some(string);
+            }
+        }
+
+        // drop any remaining source elements
+        drop(stage);
+
+        if let Some(spill) = spill {
+            return spill;
+        }
+
+        let writer = ManuallyDrop::new(writer);
+
+        // the writer only holds whole characters
+        let mut string = unsafe {
+            String::from_utf8_unchecked(Vec::from_raw_parts(writer.start, writer.len, writer.cap))
+        };
+
+        if let Some(hint) = hint {
+            string.shrink_to(hint);
+        }
+
+        string
+    }
+}
+
+impl<S: Stage<Item = u8>> ReuseIter<S> {
+    /// Collect a pipeline of bytes into a string, if they are valid UTF-8
+    ///
+    /// The allocation is reused in the same way as [`ReuseIter::collect_reuse`],
+    /// and the bytes are returned in the error if they aren't valid UTF-8
+    pub fn try_collect_string(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.collect_reuse())
+    }
+}
+
+// owns the output allocation, and the initialized outputs
+struct Writer<T> {
+    start: *mut T,
+    len: usize,
+    cap: usize,
+}
+
+impl<T> Writer<T> {
+    /// # Safety
+    ///
+    /// The next slot must not hold an unread element of the source
+    pub(super) unsafe fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            // the items of a stage only outnumber the consumed source elements
+            // once the source is exhausted, so nothing else is in the allocation
+            let mut vec = ManuallyDrop::new(Vec::from_raw_parts(self.start, self.len, self.cap));
+            vec.reserve(1);
+            self.start = vec.as_mut_ptr();
+            self.cap = vec.capacity();
+        }
+
+        self.start.add(self.len).write(value);
+        self.len += 1;
+    }
+}
+
+impl<T> Drop for Writer<T> {
+    fn drop(&mut self) {
+        if !self.start.is_null() {
+            unsafe {
+                Vec::from_raw_parts(self.start, self.len, self.cap);
+            }
+        }
+    }
+}
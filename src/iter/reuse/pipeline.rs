@@ -0,0 +1,153 @@
+use super::{ReuseIter, Source, Stage};
+
+enum Step<U> {
+    Yield(U),
+    Skip,
+    Stop,
+}
+
+/// A pipeline whose stages are chosen at runtime, the stages are boxed
+/// closures that are fused together into a single pass over the input
+///
+/// Like [`ReuseIter::collect_reuse`], the allocation of the input is reused
+/// if the allocation layouts of `T` and `U` match
+///
+/// ```rust
+/// use vec_utils::DynPipeline;
+///
+/// let (scale, skip_odd) = (10, true);
+///
+/// let mut pipeline = DynPipeline::new().map(move |x: u32| x * scale);
+///
+/// if skip_odd {
+///     pipeline = pipeline.filter(|x| x % 20 == 0);
+/// }
+///
+/// assert_eq!(pipeline.run(vec![1, 2, 3, 4]), [20, 40]);
+/// assert_eq!(pipeline.run(vec![6, 7]), [60]);
+/// ```
+pub struct DynPipeline<'a, T, U> {
+    f: Box<dyn FnMut(T) -> Step<U> + 'a>,
+}
+
+impl<'a, T: 'a> DynPipeline<'a, T, T> {
+    /// Create a pipeline that yields the input as is
+    pub fn new() -> Self {
+        Self {
+            f: Box::new(Step::Yield),
+        }
+    }
+}
+
+impl<'a, T: 'a> Default for DynPipeline<'a, T, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: 'a, U: 'a> DynPipeline<'a, T, U> {
+    /// Map each item of the pipeline
+    pub fn map<V, F: FnMut(U) -> V + 'a>(self, mut g: F) -> DynPipeline<'a, T, V> {
+        let mut f = self.f;
+
+        DynPipeline {
+            f: Box::new(move |x| match f(x) {
+                Step::Yield(y) => Step::Yield(g(y)),
+                Step::Skip => Step::Skip,
+                Step::Stop => Step::Stop,
+            }),
+        }
+    }
+
+    /// Only keep the items of the pipeline that match the predicate
+    pub fn filter<F: FnMut(&U) -> bool + 'a>(self, mut g: F) -> Self {
+        let mut f = self.f;
+
+        DynPipeline {
+            f: Box::new(move |x| match f(x) {
+                Step::Yield(y) if g(&y) => Step::Yield(y),
+                Step::Yield(_) | Step::Skip => Step::Skip,
+                Step::Stop => Step::Stop,
+            }),
+        }
+    }
+
+    /// Zip the pipeline with another iterator, the pipeline stops once
+    /// the iterator is exhausted
+    ///
+    /// The iterator is shared between runs, so each run continues
+    /// where the previous run left off
+    pub fn zip<I: IntoIterator>(self, other: I) -> DynPipeline<'a, T, (U, I::Item)>
+    where
+        I::IntoIter: 'a,
+    {
+        let mut f = self.f;
+        let mut other = other.into_iter();
+
+        DynPipeline {
+            f: Box::new(move |x| match f(x) {
+                Step::Yield(y) => match other.next() {
+                    Some(z) => Step::Yield((y, z)),
+                    None => Step::Stop,
+                },
+                Step::Skip => Step::Skip,
+                Step::Stop => Step::Stop,
+            }),
+        }
+    }
+
+    /// Run the pipeline over a vector
+    pub fn run(&mut self, vec: Vec<T>) -> Vec<U> {
+        ReuseIter {
+            stage: DynStage {
+                source: Source::from(vec),
+                f: &mut *self.f,
+                done: false,
+            },
+        }
+        .collect_reuse()
+    }
+}
+
+struct DynStage<'p, T, U> {
+    source: Source<T>,
+    f: &'p mut dyn FnMut(T) -> Step<U>,
+    done: bool,
+}
+
+impl<T, U> Iterator for DynStage<'_, T, U> {
+    type Item = U;
+
+    fn next(&mut self) -> Option<U> {
+        if self.done {
+            return None;
+        }
+
+        for x in &mut self.source {
+            match (self.f)(x) {
+                Step::Yield(y) => return Some(y),
+                Step::Skip => (),
+                Step::Stop => break,
+            }
+        }
+
+        self.done = true;
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, self.source.size_hint().1)
+        }
+    }
+}
+
+unsafe impl<T, U> Stage for DynStage<'_, T, U> {
+    type Source = T;
+
+    fn source(&mut self) -> &mut Source<T> {
+        &mut self.source
+    }
+}
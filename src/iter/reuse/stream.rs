@@ -0,0 +1,155 @@
+use std::alloc::Layout;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+
+use ::futures_core::Stream;
+
+use super::{ReuseIter, Stage, Writer};
+use crate::RecycleBin;
+
+/// An async [`ReuseIter`] pipeline, created by [`ReuseIter::then`]
+///
+/// This implements `Stream` from the `futures-core` crate
+pub struct ReuseStream<S, F, Fut> {
+    // the element that is currently being processed is owned by this future
+    pending: Option<Fut>,
+    stage: S,
+    f: F,
+}
+
+impl<S: Stage> ReuseIter<S> {
+    /// Process each item of the pipeline with an async function, the items are
+    /// processed one at a time, in order
+    ///
+    /// ```rust
+    /// use vec_utils::VecExt;
+    ///
+    /// async fn double(v: Vec<u32>) -> Vec<u32> {
+    ///     v.into_reuse_iter().then(|x| async move { x * 2 }).collect_reuse().await
+    /// }
+    /// ```
+    pub fn then<Fut: Future, F: FnMut(S::Item) -> Fut>(self, f: F) -> ReuseStream<S, F, Fut> {
+        ReuseStream {
+            pending: None,
+            stage: self.stage,
+            f,
+        }
+    }
+}
+
+impl<S: Stage, F: FnMut(S::Item) -> Fut, Fut: Future> ReuseStream<S, F, Fut> {
+    /// Attempt to get the next output of the pipeline,
+    /// returns `None` once the pipeline is exhausted
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Fut::Output>> {
+        // only `pending` is structurally pinned, and it is never moved out of
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if this.pending.is_none() {
+            match this.stage.next() {
+                Some(value) => this.pending = Some((this.f)(value)),
+                None => return Poll::Ready(None),
+            }
+        }
+
+        let pending = this.pending.as_mut().unwrap();
+
+        match unsafe { Pin::new_unchecked(pending) }.poll(cx) {
+            Poll::Ready(value) => {
+                this.pending = None;
+                Poll::Ready(Some(value))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Get the next output of the pipeline, returns `None` once the pipeline is exhausted
+    pub async fn next(mut self: Pin<&mut Self>) -> Option<Fut::Output> {
+        std::future::poll_fn(move |cx| self.as_mut().poll_next(cx)).await
+    }
+
+    /// The bounds on the number of remaining outputs
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = usize::from(self.pending.is_some());
+        let (low, high) = self.stage.size_hint();
+
+        (
+            low.saturating_add(pending),
+            high.and_then(|high| high.checked_add(pending)),
+        )
+    }
+
+    /// Collect the pipeline into a vector, will try and reuse the allocation
+    /// of the source vector if the allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<Fut::Output>::new()`
+    /// then the allocation will be reused
+    ///
+    /// If this future is dropped before it completes, the outputs that were already
+    /// produced and the rest of the source elements are dropped
+    pub async fn collect_reuse(self) -> Vec<Fut::Output> {
+        let hint = self.stage.capacity_hint();
+
+        if Layout::new::<S::Source>() != Layout::new::<Fut::Output>() {
+            let min_len = hint.unwrap_or(self.size_hint().0);
+            let mut output = RecycleBin::take_local(min_len)
+                .unwrap_or_else(|| Vec::with_capacity(hint.unwrap_or(0)));
+            let mut stream = pin!(self);
+
+            while let Some(value) = stream.as_mut().next().await {
+                output.push(value);
+            }
+
+            return output;
+        }
+
+        let mut writer = Writer::<Fut::Output> {
+            start: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+
+        let mut stream = self;
+
+        let source = stream.stage.source();
+        source.drop_alloc = false;
+        writer.start = source.start as *mut Fut::Output;
+        writer.cap = source.cap;
+
+        {
+            // `stream` must be dropped before `writer`, because the rest of
+            // the source elements live in `writer`'s allocation
+            let mut stream = pin!(stream);
+
+            while let Some(value) = stream.as_mut().next().await {
+                // every item consumes at least one element of the source
+                // so this can't overwrite any element that wasn't read yet
+                unsafe {
+                    writer.push(value);
+                }
+            }
+        }
+
+        let writer = ManuallyDrop::new(writer);
+
+        let mut vec = unsafe { Vec::from_raw_parts(writer.start, writer.len, writer.cap) };
+
+        if let Some(hint) = hint {
+            vec.shrink_to(hint);
+        }
+
+        vec
+    }
+}
+
+impl<S: Stage, F: FnMut(S::Item) -> Fut, Fut: Future> Stream for ReuseStream<S, F, Fut> {
+    type Item = Fut::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        ReuseStream::poll_next(self, cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        ReuseStream::size_hint(self)
+    }
+}
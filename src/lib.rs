@@ -1,4 +1,5 @@
 #![forbid(missing_docs)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 /*!
 # vec-utils
@@ -45,7 +46,7 @@ fn to_bits_no_nans(v: Vec<f32>) -> Result<Vec<u32>, &'static str> {
 }
 ```
 
-You can use as many input vectors as you want, just put them all inside the input tuple. Note that the second argument is not a closure, but syntax that looks like a closure, i.e. you can't make a closure before-hand and pass it as the second argument. Also, you can't use general patterns in the "closure"'s arguments, only identifiers are allowed. You can specify if you want a move closure by adding the move keyword in from of the "closure".
+You can use as many input vectors as you want, just put them all inside the input tuple. Note that the second argument is not a closure, but syntax that looks like a closure, i.e. you can't make a closure before-hand and pass it as the second argument. The "closure"'s arguments can be any irrefutable pattern, like `|(a, b), c|`, but or-patterns are not allowed. You can specify if you want a move closure by adding the move keyword in from of the "closure".
 
 ```rust
 use vec_utils::zip_with;
@@ -110,8 +111,8 @@ macro_rules! defer {
 /// ```
 /// You can use as many input vectors as you want, just put them all inside the input tuple.
 /// Note that the second argument is not a closure, but syntax that looks like a closure,
-/// i.e. you can't make a closure before-hand and pass it as the second argument. Also, you can't
-/// use general patterns in the "closure"'s arguments, only identifiers are allowed.
+/// i.e. you can't make a closure before-hand and pass it as the second argument. The "closure"'s
+/// arguments can be any irrefutable pattern, like `|(a, b), c|`, but or-patterns are not allowed.
 /// You can specify if you want a move closure by adding the move keyword in from of the "closure".
 ///
 /// ```rust
@@ -121,23 +122,122 @@ macro_rules! defer {
 ///     zip_with!(a, move |a| a + b)
 /// }
 /// ```
+///
+/// You can also declare the return type of the "closure", which makes the body behave
+/// like a try-block, so `?` converts errors into the declared error type with `From`
+///
+/// ```rust
+/// use vec_utils::try_zip_with;
+///
+/// #[derive(Debug)]
+/// enum Error {
+///     Parse(std::num::ParseIntError),
+///     Overflow,
+/// }
+///
+/// impl From<std::num::ParseIntError> for Error {
+///     fn from(e: std::num::ParseIntError) -> Self {
+///         Error::Parse(e)
+///     }
+/// }
+///
+/// fn add(a: Vec<&str>, b: Vec<u8>) -> Result<Vec<u8>, Error> {
+///     try_zip_with!((a, b), |a, b| -> Result<u8, Error> {
+///         a.parse::<u8>()?.checked_add(b).ok_or(Error::Overflow)
+///     })
+/// }
+///
+/// assert_eq!(add(vec!["1", "2"], vec![3, 4]).unwrap(), [4, 6]);
+/// ```
 #[macro_export]
 macro_rules! try_zip_with {
-    ($vec:expr, $($move:ident)? |$($i:ident),+ $(,)?| $($work:tt)*) => {{
+    ($vec:expr, $($move:ident)? |$($p:pat),+ $(,)?| -> $ret:ty { $($body:tt)* }) => {
+        $crate::bind_vecs!(try_zip_with_impl [] [$($p),+] ($vec) [$($p),+] ($($move)?) -> $ret { $($body)* })
+    };
+    ($vec:expr, $($move:ident)? |$($p:pat),+ $(,)?| $($work:tt)*) => {
+        $crate::bind_vecs!(try_zip_with_impl [] [$($p),+] ($vec) [$($p),+] ($($move)?) $($work)*)
+    };
+}
+
+/// Creates a fresh identifier for each vector, because the "closure"'s
+/// arguments may be patterns, which can't be used to name the vectors
+#[doc(hidden)]
+#[macro_export]
+macro_rules! bind_vecs {
+    ($impl:ident [$($v:ident)*] [$p:pat $(, $rest:pat)*] $($tail:tt)*) => {
+        $crate::bind_vecs!($impl [$($v)* vec] [$($rest),*] $($tail)*)
+    };
+    ($impl:ident [$($v:ident)*] [] ($vec:expr) [$($p:pat),+] ($($move:ident)?) $($work:tt)*) => {{
         #[allow(unused_parens)]
-        let ($($i),*) = $vec;
+        let ($($v),*) = $vec;
 
-        $crate::try_zip_with_impl(
-            $crate::list!(WRAP $($i),*),
-            $($move)? |$crate::list!(PLACE $($i),*)| $($work)*
+        $crate::$impl(
+            $crate::list!(WRAP $($v),*),
+            $($move)? |$crate::list!(PLACE $($p),*)| $($work)*
         )
     }};
 }
 
+/// Zip the vectors, and rebind the first vector to the result
+///
+/// Unlike `zip_with`, this always reuses the allocation of the first vector,
+/// so the output must have the same layout as the elements of the first vector.
+/// This is checked at compile time
+///
+/// ```rust
+/// use vec_utils::zip_assign;
+///
+/// let acc = vec![1.0f32, 2.0, 3.0];
+/// let x = vec![1.0f32, 1.0, 1.0];
+/// let y = vec![2.0f32, 0.0, -1.0];
+///
+/// let ptr = acc.as_ptr() as usize;
+///
+/// zip_assign!((acc, x, y), |acc, x, y| (acc + x * y).to_bits());
+///
+/// assert_eq!(acc, [3.0f32.to_bits(), 2.0f32.to_bits(), 2.0f32.to_bits()]);
+/// assert_eq!(acc.as_ptr() as usize, ptr);
+/// ```
+///
+/// ```rust,compile_fail
+/// use vec_utils::zip_assign;
+///
+/// let acc = vec![1u8, 2, 3];
+///
+/// // `u32` doesn't have the same layout as `u8`
+/// zip_assign!(acc, |x| u32::from(x));
+/// ```
+#[macro_export]
+macro_rules! zip_assign {
+    (($dst:ident $(, $src:expr)* $(,)?), $($move:ident)? |$($p:pat),+ $(,)?| $($work:tt)*) => {
+        $crate::zip_assign!(@assign $dst ($dst $(, $src)*), $($move)? |$($p),+| $($work)*);
+    };
+    ($dst:ident, $($move:ident)? |$($p:pat),+ $(,)?| $($work:tt)*) => {
+        $crate::zip_assign!(@assign $dst ($dst), $($move)? |$($p),+| $($work)*);
+    };
+    (@assign $dst:ident $vec:expr, $($move:ident)? |$($p:pat),+| $($work:tt)*) => {
+        let $dst = {
+            let check = $crate::LayoutCheck::new(&$dst);
+
+            let output = match $crate::bind_vecs!(
+                try_zip_assign_impl [] [$($p),+] ($vec) [$($p),+] ($($move)?)
+                Ok::<_, std::convert::Infallible>($($work)*)
+            ) {
+                Ok(x) => x,
+                Err(x) => match x {}
+            };
+
+            check.verify(&output);
+
+            output
+        };
+    };
+}
+
 /// A wrapper around `try_zip_with` for infallible mapping
 #[macro_export]
 macro_rules! zip_with {
-    ($vec:expr, $($move:ident)? |$($i:ident),+ $(,)?| $($work:tt)*) => {
+    ($vec:expr, $($move:ident)? |$($i:pat),+ $(,)?| $($work:tt)*) => {
         match $crate::try_zip_with!(
             $vec, $($move)? |$($i),+|
             Ok::<_, std::convert::Infallible>($($work)*)
@@ -154,11 +254,14 @@ macro_rules! list {
     (WRAP $e:ident) => {
         ($e,)
     };
-    (PLACE $e:ident) => {
+    (PLACE $e:pat) => {
         $e
     };
-    ($wrap:ident $e:ident $(, $rest:ident)* $(,)?) => {
-        ($e, $crate::list!($wrap $($rest),*))
+    (WRAP $e:ident $(, $rest:ident)* $(,)?) => {
+        ($e, $crate::list!(WRAP $($rest),*))
+    };
+    (PLACE $e:pat $(, $rest:pat)* $(,)?) => {
+        ($e, $crate::list!(PLACE $($rest),*))
     };
 }
 
@@ -170,10 +273,71 @@ impl<F: FnOnce()> Drop for OnDrop<F> {
     }
 }
 
+mod array;
 mod boxed;
+#[cfg(feature = "bumpalo")]
+mod bumpalo;
+#[cfg(feature = "bytemuck")]
+mod bytemuck;
+#[cfg(feature = "bytes")]
+mod bytes;
+mod cleanup;
+mod columns;
+mod config;
+mod cow;
+mod faults;
+pub mod fns;
+mod heap;
+mod io;
+mod iter;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+pub mod ops;
+mod plan;
+pub mod prelude;
+mod raw;
+#[cfg(feature = "rayon")]
+mod rayon;
+mod recycle;
+#[cfg(feature = "serde")]
+mod serde;
+mod sort;
+mod string;
+#[cfg(feature = "thin-vec")]
+mod thin_vec;
+mod trace;
 mod r#try;
 mod vec;
 
+pub use self::array::*;
 pub use self::boxed::*;
+#[cfg(feature = "bumpalo")]
+pub use self::bumpalo::*;
+#[cfg(feature = "bytemuck")]
+pub use self::bytemuck::*;
+#[cfg(feature = "bytes")]
+pub use self::bytes::*;
+pub use self::cleanup::*;
+pub use self::columns::*;
+pub use self::config::*;
+pub use self::cow::*;
+#[cfg(feature = "test-faults")]
+pub use self::faults::{Faults, InjectedFault};
+pub use self::heap::*;
+pub use self::io::*;
+pub use self::iter::*;
+#[cfg(feature = "ndarray")]
+pub use self::ndarray::*;
+pub use self::plan::*;
 pub use self::r#try::*;
+pub use self::raw::*;
+#[cfg(feature = "rayon")]
+pub use self::rayon::*;
+pub use self::recycle::*;
+#[cfg(feature = "serde")]
+pub use self::serde::*;
+pub use self::sort::*;
+pub use self::string::*;
+#[cfg(feature = "thin-vec")]
+pub use self::thin_vec::*;
 pub use self::vec::*;
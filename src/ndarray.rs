@@ -0,0 +1,82 @@
+use ::ndarray::{Array, Dimension};
+
+use super::VecExt;
+
+/// Extension methods for owned `ndarray::Array<T, D>`
+pub trait ArrayExt: Sized {
+    /// The type that the `Array<T, D>` stores
+    type T;
+
+    /// The dimension of the `Array<T, D>`
+    type D: Dimension;
+
+    /// Map an array to another array with the same shape, will try and reuse
+    /// the backing allocation if the allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused
+    ///
+    /// The backing allocation can only be reused if the array is in standard layout,
+    /// otherwise the elements will be collected into a new allocation first
+    fn map_reuse<U, F: FnMut(Self::T) -> U>(self, f: F) -> Array<U, Self::D>;
+
+    /// Zip an array with another array of the same shape and combine them,
+    /// the allocation will be reused if possible, the larger allocation of the input
+    /// arrays will be used if all of `T`, `U`, and `V` have the same allocation layouts.
+    ///
+    /// # Panic
+    ///
+    /// if the shapes of the two arrays are different then this function will panic
+    fn zip_with_reuse<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Array<U, Self::D>,
+        f: F,
+    ) -> Array<V, Self::D>;
+}
+
+/// Convert an array into it's elements in logical order
+///
+/// This reuses the backing allocation if the array is in standard layout
+fn into_standard_vec<T, D: Dimension>(array: Array<T, D>) -> (Vec<T>, D) {
+    let dim = array.raw_dim();
+    let len = array.len();
+
+    if array.is_standard_layout() {
+        let (mut vec, offset) = array.into_raw_vec_and_offset();
+        let offset = offset.unwrap_or(0);
+
+        // the array may have been sliced in place, in which case there
+        // are elements outside of the logical array that need to be removed
+        // (this never reallocates)
+        vec.truncate(offset + len);
+        vec.drain(..offset);
+
+        (vec, dim)
+    } else {
+        (array.into_iter().collect(), dim)
+    }
+}
+
+impl<T, D: Dimension> ArrayExt for Array<T, D> {
+    type T = T;
+    type D = D;
+
+    fn map_reuse<U, F: FnMut(T) -> U>(self, f: F) -> Array<U, D> {
+        let (vec, dim) = into_standard_vec(self);
+
+        Array::from_shape_vec(dim, vec.map(f)).expect("the shape of the array cannot change")
+    }
+
+    fn zip_with_reuse<U, V, F: FnMut(T, U) -> V>(self, other: Array<U, D>, f: F) -> Array<V, D> {
+        assert_eq!(
+            self.shape(),
+            other.shape(),
+            "Cannot zip arrays with different shapes"
+        );
+
+        let (vec, dim) = into_standard_vec(self);
+        let (other, _) = into_standard_vec(other);
+
+        Array::from_shape_vec(dim, vec.zip_with(other, f))
+            .expect("the shape of the array cannot change")
+    }
+}
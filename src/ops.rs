@@ -0,0 +1,122 @@
+//! Elementwise arithmetic on vectors of numbers, built on [`VecExt::zip_with`]
+//! and [`VecExt::map`], so the allocation of the left vector is always reused
+//!
+//! The binary operations zip the vectors, so the output is as long as the shorter one
+//!
+//! ```rust
+//! use vec_utils::ops;
+//!
+//! let vec = ops::add(vec![1, 2, 3], vec![10, 20, 30]);
+//! let vec = ops::scale(vec, 2);
+//! let vec = ops::saturating_sub(vec, vec![100, 0, 100]);
+//!
+//! assert_eq!(vec, [0, 44, 0u32]);
+//! ```
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::VecExt;
+
+mod seal {
+    pub trait Seal {}
+}
+
+/// The primitive integer types, which have saturating and wrapping arithmetic
+pub trait Integer: seal::Seal + Copy {
+    #[doc(hidden)]
+    fn saturating_add(self, other: Self) -> Self;
+    #[doc(hidden)]
+    fn saturating_sub(self, other: Self) -> Self;
+    #[doc(hidden)]
+    fn saturating_mul(self, other: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_add(self, other: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_sub(self, other: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_mul(self, other: Self) -> Self;
+}
+
+macro_rules! integer {
+    ($($type:ty)*) => {$(
+        impl seal::Seal for $type {}
+
+        impl Integer for $type {
+            fn saturating_add(self, other: Self) -> Self {
+                self.saturating_add(other)
+            }
+
+            fn saturating_sub(self, other: Self) -> Self {
+                self.saturating_sub(other)
+            }
+
+            fn saturating_mul(self, other: Self) -> Self {
+                self.saturating_mul(other)
+            }
+
+            fn wrapping_add(self, other: Self) -> Self {
+                self.wrapping_add(other)
+            }
+
+            fn wrapping_sub(self, other: Self) -> Self {
+                self.wrapping_sub(other)
+            }
+
+            fn wrapping_mul(self, other: Self) -> Self {
+                self.wrapping_mul(other)
+            }
+        }
+    )*};
+}
+
+integer! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+/// Add the elements of two vectors
+pub fn add<T: Add<Output = T>>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::add)
+}
+
+/// Subtract the elements of `b` from the elements of `a`
+pub fn sub<T: Sub<Output = T>>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::sub)
+}
+
+/// Multiply the elements of two vectors
+pub fn mul<T: Mul<Output = T>>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::mul)
+}
+
+/// Multiply each element of the vector by `factor`
+pub fn scale<T: Mul<Output = T> + Copy>(vec: Vec<T>, factor: T) -> Vec<T> {
+    vec.map(|x| x * factor)
+}
+
+/// Add the elements of two vectors, saturating at the numeric bounds
+pub fn saturating_add<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::saturating_add)
+}
+
+/// Subtract the elements of `b` from the elements of `a`, saturating at the numeric bounds
+pub fn saturating_sub<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::saturating_sub)
+}
+
+/// Multiply the elements of two vectors, saturating at the numeric bounds
+pub fn saturating_mul<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::saturating_mul)
+}
+
+/// Add the elements of two vectors, wrapping around at the numeric bounds
+pub fn wrapping_add<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::wrapping_add)
+}
+
+/// Subtract the elements of `b` from the elements of `a`, wrapping around at the numeric bounds
+pub fn wrapping_sub<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::wrapping_sub)
+}
+
+/// Multiply the elements of two vectors, wrapping around at the numeric bounds
+pub fn wrapping_mul<T: Integer>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.zip_with(b, T::wrapping_mul)
+}
@@ -0,0 +1,179 @@
+use std::alloc::Layout;
+
+use crate::{reuse_config, ReuseConfig};
+
+/// Why the allocation of an input isn't reused for the output, see [`ReusePlan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rejection {
+    /// The elements of the input and the output have different layouts
+    LayoutMismatch {
+        /// The layout of the input elements
+        input: Layout,
+        /// The layout of the output elements
+        output: Layout,
+    },
+    /// Another input with a larger capacity is reused instead
+    SmallerCapacity {
+        /// The capacity of the input
+        capacity: usize,
+    },
+    /// The [`ReuseConfig`] declines to reuse the allocation, because the input
+    /// is too short or the allocation would have too much unused capacity
+    Config {
+        /// The length of the output
+        len: usize,
+        /// The capacity of the input
+        capacity: usize,
+    },
+}
+
+/// Predicts which allocation a transform writes its output into, without running it
+///
+/// The inputs are numbered in the order they are passed to the transform,
+/// and each input that isn't reused has a [`Rejection`]
+///
+/// ```rust
+/// use std::alloc::Layout;
+/// use vec_utils::{Rejection, ReusePlan};
+///
+/// let a = vec![0u32; 4];
+/// let b = Vec::<f32>::with_capacity(8);
+///
+/// let plan = ReusePlan::for_zip::<_, _, f32>(&a, &b);
+/// assert_eq!(plan.reused(), Some(1));
+/// assert_eq!(plan.capacity(), 8);
+/// assert_eq!(plan.rejections(), [(0, Rejection::SmallerCapacity { capacity: 4 })]);
+///
+/// let plan = ReusePlan::for_map::<_, u64>(&a);
+/// let rejection = Rejection::LayoutMismatch {
+///     input: Layout::new::<u32>(),
+///     output: Layout::new::<u64>(),
+/// };
+/// assert_eq!(plan.reused(), None);
+/// assert_eq!(plan.rejections(), [(0, rejection)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReusePlan {
+    reused: Option<usize>,
+    capacity: usize,
+    rejections: Vec<(usize, Rejection)>,
+}
+
+fn check<T, V>(
+    index: usize,
+    len: usize,
+    capacity: usize,
+    config: &ReuseConfig,
+    rejections: &mut Vec<(usize, Rejection)>,
+) -> bool {
+    let (input, output) = (Layout::new::<T>(), Layout::new::<V>());
+
+    let rejection = if input != output {
+        Rejection::LayoutMismatch { input, output }
+    } else if !config.reuses(len, capacity) {
+        Rejection::Config { len, capacity }
+    } else {
+        return true;
+    };
+
+    rejections.push((index, rejection));
+    false
+}
+
+impl ReusePlan {
+    fn fallback(len: usize, rejections: Vec<(usize, Rejection)>) -> Self {
+        Self {
+            reused: None,
+            capacity: len,
+            rejections,
+        }
+    }
+
+    /// The plan for [`VecExt::map`](crate::VecExt::map) and the other transforms
+    /// that reuse the allocation if the layouts of the elements match,
+    /// with the process-wide [`ReuseConfig`]
+    pub fn for_map<T, U>(vec: &Vec<T>) -> Self {
+        Self::for_map_with_config::<T, U>(vec, reuse_config())
+    }
+
+    /// The plan for [`VecExt::map_with_config`](crate::VecExt::map_with_config)
+    pub fn for_map_with_config<T, U>(vec: &Vec<T>, config: ReuseConfig) -> Self {
+        Self::map_parts::<T, U>(vec.len(), vec.capacity(), config)
+    }
+
+    /// The plan for a map over an input with the given length and capacity
+    pub(crate) fn map_parts<T, U>(len: usize, capacity: usize, config: ReuseConfig) -> Self {
+        let mut rejections = Vec::new();
+
+        if check::<T, U>(0, len, capacity, &config, &mut rejections) {
+            Self {
+                reused: Some(0),
+                capacity: config.finished_capacity(len, capacity),
+                rejections,
+            }
+        } else {
+            Self::fallback(len, rejections)
+        }
+    }
+
+    /// The plan for [`VecExt::zip_with`](crate::VecExt::zip_with), the input with
+    /// the larger capacity is reused if both inputs could be, with the process-wide
+    /// [`ReuseConfig`]
+    pub fn for_zip<T, U, V>(a: &Vec<T>, b: &Vec<U>) -> Self {
+        Self::for_zip_with_config::<T, U, V>(a, b, reuse_config())
+    }
+
+    /// The plan for [`VecExt::zip_with_with_config`](crate::VecExt::zip_with_with_config)
+    pub fn for_zip_with_config<T, U, V>(a: &Vec<T>, b: &Vec<U>, config: ReuseConfig) -> Self {
+        Self::zip_parts::<T, U, V>(a.len().min(b.len()), [a.capacity(), b.capacity()], config)
+    }
+
+    /// The plan for a zip of `len` pairs over inputs with the given capacities
+    pub(crate) fn zip_parts<T, U, V>(
+        len: usize,
+        [a_cap, b_cap]: [usize; 2],
+        config: ReuseConfig,
+    ) -> Self {
+        let mut rejections = Vec::new();
+
+        let left = check::<T, V>(0, len, a_cap, &config, &mut rejections);
+        let right = check::<U, V>(1, len, b_cap, &config, &mut rejections);
+
+        let (reused, capacity) = match (left, right) {
+            (true, true) if a_cap >= b_cap => {
+                rejections.push((1, Rejection::SmallerCapacity { capacity: b_cap }));
+                (0, a_cap)
+            }
+            (true, true) => {
+                rejections.push((0, Rejection::SmallerCapacity { capacity: a_cap }));
+                (1, b_cap)
+            }
+            (true, false) => (0, a_cap),
+            (false, true) => (1, b_cap),
+            (false, false) => return Self::fallback(len, rejections),
+        };
+
+        Self {
+            reused: Some(reused),
+            capacity: config.finished_capacity(len, capacity),
+            rejections,
+        }
+    }
+
+    /// The input whose allocation is reused, if any
+    pub fn reused(&self) -> Option<usize> {
+        self.reused
+    }
+
+    /// The capacity of the output, if no input is reused then the output
+    /// is allocated with at least this capacity, or taken from the thread-local
+    /// [`RecycleBin`](crate::RecycleBin)
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Why each of the other inputs isn't reused
+    pub fn rejections(&self) -> &[(usize, Rejection)] {
+        &self.rejections
+    }
+}
@@ -0,0 +1,36 @@
+//! The extension traits and macros of this crate
+//!
+//! ```rust
+//! use vec_utils::prelude::*;
+//!
+//! fn to_bits(v: Vec<f32>) -> Vec<u32> {
+//!     v.map(|x| x.to_bits())
+//! }
+//!
+//! fn sum_3(a: Vec<f32>, b: Vec<f32>, c: Vec<f32>) -> Vec<f32> {
+//!     zip_with!((a, b, c), |a, b, c| a + b + c)
+//! }
+//! ```
+
+pub use crate::{impl_columns, try_zip_with, zip_assign, zip_with};
+
+pub use crate::{
+    BinaryHeapExt, BoxExt, ByteVecExt, CStringExt, CollectWith, Columns, CowExt, SliceSortExt,
+    SpareCapacityExt, StringVecExt, TransparentVecExt, VecArrayExt, VecBoxExt, VecExt,
+    VecOptionExt, VecResultExt, VecRunsExt, VecVecExt,
+};
+
+#[cfg(feature = "bumpalo")]
+pub use crate::ArenaExt;
+#[cfg(feature = "ndarray")]
+pub use crate::ArrayExt;
+#[cfg(feature = "futures")]
+pub use crate::AsyncVecExt;
+#[cfg(feature = "rayon")]
+pub use crate::ParVecExt;
+#[cfg(feature = "simd")]
+pub use crate::SimdVecExt;
+#[cfg(feature = "thin-vec")]
+pub use crate::ThinVecExt;
+#[cfg(feature = "bytes")]
+pub use crate::{BytesExt, BytesMutExt};
@@ -0,0 +1,316 @@
+use std::alloc::Layout;
+use std::fmt;
+use std::mem::ManuallyDrop;
+
+/// A container that can be decomposed into the raw parts of a `Vec<T>`,
+/// and rebuilt from them
+///
+/// This is what allows containers other than `Vec<T>` to be used with
+/// `VecExt` and the `zip_with` family of macros. Decomposing a container
+/// and building a `Vec<T>` from the parts (or the reverse) must be free,
+/// so that the allocation can be reused.
+///
+/// Only the global allocator is supported
+///
+/// # Safety
+///
+/// * `into_raw_parts` must return the parts of an allocation that can be
+///   passed to `Vec::from_raw_parts`, and ownership of the allocation and
+///   the first `len` elements is passed to the caller
+/// * `from_raw_parts` must accept any parts returned by `Vec::into_raw_parts`
+///   (i.e. `len <= cap`), and take ownership of the allocation and the
+///   first `len` elements
+pub unsafe trait RawParts: Sized {
+    /// The type of the elements in the container
+    type Item;
+
+    /// The elements in the container
+    fn as_slice(&self) -> &[Self::Item];
+
+    /// Decompose the container into `(ptr, len, cap)`
+    fn into_raw_parts(self) -> (*mut Self::Item, usize, usize);
+
+    /// Rebuild the container from `(ptr, len, cap)`
+    ///
+    /// # Safety
+    ///
+    /// The parts must be valid to pass to `Vec::from_raw_parts`
+    unsafe fn from_raw_parts(ptr: *mut Self::Item, len: usize, cap: usize) -> Self;
+
+    /// Convert the container into a `Vec<T>` without reallocating
+    #[inline]
+    fn into_vec(self) -> Vec<Self::Item> {
+        let (ptr, len, cap) = self.into_raw_parts();
+
+        unsafe { Vec::from_raw_parts(ptr, len, cap) }
+    }
+
+    /// Convert a `Vec<T>` into the container
+    #[inline]
+    fn from_vec(vec: Vec<Self::Item>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+
+        unsafe { Self::from_raw_parts(vec.as_mut_ptr(), vec.len(), vec.capacity()) }
+    }
+
+    /// Run a function on the container as if it was a `Vec<T>`
+    ///
+    /// The container is rebuilt from the vector afterwards, even if `f` panics
+    #[inline]
+    fn with_vec<R, F: FnOnce(&mut Vec<Self::Item>) -> R>(&mut self, f: F) -> R {
+        struct Restore<'a, C: RawParts> {
+            container: &'a mut C,
+            vec: Vec<C::Item>,
+        }
+
+        impl<C: RawParts> Drop for Restore<'_, C> {
+            fn drop(&mut self) {
+                let vec = std::mem::take(&mut self.vec);
+
+                unsafe { std::ptr::write(self.container, C::from_vec(vec)) }
+            }
+        }
+
+        // the container is logically moved out until `Restore` is dropped
+        let vec = unsafe { std::ptr::read(self) }.into_vec();
+        let mut restore = Restore {
+            container: self,
+            vec,
+        };
+
+        f(&mut restore.vec)
+    }
+}
+
+unsafe impl<T> RawParts for Vec<T> {
+    type Item = T;
+
+    #[inline]
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    #[inline]
+    fn into_raw_parts(self) -> (*mut T, usize, usize) {
+        let mut vec = ManuallyDrop::new(self);
+
+        (vec.as_mut_ptr(), vec.len(), vec.capacity())
+    }
+
+    #[inline]
+    unsafe fn from_raw_parts(ptr: *mut T, len: usize, cap: usize) -> Self {
+        Vec::from_raw_parts(ptr, len, cap)
+    }
+
+    #[inline]
+    fn into_vec(self) -> Vec<T> {
+        self
+    }
+
+    #[inline]
+    fn from_vec(vec: Vec<T>) -> Self {
+        vec
+    }
+
+    #[inline]
+    fn with_vec<R, F: FnOnce(&mut Vec<T>) -> R>(&mut self, f: F) -> R {
+        f(self)
+    }
+}
+
+unsafe impl<T> RawParts for Box<[T]> {
+    type Item = T;
+
+    #[inline]
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    #[inline]
+    fn into_raw_parts(self) -> (*mut T, usize, usize) {
+        let len = self.len();
+        let ptr = Box::into_raw(self) as *mut T;
+
+        (ptr, len, len)
+    }
+
+    /// This will shrink the allocation if `len != cap`
+    #[inline]
+    unsafe fn from_raw_parts(ptr: *mut T, len: usize, cap: usize) -> Self {
+        Vec::from_raw_parts(ptr, len, cap).into_boxed_slice()
+    }
+
+    #[inline]
+    fn into_vec(self) -> Vec<T> {
+        Vec::from(self)
+    }
+
+    #[inline]
+    fn from_vec(vec: Vec<T>) -> Self {
+        vec.into_boxed_slice()
+    }
+}
+
+/// The error returned when the capacity of an allocation can't be
+/// expressed in terms of another type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleError {
+    /// One of the types is zero-sized, so the allocation can't be shared
+    ZeroSized,
+    /// The types have different alignments
+    AlignMismatch {
+        /// The alignment of the source type
+        from: usize,
+        /// The alignment of the target type
+        to: usize,
+    },
+    /// The size of the allocation in bytes would exceed `isize::MAX`
+    Overflow {
+        /// The number of elements of the source type
+        capacity: usize,
+        /// The size of the source type
+        size: usize,
+    },
+    /// The size of the allocation in bytes isn't a multiple of the size of the target type
+    Uneven {
+        /// The size of the allocation in bytes
+        bytes: usize,
+        /// The size of the target type
+        size: usize,
+    },
+}
+
+impl fmt::Display for ScaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ScaleError::ZeroSized => f.write_str("can't reuse an allocation for a zero-sized type"),
+            ScaleError::AlignMismatch { from, to } => write!(
+                f,
+                "can't reuse an allocation with an alignment of {} for a type with an alignment of {}",
+                from, to
+            ),
+            ScaleError::Overflow { capacity, size } => write!(
+                f,
+                "{} elements of {} bytes would exceed `isize::MAX` bytes",
+                capacity, size
+            ),
+            ScaleError::Uneven { bytes, size } => write!(
+                f,
+                "an allocation of {} bytes can't be evenly divided into elements of {} bytes",
+                bytes, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScaleError {}
+
+/// Convert a capacity of `from`s into the capacity of `to`s
+/// that fits in the same allocation
+pub(crate) fn scale(capacity: usize, from: Layout, to: Layout) -> Result<usize, ScaleError> {
+    if from.size() == 0 || to.size() == 0 {
+        return Err(ScaleError::ZeroSized);
+    }
+
+    if from.align() != to.align() {
+        return Err(ScaleError::AlignMismatch {
+            from: from.align(),
+            to: to.align(),
+        });
+    }
+
+    let bytes = capacity
+        .checked_mul(from.size())
+        .filter(|&bytes| bytes <= isize::MAX as usize)
+        .ok_or(ScaleError::Overflow {
+            capacity,
+            size: from.size(),
+        })?;
+
+    if !bytes.is_multiple_of(to.size()) {
+        return Err(ScaleError::Uneven {
+            bytes,
+            size: to.size(),
+        });
+    }
+
+    Ok(bytes / to.size())
+}
+
+/// Convert a capacity of `T`s into the capacity of `U`s that fits in the same allocation
+///
+/// This succeeds if neither type is zero-sized, both types have the same alignment,
+/// and the size of the allocation in bytes is at most `isize::MAX` and is a multiple
+/// of the size of `U`
+///
+/// ```rust
+/// use vec_utils::{scale_capacity, ScaleError};
+///
+/// assert_eq!(scale_capacity::<u32, [u32; 2]>(8), Ok(4));
+/// assert_eq!(scale_capacity::<u32, [u32; 3]>(8), Err(ScaleError::Uneven { bytes: 32, size: 12 }));
+/// ```
+pub fn scale_capacity<T, U>(capacity: usize) -> Result<usize, ScaleError> {
+    scale(capacity, Layout::new::<T>(), Layout::new::<U>())
+}
+
+/// Drop all of the elements of `vec`, and convert its allocation into
+/// an empty `Vec<U>` if the allocation can hold `U`s, see [`scale_capacity`]
+pub(crate) fn convert_alloc<T, U>(mut vec: Vec<T>) -> Result<Vec<U>, ScaleError> {
+    let capacity = scale_capacity::<T, U>(vec.capacity())?;
+
+    vec.clear();
+
+    let mut vec = ManuallyDrop::new(vec);
+
+    unsafe { Ok(Vec::from_raw_parts(vec.as_mut_ptr() as *mut U, 0, capacity)) }
+}
+
+/// Reinterpret the elements of `vec` as `U`s, without touching them
+///
+/// # Safety
+///
+/// Every `T` must be a valid `U`
+pub(crate) unsafe fn cast_vec<T, U>(vec: Vec<T>) -> Vec<U> {
+    const {
+        assert!(
+            std::mem::size_of::<T>() == std::mem::size_of::<U>()
+                && std::mem::align_of::<T>() == std::mem::align_of::<U>(),
+            "the layouts of `T` and `U` must match"
+        )
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+
+    Vec::from_raw_parts(vec.as_mut_ptr() as *mut U, vec.len(), vec.capacity())
+}
+
+/// Frees an allocation made by a `Vec<T>` when dropped, without touching its elements
+pub(crate) struct RawAllocGuard {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl RawAllocGuard {
+    /// # Safety
+    ///
+    /// `ptr` and `cap` must be the pointer and capacity of a `Vec<T>`,
+    /// and the allocation must not be used after this guard is dropped
+    pub(crate) unsafe fn new<T>(ptr: *mut T, cap: usize) -> Self {
+        Self {
+            ptr: ptr as *mut u8,
+            layout: Layout::from_size_align_unchecked(
+                cap * std::mem::size_of::<T>(),
+                std::mem::align_of::<T>(),
+            ),
+        }
+    }
+}
+
+impl Drop for RawAllocGuard {
+    fn drop(&mut self) {
+        // `Vec` doesn't allocate for zero-sized types or zero capacity
+        if self.layout.size() != 0 {
+            unsafe { std::alloc::dealloc(self.ptr, self.layout) }
+        }
+    }
+}
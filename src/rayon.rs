@@ -0,0 +1,564 @@
+use ::rayon::iter::plumbing::{
+    bridge, Consumer, Folder, Producer, ProducerCallback, Reducer, UnindexedConsumer,
+};
+use ::rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// Extension methods for running transforms of `Vec<T>` on the rayon thread pool
+///
+/// The allocation of the input is reused for the output if the allocation
+/// layouts of the input and output elements match
+pub trait ParVecExt: Sized {
+    /// The type that the `Vec<T>` stores
+    type T: Send;
+
+    /// Turn the vector into a parallel pipeline that can write
+    /// its output back into the allocation of the vector
+    ///
+    /// ```rust
+    /// use rayon::prelude::*;
+    /// use vec_utils::ParVecExt;
+    ///
+    /// let vec = vec![1.0f32, 2.0, 3.0];
+    /// let ptr = vec.as_ptr();
+    ///
+    /// let vec = vec
+    ///     .into_reuse_par_iter()
+    ///     .zip(vec![1u32, 2, 3])
+    ///     .map(|(x, y)| x.to_bits() ^ y)
+    ///     .collect_reuse();
+    /// assert_eq!(vec.as_ptr() as usize, ptr as usize);
+    ///
+    /// // it's a regular parallel iterator too
+    /// let sum: u32 = vec![1u32, 2, 3].into_reuse_par_iter().map(|x| x * 2).sum();
+    /// assert_eq!(sum, 12);
+    /// ```
+    fn into_reuse_par_iter(self) -> ParReuseIter<ParSource<Self::T>>;
+
+    /// The same as `VecExt::map`, but the elements are mapped in parallel
+    fn par_map<U: Send, F: Fn(Self::T) -> U + Sync + Send>(self, f: F) -> Vec<U> {
+        self.into_reuse_par_iter().map(f).collect_reuse()
+    }
+
+    /// The same as `VecExt::zip_with`, but the elements are combined in parallel
+    fn par_zip_with<U: Send, V: Send, F: Fn(Self::T, U) -> V + Sync + Send>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<V>;
+}
+
+impl<T: Send> ParVecExt for Vec<T> {
+    type T = T;
+
+    fn into_reuse_par_iter(self) -> ParReuseIter<ParSource<T>> {
+        ParReuseIter {
+            stage: ParSource::from(self),
+        }
+    }
+
+    fn par_zip_with<U: Send, V: Send, F: Fn(T, U) -> V + Sync + Send>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<V> {
+        let left = Layout::new::<T>() == Layout::new::<V>();
+        let right = Layout::new::<U>() == Layout::new::<V>();
+
+        if !right || left && self.capacity() >= other.capacity() {
+            self.into_reuse_par_iter()
+                .zip(other)
+                .map(move |(x, y)| f(x, y))
+                .collect_reuse()
+        } else {
+            other
+                .into_reuse_par_iter()
+                .zip(self)
+                .map(move |(y, x)| f(x, y))
+                .collect_reuse()
+        }
+    }
+}
+
+/// A stage of a [`ParReuseIter`] pipeline, that keeps track of the source allocation
+///
+/// # Safety
+///
+/// The `n`th item of the stage must be produced from the `n`th element of the source,
+/// after that element was read, and the stage must not read an element of the source
+/// after the item at its index was produced
+pub unsafe trait ParStage: IndexedParallelIterator {
+    /// The elements of the source vector
+    type Source: Send;
+
+    /// The start of the pipeline
+    fn source(&mut self) -> &mut ParSource<Self::Source>;
+}
+
+/// The start of a [`ParReuseIter`] pipeline, it yields the elements of a `Vec<T>`,
+/// and keeps track of its allocation
+pub struct ParSource<T> {
+    ptr: *mut T,
+    // the number of elements that weren't handed to a producer yet
+    len: usize,
+    cap: usize,
+    drop_alloc: bool,
+    drop: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for ParSource<T> {}
+unsafe impl<T: Sync> Sync for ParSource<T> {}
+
+impl<T> From<Vec<T>> for ParSource<T> {
+    fn from(vec: Vec<T>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+
+        Self {
+            ptr: vec.as_mut_ptr(),
+            len: vec.len(),
+            cap: vec.capacity(),
+            drop_alloc: true,
+            drop: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for ParSource<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let _alloc = self
+                .drop_alloc
+                .then(|| RawAllocGuard::new(self.ptr, self.cap));
+
+            cleanup::drop_slice(self.ptr, self.len);
+        }
+    }
+}
+
+impl<T: Send> ParallelIterator for ParSource<T> {
+    type Item = T;
+
+    fn drive_unindexed<C: UnindexedConsumer<T>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<T: Send> IndexedParallelIterator for ParSource<T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn drive<C: Consumer<T>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<T>>(mut self, callback: CB) -> CB::Output {
+        // the producer owns the elements now, the allocation is freed
+        // once the callback returns, if it wasn't taken for the output
+        let len = std::mem::replace(&mut self.len, 0);
+
+        callback.callback(SourceProducer {
+            ptr: self.ptr,
+            len,
+            drop: PhantomData,
+        })
+    }
+}
+
+unsafe impl<T: Send> ParStage for ParSource<T> {
+    type Source = T;
+
+    fn source(&mut self) -> &mut ParSource<T> {
+        self
+    }
+}
+
+/// Owns the elements of a part of the source
+struct SourceProducer<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    drop: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for SourceProducer<'_, T> {}
+
+impl<T> Drop for SourceProducer<'_, T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr, self.len) }
+    }
+}
+
+impl<'a, T: Send> Producer for SourceProducer<'a, T> {
+    type Item = T;
+    type IntoIter = SourceIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let this = ManuallyDrop::new(self);
+
+        SourceIter {
+            ptr: this.ptr,
+            start: 0,
+            end: this.len,
+            drop: PhantomData,
+        }
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        assert!(index <= self.len);
+        let this = ManuallyDrop::new(self);
+
+        let right = SourceProducer {
+            ptr: unsafe { this.ptr.add(index) },
+            len: this.len - index,
+            drop: PhantomData,
+        };
+
+        let left = SourceProducer {
+            ptr: this.ptr,
+            len: index,
+            drop: PhantomData,
+        };
+
+        (left, right)
+    }
+}
+
+/// Reads the elements of a part of the source, and drops the rest of them
+struct SourceIter<'a, T> {
+    ptr: *mut T,
+    // the elements from `start` to `end` weren't read yet
+    start: usize,
+    end: usize,
+    drop: PhantomData<&'a mut [T]>,
+}
+
+impl<T> Drop for SourceIter<'_, T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr.add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T> Iterator for SourceIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+        Some(unsafe { self.ptr.add(index).read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for SourceIter<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        Some(unsafe { self.ptr.add(self.end).read() })
+    }
+}
+
+impl<T> ExactSizeIterator for SourceIter<'_, T> {}
+
+/// A [`ParReuseIter`] stage that maps each item
+pub struct ParMap<S, F> {
+    base: S,
+    f: F,
+}
+
+/// A [`ParReuseIter`] stage that zips the items with another parallel iterator
+pub struct ParZip<S, I> {
+    base: S,
+    other: I,
+}
+
+impl<U: Send, S: ParStage, F: Fn(S::Item) -> U + Sync + Send> ParallelIterator for ParMap<S, F> {
+    type Item = U;
+
+    fn drive_unindexed<C: UnindexedConsumer<U>>(self, consumer: C) -> C::Result {
+        self.base.map(self.f).drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.base.len())
+    }
+}
+
+impl<U: Send, S: ParStage, F: Fn(S::Item) -> U + Sync + Send> IndexedParallelIterator
+    for ParMap<S, F>
+{
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn drive<C: Consumer<U>>(self, consumer: C) -> C::Result {
+        self.base.map(self.f).drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<U>>(self, callback: CB) -> CB::Output {
+        self.base.map(self.f).with_producer(callback)
+    }
+}
+
+unsafe impl<U: Send, S: ParStage, F: Fn(S::Item) -> U + Sync + Send> ParStage for ParMap<S, F> {
+    type Source = S::Source;
+
+    fn source(&mut self) -> &mut ParSource<S::Source> {
+        self.base.source()
+    }
+}
+
+impl<S: ParStage, I: IndexedParallelIterator> ParallelIterator for ParZip<S, I> {
+    type Item = (S::Item, I::Item);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.base.zip(self.other).drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(IndexedParallelIterator::len(self))
+    }
+}
+
+impl<S: ParStage, I: IndexedParallelIterator> IndexedParallelIterator for ParZip<S, I> {
+    fn len(&self) -> usize {
+        self.base.len().min(self.other.len())
+    }
+
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.base.zip(self.other).drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        self.base.zip(self.other).with_producer(callback)
+    }
+}
+
+unsafe impl<S: ParStage, I: IndexedParallelIterator> ParStage for ParZip<S, I> {
+    type Source = S::Source;
+
+    fn source(&mut self) -> &mut ParSource<S::Source> {
+        self.base.source()
+    }
+}
+
+/// A parallel pipeline over a `Vec<T>`, that can collect its output into the
+/// allocation of the vector, see [`ParVecExt::into_reuse_par_iter`]
+///
+/// Only `map` and `zip` are tracked, since they keep the index of each item. The
+/// other rayon adapters are available through [`ParallelIterator`] and
+/// [`IndexedParallelIterator`], but they can't collect into the allocation
+pub struct ParReuseIter<S> {
+    stage: S,
+}
+
+impl<S: ParStage> ParReuseIter<S> {
+    /// Map each item of the pipeline
+    pub fn map<U: Send, F: Fn(S::Item) -> U + Sync + Send>(
+        self,
+        f: F,
+    ) -> ParReuseIter<ParMap<S, F>> {
+        ParReuseIter {
+            stage: ParMap {
+                base: self.stage,
+                f,
+            },
+        }
+    }
+
+    /// Zip the pipeline with another parallel iterator, the
+    /// pipeline is as long as the shorter of the two
+    pub fn zip<I: IntoParallelIterator>(self, other: I) -> ParReuseIter<ParZip<S, I::Iter>>
+    where
+        I::Iter: IndexedParallelIterator,
+    {
+        ParReuseIter {
+            stage: ParZip {
+                base: self.stage,
+                other: other.into_par_iter(),
+            },
+        }
+    }
+
+    /// Collect the pipeline into a vector, the allocation of the source vector is reused
+    /// if the allocation layouts of the items and the source elements match, otherwise
+    /// this is the same as `collect::<Vec<_>>()`
+    ///
+    /// If a stage panics, then the outputs and the rest of the source elements are dropped
+    pub fn collect_reuse(mut self) -> Vec<S::Item> {
+        if Layout::new::<S::Source>() != Layout::new::<S::Item>()
+            || std::mem::size_of::<S::Item>() == 0
+        {
+            return self.stage.collect();
+        }
+
+        let len = self.stage.len();
+
+        let source = self.stage.source();
+        let (ptr, cap) = (source.ptr as *mut S::Item, source.cap);
+
+        unsafe {
+            // the output takes over the allocation
+            source.drop_alloc = false;
+            let alloc = RawAllocGuard::new(ptr, cap);
+
+            let written = self.stage.drive(OutputConsumer {
+                ptr,
+                len,
+                drop: PhantomData,
+            });
+
+            assert_eq!(written.len, len, "expected {} items", len);
+            std::mem::forget((written, alloc));
+
+            Vec::from_raw_parts(ptr, len, cap)
+        }
+    }
+}
+
+impl<S: ParStage> ParallelIterator for ParReuseIter<S> {
+    type Item = S::Item;
+
+    fn drive_unindexed<C: UnindexedConsumer<S::Item>>(self, consumer: C) -> C::Result {
+        self.stage.drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.stage.len())
+    }
+}
+
+impl<S: ParStage> IndexedParallelIterator for ParReuseIter<S> {
+    fn len(&self) -> usize {
+        self.stage.len()
+    }
+
+    fn drive<C: Consumer<S::Item>>(self, consumer: C) -> C::Result {
+        self.stage.drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<S::Item>>(self, callback: CB) -> CB::Output {
+        self.stage.with_producer(callback)
+    }
+}
+
+/// Writes the items into a part of the source allocation, which
+/// starts at `ptr` and has room for `len` items
+struct OutputConsumer<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    drop: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for OutputConsumer<'_, T> {}
+
+/// The items that were written into a part of the source allocation,
+/// they are dropped unless they are merged into the full output
+struct Written<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    cap: usize,
+    drop: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for Written<'_, T> {}
+
+impl<T> Drop for Written<'_, T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr, self.len) }
+    }
+}
+
+impl<'a, T: Send> Consumer<T> for OutputConsumer<'a, T> {
+    type Folder = Written<'a, T>;
+    type Reducer = MergeWritten;
+    type Result = Written<'a, T>;
+
+    fn split_at(self, index: usize) -> (Self, Self, MergeWritten) {
+        assert!(index <= self.len);
+
+        let right = OutputConsumer {
+            ptr: unsafe { self.ptr.add(index) },
+            len: self.len - index,
+            drop: PhantomData,
+        };
+
+        let left = OutputConsumer {
+            ptr: self.ptr,
+            len: index,
+            drop: PhantomData,
+        };
+
+        (left, right, MergeWritten)
+    }
+
+    fn into_folder(self) -> Written<'a, T> {
+        Written {
+            ptr: self.ptr,
+            len: 0,
+            cap: self.len,
+            drop: PhantomData,
+        }
+    }
+
+    fn full(&self) -> bool {
+        false
+    }
+}
+
+impl<'a, T> Folder<T> for Written<'a, T> {
+    type Result = Self;
+
+    fn consume(mut self, item: T) -> Self {
+        assert!(self.len < self.cap, "too many items");
+
+        // the stage read the source element at this index before producing `item`
+        unsafe { self.ptr.add(self.len).write(item) }
+        self.len += 1;
+        self
+    }
+
+    fn complete(self) -> Self {
+        self
+    }
+
+    fn full(&self) -> bool {
+        false
+    }
+}
+
+struct MergeWritten;
+
+impl<'a, T> Reducer<Written<'a, T>> for MergeWritten {
+    fn reduce(self, mut left: Written<'a, T>, right: Written<'a, T>) -> Written<'a, T> {
+        // if the left part is complete, then the parts are contiguous,
+        // otherwise `right` is dropped and the output is incomplete
+        if left.len == left.cap {
+            let right = ManuallyDrop::new(right);
+            left.len += right.len;
+            left.cap += right.cap;
+        }
+
+        left
+    }
+}
@@ -0,0 +1,346 @@
+use std::alloc::Layout;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+
+use crate::raw::scale;
+use crate::UninitBox;
+
+/// A pool of retired allocations, keyed by their layout
+///
+/// Vectors can be deposited into the bin, which drops their elements
+/// and keeps their allocation around. Later, an empty vector of any type
+/// that fits into one of these allocations can be taken out of the bin.
+///
+/// There is also a thread-local bin (see [`RecycleBin::with_local`]), which the
+/// fallback paths of `VecExt`, the `zip_with` family of macros, `CollectWith`,
+/// and `ReuseIter` consult before allocating a new vector.
+///
+/// The bin holds at most [`RecycleBin::DEFAULT_MAX_LEN`] allocations, that take up at most
+/// [`RecycleBin::DEFAULT_MAX_BYTES`] bytes (see [`RecycleBin::with_limits`]),
+/// allocations that are deposited past these limits are freed.
+pub struct RecycleBin {
+    // (size, align) -> allocations
+    allocs: HashMap<(usize, usize), Vec<NonNull<u8>>>,
+    len: usize,
+    bytes: usize,
+    max_len: usize,
+    max_bytes: usize,
+}
+
+// the allocations are owned by the bin, and aren't tied to any thread
+unsafe impl Send for RecycleBin {}
+unsafe impl Sync for RecycleBin {}
+
+thread_local! {
+    static LOCAL: RefCell<RecycleBin> = RefCell::new(RecycleBin::new());
+}
+
+impl Default for RecycleBin {
+    fn default() -> Self {
+        Self::with_limits(Self::DEFAULT_MAX_LEN, Self::DEFAULT_MAX_BYTES)
+    }
+}
+
+impl RecycleBin {
+    /// The default maximum number of allocations in a bin
+    pub const DEFAULT_MAX_LEN: usize = 32;
+
+    /// The default maximum number of bytes of all of the allocations in a bin
+    pub const DEFAULT_MAX_BYTES: usize = 16 << 20;
+
+    /// Create a new empty bin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new empty bin, that holds at most `max_len` allocations,
+    /// which take up at most `max_bytes` bytes
+    pub fn with_limits(max_len: usize, max_bytes: usize) -> Self {
+        Self {
+            allocs: HashMap::new(),
+            len: 0,
+            bytes: 0,
+            max_len,
+            max_bytes,
+        }
+    }
+
+    /// The number of allocations in the bin
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bytes of all of the allocations in the bin
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns true if there are no allocations in the bin
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drop all of the elements of the vector, and put it's allocation into the bin
+    ///
+    /// If the vector doesn't have an allocation, this does nothing. If the bin
+    /// is full, the allocation is freed
+    pub fn deposit<T>(&mut self, mut vec: Vec<T>) {
+        vec.clear();
+
+        let size = vec.capacity() * std::mem::size_of::<T>();
+
+        if size == 0 {
+            return;
+        }
+
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+
+        unsafe {
+            let ptr = NonNull::new_unchecked(vec.as_mut_ptr() as *mut u8);
+            self.insert(
+                ptr,
+                Layout::from_size_align_unchecked(size, std::mem::align_of::<T>()),
+            );
+        }
+    }
+
+    /// Put the allocation into the bin, or free it if the bin is full
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by the global allocator with the given `layout`,
+    /// and the `layout` must have a non-zero size
+    unsafe fn insert(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let size = layout.size();
+
+        if self.len >= self.max_len || size > self.max_bytes - self.bytes {
+            std::alloc::dealloc(ptr.as_ptr(), layout);
+            return;
+        }
+
+        self.allocs
+            .entry((size, layout.align()))
+            .or_default()
+            .push(ptr);
+        self.len += 1;
+        self.bytes += size;
+    }
+
+    /// Take an empty vector with a capacity of at least `min_cap` out of the bin
+    ///
+    /// The smallest allocation that has the same alignment as `T`, whose size
+    /// is a multiple of the size of `T`, and that can hold at most `2 * min_cap`
+    /// elements is picked. If there is no such allocation, then `None` is returned
+    pub fn take<T>(&mut self, min_cap: usize) -> Option<Vec<T>> {
+        let size_t = std::mem::size_of::<T>();
+        let align = std::mem::align_of::<T>();
+
+        if size_t == 0 || self.is_empty() {
+            return None;
+        }
+
+        let min_size = min_cap.checked_mul(size_t)?;
+        // don't hand out allocations that are mostly unused
+        let max_size = min_size.saturating_mul(2);
+
+        let size = self
+            .allocs
+            .iter()
+            .filter(|(&(size, a), allocs)| {
+                a == align
+                    && (min_size..=max_size).contains(&size)
+                    && size % size_t == 0
+                    && !allocs.is_empty()
+            })
+            .map(|(&(size, _), _)| size)
+            .min()?;
+
+        let allocs = self.allocs.get_mut(&(size, align))?;
+        let ptr = allocs.pop()?;
+
+        if allocs.is_empty() {
+            self.allocs.remove(&(size, align));
+        }
+
+        self.len -= 1;
+        self.bytes -= size;
+
+        unsafe {
+            Some(Vec::from_raw_parts(
+                ptr.as_ptr() as *mut T,
+                0,
+                size / size_t,
+            ))
+        }
+    }
+
+    /// Free all of the allocations in the bin
+    pub fn clear(&mut self) {
+        for (&(size, align), allocs) in self.allocs.iter() {
+            for ptr in allocs {
+                unsafe {
+                    std::alloc::dealloc(
+                        ptr.as_ptr(),
+                        Layout::from_size_align_unchecked(size, align),
+                    );
+                }
+            }
+        }
+
+        self.allocs.clear();
+        self.len = 0;
+        self.bytes = 0;
+    }
+
+    /// Run a function with the thread-local bin
+    ///
+    /// # Panic
+    ///
+    /// if this is called from inside of `f` then this function will panic
+    pub fn with_local<R, F: FnOnce(&mut RecycleBin) -> R>(f: F) -> R {
+        LOCAL.with(move |bin| f(&mut bin.borrow_mut()))
+    }
+
+    /// Take an empty vector out of the thread-local bin, if the thread-local bin
+    /// isn't available (i.e. it is in use, or being destroyed) then `None` is returned
+    pub(crate) fn take_local<T>(min_cap: usize) -> Option<Vec<T>> {
+        LOCAL
+            .try_with(|bin| bin.try_borrow_mut().ok()?.take(min_cap))
+            .ok()
+            .flatten()
+    }
+
+    /// Drop all of the elements of the vector, and put its allocation into the
+    /// thread-local bin, if the thread-local bin isn't available (i.e. it is in use,
+    /// or being destroyed) then the allocation is freed
+    pub(crate) fn deposit_local<T>(mut vec: Vec<T>) {
+        // the destructors may use the thread-local bin, so they must run before it's borrowed
+        vec.clear();
+
+        let _ = LOCAL.try_with(move |bin| {
+            if let Ok(mut bin) = bin.try_borrow_mut() {
+                bin.deposit(vec)
+            }
+        });
+    }
+}
+
+impl Drop for RecycleBin {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+/// The allocation of a vector whose elements were dropped, which can be
+/// turned into an empty vector or an [`UninitBox`] of another type
+///
+/// This is returned by the `_recycle` variants of [`VecExt::map`](crate::VecExt::map)
+/// and [`VecExt::zip_with`](crate::VecExt::zip_with) when an input allocation isn't reused
+///
+/// ```rust
+/// use vec_utils::VecExt;
+///
+/// let (floats, spare) = vec![1u8, 2, 3, 4].map_recycle(f32::from);
+/// assert_eq!(floats, [1.0, 2.0, 3.0, 4.0]);
+///
+/// let spare = spare.unwrap();
+/// assert_eq!(spare.layout().size(), 4);
+///
+/// let mut vec = spare.into_vec::<[u8; 2]>().unwrap();
+/// assert_eq!(vec.capacity(), 2);
+/// vec.push([5, 6]);
+/// ```
+#[derive(Debug)]
+pub struct RecycledAlloc {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// the allocation is owned by the handle, and isn't tied to any thread
+unsafe impl Send for RecycledAlloc {}
+unsafe impl Sync for RecycledAlloc {}
+
+impl RecycledAlloc {
+    /// Drop all of the elements of the vector, and take its allocation
+    ///
+    /// If the vector doesn't have an allocation, then `None` is returned
+    pub fn new<T>(mut vec: Vec<T>) -> Option<Self> {
+        vec.clear();
+
+        let size = vec.capacity() * std::mem::size_of::<T>();
+
+        if size == 0 {
+            return None;
+        }
+
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+
+        unsafe {
+            Some(Self {
+                ptr: NonNull::new_unchecked(vec.as_mut_ptr() as *mut u8),
+                layout: Layout::from_size_align_unchecked(size, std::mem::align_of::<T>()),
+            })
+        }
+    }
+
+    /// The layout of the allocation
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Convert the allocation into an empty `Vec<U>`, if the allocation
+    /// can hold `U`s, see [`scale_capacity`](crate::scale_capacity)
+    ///
+    /// Otherwise the allocation is returned as is
+    pub fn into_vec<U>(self) -> Result<Vec<U>, Self> {
+        let capacity = match scale(1, self.layout, Layout::new::<U>()) {
+            Ok(capacity) => capacity,
+            Err(_) => return Err(self),
+        };
+
+        let this = std::mem::ManuallyDrop::new(self);
+
+        unsafe {
+            Ok(Vec::from_raw_parts(
+                this.ptr.as_ptr() as *mut U,
+                0,
+                capacity,
+            ))
+        }
+    }
+
+    /// Convert the allocation into an [`UninitBox`]
+    pub fn into_uninit_box(self) -> UninitBox {
+        let this = std::mem::ManuallyDrop::new(self);
+
+        unsafe { UninitBox::from_raw_parts(this.ptr, this.layout) }
+    }
+
+    /// Put the allocation into the thread-local [`RecycleBin`]
+    ///
+    /// If the thread-local bin is full or isn't available, then the allocation is freed
+    pub fn retire(self) {
+        let this = std::mem::ManuallyDrop::new(self);
+        let (ptr, layout) = (this.ptr, this.layout);
+
+        let deposited = LOCAL
+            .try_with(|bin| {
+                let mut bin = bin.try_borrow_mut().ok()?;
+                unsafe { bin.insert(ptr, layout) }
+                Some(())
+            })
+            .ok()
+            .flatten();
+
+        if deposited.is_none() {
+            unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+}
+
+impl Drop for RecycledAlloc {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use ::serde::de::{Deserialize, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+
+use crate::raw::convert_alloc;
+
+/// A `DeserializeSeed` that deserializes a sequence into the allocation of a donor vector
+///
+/// ```rust
+/// use serde::de::DeserializeSeed;
+/// use vec_utils::ReuseVec;
+///
+/// let mut vec = Vec::<u32>::with_capacity(16);
+/// let ptr = vec.as_ptr();
+///
+/// for message in ["[1, 2, 3]", "[4, 5]"] {
+///     let mut de = serde_json::Deserializer::from_str(message);
+///     vec = ReuseVec::new(vec).deserialize(&mut de).unwrap();
+///     assert_eq!(vec.as_ptr(), ptr);
+/// }
+///
+/// assert_eq!(vec, [4, 5]);
+/// ```
+pub struct ReuseVec<T> {
+    donor: Vec<T>,
+}
+
+impl<T> ReuseVec<T> {
+    /// Deserialize into the allocation of `donor`, the elements of `donor` are dropped
+    pub fn new(mut donor: Vec<T>) -> Self {
+        donor.clear();
+        Self { donor }
+    }
+
+    /// Deserialize into the allocation of `donor`, if the alignments of `T` and `U`
+    /// are the same and the size of the allocation in bytes is a multiple of the size
+    /// of `T`, see [`scale_capacity`](crate::scale_capacity). Otherwise, a new vector is
+    /// allocated. The elements of `donor` are dropped
+    pub fn from_alloc<U>(donor: Vec<U>) -> Self {
+        Self {
+            donor: convert_alloc(donor).unwrap_or_default(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for ReuseVec<T> {
+    type Value = Vec<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<T>, D::Error> {
+        deserializer.deserialize_seq(ReuseVisitor(self.donor, PhantomData))
+    }
+}
+
+struct ReuseVisitor<'de, T>(Vec<T>, PhantomData<&'de ()>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ReuseVisitor<'de, T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+        let mut vec = self.0;
+
+        // don't trust the size hint too much, it may come from untrusted input
+        if let Some(len) = seq.size_hint() {
+            vec.reserve(len.min(4096));
+        }
+
+        while let Some(value) = seq.next_element()? {
+            vec.push(value);
+        }
+
+        Ok(vec)
+    }
+}
@@ -0,0 +1,126 @@
+use crate::raw::convert_alloc;
+use crate::RecycleBin;
+
+/// Extension methods for sorting and reordering slices without allocating
+pub trait SliceSortExt {
+    /// The type that the slice stores
+    type T;
+
+    /// The same as `slice::sort_by_cached_key`, but the keys and indices are stored in the
+    /// allocation of `scratch`, which is returned empty so it can be used for the next sort
+    ///
+    /// The allocation of `scratch` is only used if it can hold `(K, usize)`s, see
+    /// [`scale_capacity`](crate::scale_capacity), and it's grown if it's too small
+    ///
+    /// ```rust
+    /// use vec_utils::SliceSortExt;
+    ///
+    /// let mut scratch = Vec::new();
+    ///
+    /// for frame in 0..3 {
+    ///     let mut words = vec!["ccc", "a", "bb"];
+    ///     scratch = words.sort_by_cached_key_with(|s| s.len(), scratch);
+    ///     assert_eq!(words, ["a", "bb", "ccc"]);
+    /// }
+    /// ```
+    fn sort_by_cached_key_with<K: Ord, F: FnMut(&Self::T) -> K, S>(
+        &mut self,
+        f: F,
+        scratch: Vec<S>,
+    ) -> Vec<(K, usize)>;
+
+    /// Reorder the slice so the element at `i` is the element that was at `perm[i]`,
+    /// i.e. apply the output of an argsort
+    ///
+    /// The elements are swapped along the cycles of the permutation, the bookkeeping
+    /// is taken from `bin` and deposited back into it afterwards
+    ///
+    /// # Panic
+    ///
+    /// If `perm` isn't a permutation of `0..self.len()`, the slice is unchanged
+    fn apply_permutation(&mut self, perm: &[usize], bin: &mut RecycleBin);
+}
+
+impl<T> SliceSortExt for [T] {
+    type T = T;
+
+    fn sort_by_cached_key_with<K: Ord, F: FnMut(&T) -> K, S>(
+        &mut self,
+        f: F,
+        scratch: Vec<S>,
+    ) -> Vec<(K, usize)> {
+        let len = self.len();
+        let mut indices = convert_alloc::<S, (K, usize)>(scratch).unwrap_or_default();
+
+        indices.reserve_exact(len);
+        indices.extend(self.iter().map(f).enumerate().map(|(i, key)| (key, i)));
+
+        // the indices are unique, so the sort is stable
+        indices.sort_unstable();
+
+        // the element that belongs at `i` may have been swapped away,
+        // so follow the swaps until it's found
+        for i in 0..len {
+            let mut index = indices[i].1;
+
+            while index < i {
+                index = indices[index].1;
+            }
+
+            indices[i].1 = index;
+            self.swap(i, index);
+        }
+
+        indices.clear();
+        indices
+    }
+
+    fn apply_permutation(&mut self, perm: &[usize], bin: &mut RecycleBin) {
+        const BITS: usize = usize::BITS as usize;
+
+        let len = self.len();
+        assert_eq!(
+            perm.len(),
+            len,
+            "the permutation must have the same length as the slice"
+        );
+
+        let words = len.div_ceil(BITS);
+        let mut visited = bin
+            .take::<usize>(words)
+            .unwrap_or_else(|| Vec::with_capacity(words));
+        visited.resize(words, 0);
+
+        for &index in perm {
+            let bit = 1 << (index % BITS);
+
+            match visited.get_mut(index / BITS) {
+                Some(word) if index < len && *word & bit == 0 => *word |= bit,
+                _ => panic!("{:?} isn't a permutation of 0..{}", perm, len),
+            }
+        }
+
+        // every bit is set, so each element is visited by clearing it's bit
+        for start in 0..len {
+            if visited[start / BITS] & (1 << (start % BITS)) == 0 {
+                continue;
+            }
+
+            let mut i = start;
+
+            loop {
+                visited[i / BITS] &= !(1 << (i % BITS));
+                let next = perm[i];
+
+                if next == start {
+                    break;
+                }
+
+                self.swap(i, next);
+                i = next;
+            }
+        }
+
+        bin.deposit(visited);
+    }
+}
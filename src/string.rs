@@ -0,0 +1,115 @@
+use std::ffi::{CString, NulError};
+use std::string::FromUtf8Error;
+
+use super::VecExt;
+
+/// Extension methods for `CString`
+pub trait CStringExt: Sized {
+    /// Map each byte of the string (not including the nul terminator),
+    /// reusing the allocation of the `CString`
+    ///
+    /// The nul terminator is written back into the slot it came from, so
+    /// this never reallocates. If `f` produces a nul byte, then the mapped
+    /// bytes are returned in the `NulError`
+    fn map_bytes<F: FnMut(u8) -> u8>(self, f: F) -> Result<CString, NulError>;
+
+    /// Map each byte of the string (not including the nul terminator) and
+    /// convert the result into a `String`, reusing the allocation of the `CString`
+    ///
+    /// If the mapped bytes are not valid utf-8, then they are returned in the
+    /// `FromUtf8Error`
+    fn map_into_string<F: FnMut(u8) -> u8>(self, f: F) -> Result<String, FromUtf8Error>;
+}
+
+impl CStringExt for CString {
+    fn map_bytes<F: FnMut(u8) -> u8>(self, f: F) -> Result<CString, NulError> {
+        // `into_bytes` pops the nul terminator, so there is always
+        // space left over to put it back
+        CString::new(self.into_bytes().map(f))
+    }
+
+    fn map_into_string<F: FnMut(u8) -> u8>(self, f: F) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.into_bytes().map(f))
+    }
+}
+
+/// Extension methods for `Vec<u8>`
+pub trait ByteVecExt: Sized {
+    /// Convert the bytes into a `CString`, reusing the allocation
+    ///
+    /// If the bytes already end with a nul terminator, it is used as is.
+    /// Otherwise the terminator is appended in place if there is spare capacity.
+    ///
+    /// Note that `CString` stores an exactly sized buffer, so if there is more
+    /// than one byte of spare capacity the allocation will be shrunk
+    ///
+    /// If there is an interior nul byte, then the bytes (without the
+    /// trailing terminator) are returned in the `NulError`
+    fn into_cstring(self) -> Result<CString, NulError>;
+}
+
+impl ByteVecExt for Vec<u8> {
+    fn into_cstring(mut self) -> Result<CString, NulError> {
+        if self.last() == Some(&0) {
+            // popping keeps the capacity, so `CString::new` can put the
+            // terminator back without reallocating
+            self.pop();
+        }
+
+        CString::new(self)
+    }
+}
+
+/// Extension methods for `Vec<String>`
+pub trait StringVecExt: Sized {
+    /// Map each string and join them with `sep` in between
+    ///
+    /// Instead of allocating a fresh string, the mapped string with the largest
+    /// capacity is used as the output buffer, and the rest are copied into it
+    fn join_map<F: FnMut(String) -> String>(self, sep: &str, f: F) -> String;
+}
+
+impl StringVecExt for Vec<String> {
+    fn join_map<F: FnMut(String) -> String>(self, sep: &str, f: F) -> String {
+        let mut strings = self.map(f);
+
+        let index = match strings.iter().enumerate().max_by_key(|(_, s)| s.capacity()) {
+            Some((index, _)) => index,
+            None => return String::new(),
+        };
+
+        let sep_len = sep.len() * (strings.len() - 1);
+        let len = strings.iter().map(String::len).sum::<usize>() + sep_len;
+        let prefix_len = strings[..index]
+            .iter()
+            .map(|s| s.len() + sep.len())
+            .sum::<usize>();
+
+        let mut buffer = std::mem::take(&mut strings[index]).into_bytes();
+        let old_len = buffer.len();
+
+        buffer.reserve_exact(len - old_len);
+
+        // move the chosen string into place, then fill in everything before it
+        buffer.resize(prefix_len + old_len, 0);
+        buffer.copy_within(..old_len, prefix_len);
+
+        let mut prefix = &mut buffer[..prefix_len];
+
+        for s in &strings[..index] {
+            for part in [s.as_bytes(), sep.as_bytes()] {
+                let (head, tail) = prefix.split_at_mut(part.len());
+                head.copy_from_slice(part);
+                prefix = tail;
+            }
+        }
+
+        for s in &strings[index + 1..] {
+            buffer.extend_from_slice(sep.as_bytes());
+            buffer.extend_from_slice(s.as_bytes());
+        }
+
+        // the buffer only contains whole strings
+        unsafe { String::from_utf8_unchecked(buffer) }
+    }
+}
@@ -0,0 +1,213 @@
+use ::thin_vec::{IntoIter, ThinVec};
+
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use crate::{cleanup, r#try, Output, Try, TupleElem};
+
+/// Extension methods for `ThinVec<T>`
+///
+/// The allocation of a `ThinVec<T>` is reused for the output if the
+/// allocation layouts of the input and output elements match
+pub trait ThinVecExt: Sized {
+    /// The type that the `ThinVec<T>` stores
+    type T;
+
+    /// Map a vector to another vector, will try and reuse the allocation if the
+    /// allocation layouts of the two types match, see [`VecExt::map`](crate::VecExt::map)
+    fn map<U, F: FnMut(Self::T) -> U>(self, mut f: F) -> ThinVec<U> {
+        use std::convert::Infallible;
+
+        match self.try_map(move |x| Ok::<_, Infallible>(f(x))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Map a vector to another vector, will try and reuse the allocation if the
+    /// allocation layouts of the two types match, see [`VecExt::try_map`](crate::VecExt::try_map)
+    fn try_map<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(
+        self,
+        f: F,
+    ) -> Result<ThinVec<U>, R::Error>;
+
+    /// Zip a vector to another vector and combine them, the larger allocation of the
+    /// inputs is reused if possible, see [`VecExt::zip_with`](crate::VecExt::zip_with)
+    fn zip_with<U, V, F: FnMut(Self::T, U) -> V>(self, other: ThinVec<U>, mut f: F) -> ThinVec<V> {
+        use std::convert::Infallible;
+
+        match self.try_zip_with(other, move |x, y| Ok::<_, Infallible>(f(x, y))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Zip a vector to another vector and combine them, the larger allocation of the
+    /// inputs is reused if possible, see [`VecExt::try_zip_with`](crate::VecExt::try_zip_with)
+    fn try_zip_with<U, V, R: Try<Ok = V>, F: FnMut(Self::T, U) -> R>(
+        self,
+        other: ThinVec<U>,
+        f: F,
+    ) -> Result<ThinVec<V>, R::Error>;
+}
+
+/// Reinterpret the allocation of `vec` as a `ThinVec<U>`
+///
+/// # Safety
+///
+/// `Layout::new::<T>() == Layout::new::<U>()`, and the length of `vec` must be zero
+unsafe fn cast<T, U>(vec: ThinVec<T>) -> ThinVec<U> {
+    debug_assert!(Layout::new::<T>() == Layout::new::<U>());
+    debug_assert!(vec.is_empty());
+
+    // `ThinVec` is a `repr(C)` pointer to the header, and the layout of the
+    // header and the elements only depends on the layout of the elements
+    std::mem::transmute_copy(&ManuallyDrop::new(vec))
+}
+
+/// Map the elements of `input` in place, the items that are passed to `f`
+/// are `read` from the allocation of `input`
+///
+/// # Safety
+///
+/// `Layout::new::<T>() == Layout::new::<V>()`
+unsafe fn map_in_place<T, V, R: Try<Ok = V>>(
+    mut input: ThinVec<T>,
+    mut read: impl FnMut(*mut T) -> R,
+) -> Result<ThinVec<V>, R::Error> {
+    struct Rest<T> {
+        ptr: *mut T,
+        index: usize,
+        len: usize,
+    }
+
+    impl<T> Drop for Rest<T> {
+        fn drop(&mut self) {
+            let &mut Rest { ptr, index, len } = self;
+
+            unsafe { cleanup::drop_slice(ptr.add(index), len - index) }
+        }
+    }
+
+    let len = input.len();
+    let ptr = input.as_mut_ptr();
+
+    // the elements are owned by `rest` until they are read
+    input.set_len(0);
+    let mut output = cast::<T, V>(input);
+
+    let mut rest = Rest { ptr, index: 0, len };
+
+    while rest.index < len {
+        let index = rest.index;
+        rest.index += 1;
+
+        let value = r#try!(read(ptr.add(index)));
+
+        ptr.add(index).cast::<V>().write(value);
+        output.set_len(index + 1);
+    }
+
+    Ok(output)
+}
+
+impl<T> ThinVecExt for ThinVec<T> {
+    type T = T;
+
+    fn try_map<U, R: Try<Ok = U>, F: FnMut(T) -> R>(
+        self,
+        mut f: F,
+    ) -> Result<ThinVec<U>, R::Error> {
+        if Layout::new::<T>() == Layout::new::<U>() {
+            unsafe { map_in_place(self, move |x| f(x.read())) }
+        } else {
+            self.into_iter().map(f).map(R::into_result).collect()
+        }
+    }
+
+    fn try_zip_with<U, V, R: Try<Ok = V>, F: FnMut(T, U) -> R>(
+        mut self,
+        mut other: ThinVec<U>,
+        mut f: F,
+    ) -> Result<ThinVec<V>, R::Error> {
+        let len = self.len().min(other.len());
+
+        let left = Layout::new::<T>() == Layout::new::<V>();
+        let right = Layout::new::<U>() == Layout::new::<V>();
+
+        if left && (!right || self.capacity() >= other.capacity()) {
+            self.truncate(len);
+            let mut other = other.into_iter();
+
+            unsafe {
+                map_in_place(self, move |x| match other.next() {
+                    Some(y) => f(x.read(), y),
+                    None => unreachable!(),
+                })
+            }
+        } else if right {
+            other.truncate(len);
+            let mut this = self.into_iter();
+
+            unsafe {
+                map_in_place(other, move |y| match this.next() {
+                    Some(x) => f(x, y.read()),
+                    None => unreachable!(),
+                })
+            }
+        } else {
+            self.into_iter()
+                .zip(other)
+                .map(move |(x, y)| f(x, y))
+                .map(R::into_result)
+                .collect()
+        }
+    }
+}
+
+unsafe impl<A> TupleElem for ThinVec<A> {
+    type Item = A;
+    type Data = IntoIter<A>;
+    type Iter = IntoIter<A>;
+
+    #[inline(always)]
+    fn capacity(data: &Self::Data) -> usize {
+        data.len()
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        ThinVec::len(self)
+    }
+
+    #[inline]
+    fn into_data(self) -> Self::Data {
+        self.into_iter()
+    }
+
+    #[inline]
+    fn into_iterator(self) -> Self::Iter {
+        self.into_iter()
+    }
+
+    /// The allocation of a `ThinVec<T>` can't be turned into a `Vec<T>`,
+    /// so it's never reused for the output
+    #[inline]
+    fn check_layout<V>() -> bool {
+        false
+    }
+
+    unsafe fn take_output<V>(_: &mut Self::Data) -> Output<V> {
+        unreachable!("the allocation of a `ThinVec<T>` can't be reused")
+    }
+
+    #[inline]
+    unsafe fn next_unchecked(data: &mut Self::Data) -> Self::Item {
+        data.next().unwrap_unchecked()
+    }
+
+    #[inline]
+    unsafe fn drop_rest(data: &mut Self::Data, _: usize) {
+        data.for_each(drop)
+    }
+}
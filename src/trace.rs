@@ -0,0 +1,148 @@
+//! Spans that describe how a transform picks the allocation of its output,
+//! so that unexpected allocations can be tracked down in production
+//!
+//! The spans are only emitted if the `tracing` feature is enabled, they are at the
+//! `DEBUG` level, and the fields are only computed if a subscriber is interested
+//!
+//! Each span has these fields
+//! * `len`: the number of elements that are expected in the output
+//! * `capacities`: the capacities of the inputs
+//! * `reused`: the index of the input whose allocation is reused, if any
+//! * `capacity`: the predicted capacity of the output
+//! * `rejections`: why the other inputs aren't reused, see [`Rejection`](crate::Rejection)
+//!
+//! If no allocation is reused, a `fallback` event with the capacity of the new
+//! allocation, and whether it was taken from the thread-local
+//! [`RecycleBin`](crate::RecycleBin) is emitted inside of the span
+
+// the element types are only needed to compute the fields
+#![cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+
+use crate::ReuseConfig;
+#[cfg(feature = "tracing")]
+use crate::ReusePlan;
+
+/// The span of a transform, it is entered until this is dropped
+pub(crate) struct TransformSpan {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+#[cfg(feature = "tracing")]
+fn enter(
+    span: tracing::Span,
+    capacities: &[usize],
+    plan: impl FnOnce() -> ReusePlan,
+) -> TransformSpan {
+    if !span.is_disabled() {
+        let plan = plan();
+
+        span.record("capacities", tracing::field::debug(capacities));
+        span.record("reused", tracing::field::debug(plan.reused()));
+        span.record("capacity", plan.capacity());
+        span.record("rejections", tracing::field::debug(plan.rejections()));
+    }
+
+    TransformSpan {
+        _span: span.entered(),
+    }
+}
+
+/// The span of a map over an input with the given length and capacity
+#[inline(always)]
+pub(crate) fn map<T, U>(len: usize, capacity: usize, config: &ReuseConfig) -> TransformSpan {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!(
+            "map",
+            len,
+            capacities = tracing::field::Empty,
+            reused = tracing::field::Empty,
+            capacity = tracing::field::Empty,
+            rejections = tracing::field::Empty,
+        );
+
+        enter(span, &[capacity], || {
+            ReusePlan::map_parts::<T, U>(len, capacity, *config)
+        })
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (len, capacity, config);
+        TransformSpan {}
+    }
+}
+
+/// The span of a zip of `len` pairs over inputs with the given capacities
+#[inline(always)]
+pub(crate) fn zip_with<T, U, V>(
+    len: usize,
+    capacities: [usize; 2],
+    config: &ReuseConfig,
+) -> TransformSpan {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!(
+            "zip_with",
+            len,
+            capacities = tracing::field::Empty,
+            reused = tracing::field::Empty,
+            capacity = tracing::field::Empty,
+            rejections = tracing::field::Empty,
+        );
+
+        enter(span, &capacities, || {
+            ReusePlan::zip_parts::<T, U, V>(len, capacities, *config)
+        })
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (len, capacities, config);
+        TransformSpan {}
+    }
+}
+
+/// The span of [`ReuseIter::collect_reuse`](crate::ReuseIter::collect_reuse) and its
+/// variants, `len` is the predicted length of the output
+#[inline(always)]
+pub(crate) fn collect_reuse<T, U>(
+    len: usize,
+    capacity: usize,
+    hint: Option<usize>,
+    config: &ReuseConfig,
+) -> TransformSpan {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!(
+            "collect_reuse",
+            len,
+            hint = tracing::field::debug(hint),
+            capacities = tracing::field::Empty,
+            reused = tracing::field::Empty,
+            capacity = tracing::field::Empty,
+            rejections = tracing::field::Empty,
+        );
+
+        enter(span, &[capacity], || {
+            ReusePlan::map_parts::<T, U>(len, capacity, *config)
+        })
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (len, capacity, hint, config);
+        TransformSpan {}
+    }
+}
+
+/// Called when the output is allocated because no input allocation was reused
+#[inline(always)]
+pub(crate) fn fallback(capacity: usize, recycled: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(capacity, recycled, "fallback");
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (capacity, recycled);
+}
@@ -1,3 +1,5 @@
+use std::convert::Infallible;
+use std::fmt;
 use std::task::Poll;
 
 /// A stable version of [`core::ops::Try`].
@@ -38,8 +40,23 @@ impl<T, E> Try for Result<T, E> {
 }
 
 /// The error type that results from applying the try operator (`?`) to a `None` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct NoneError;
 
+impl fmt::Display for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected a `Some` value, but found `None`")
+    }
+}
+
+impl std::error::Error for NoneError {}
+
+impl From<Infallible> for NoneError {
+    fn from(x: Infallible) -> Self {
+        match x {}
+    }
+}
+
 impl<T> Try for Option<T> {
     type Ok = T;
     type Error = NoneError;
@@ -1,12 +1,86 @@
 use std::alloc::Layout;
+use std::cmp::Ordering;
+use std::hash::Hash;
 use std::marker::PhantomData;
-use std::mem::ManuallyDrop;
-
-use super::{r#try, Try};
-
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Add, RangeBounds};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::raw::RawAllocGuard;
+use super::{
+    cleanup, faults, r#try, reuse_config, trace, RawParts, RecycleBin, RecycledAlloc, ReuseConfig,
+    ReuseIter, Source, Try,
+};
+
+mod builder;
+mod cartesian;
+mod chunks;
+mod exact;
+mod expand;
+mod falloc;
+mod feed;
+mod flat_map;
+mod flatten;
+#[cfg(feature = "futures")]
+mod future;
+mod gather;
 mod general_zip;
-
+mod group;
+mod histogram;
+mod in_place;
+mod interleave;
+mod intersperse;
+mod join;
+mod leak_guard;
+mod longest;
+mod mapper;
+mod merge;
+mod option;
+mod pairs;
+mod repartition;
+mod result;
+mod resumable;
+mod rev;
+mod rle;
+mod scan;
+#[cfg(feature = "simd")]
+mod simd;
+mod spare;
+mod strided;
+mod take;
+mod threads;
+mod transparent;
+mod transpose;
+mod unbox;
+mod uninit;
+
+pub use builder::*;
+pub use falloc::*;
+pub use feed::*;
+pub use flatten::*;
+#[cfg(feature = "futures")]
+pub use future::*;
+pub use gather::*;
 pub use general_zip::*;
+pub use group::Drain;
+pub use leak_guard::*;
+pub use longest::*;
+pub use mapper::*;
+pub use option::*;
+pub use pairs::OddPolicy;
+pub use repartition::*;
+pub use result::*;
+pub use resumable::*;
+pub use rle::*;
+#[cfg(feature = "simd")]
+pub use simd::*;
+pub use spare::*;
+pub use transparent::*;
+pub use unbox::*;
+pub use uninit::*;
+
+pub(crate) use exact::SliceGuard;
 
 /// A type that contains useful meta-data about a
 /// the Vec<_> that it was created from
@@ -25,6 +99,14 @@ pub struct Input<T> {
 
     drop_alloc: bool,
     drop: PhantomData<T>,
+
+    // the number of elements read, to catch reads past the end
+    #[cfg(debug_assertions)]
+    reads: usize,
+
+    // set once the rest of the input is dropped, to catch use after free
+    #[cfg(debug_assertions)]
+    dropped: bool,
 }
 
 /// An write only buffer that may overlap with some input buffer
@@ -40,19 +122,142 @@ pub struct Output<T> {
     // the capacity of the vec data segment
     cap: usize,
     drop: PhantomData<T>,
+
+    // the number of elements written, to catch writes past the capacity
+    #[cfg(debug_assertions)]
+    writes: usize,
 }
 
 impl<T> Output<T> {
     /// Create a new output buffer, this buffer will own it's data segment
     /// from `start` to `start.add(cap)`
+    ///
+    /// # Safety
+    ///
+    /// `start` must point to an allocation that can be freed as a `Vec<T>`
+    /// with capacity `cap`, and the returned `Output` takes ownership of it
     pub unsafe fn new(start: *mut T, cap: usize) -> Self {
         Self {
             start,
             ptr: start,
             cap,
             drop: PhantomData,
+            #[cfg(debug_assertions)]
+            writes: 0,
         }
     }
+
+    /// Write the next element of the output
+    ///
+    /// # Safety
+    ///
+    /// This must be called at most `cap` times
+    ///
+    /// # Panic
+    ///
+    /// With `debug_assertions` enabled, if this is called more than `cap` times
+    #[inline]
+    pub(crate) unsafe fn write(&mut self, value: T) {
+        #[cfg(debug_assertions)]
+        {
+            assert!(
+                self.writes < self.cap,
+                "wrote past the capacity of the output ({})",
+                self.cap
+            );
+            self.writes += 1;
+        }
+
+        self.ptr.write(value);
+        self.ptr = self.ptr.add(1);
+    }
+}
+
+impl<T> Input<T> {
+    /// The capacity of the data segment
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Read the next element of the input
+    ///
+    /// # Safety
+    ///
+    /// This must be called at most `len` times, and not after `drop_rest`
+    ///
+    /// # Panic
+    ///
+    /// With `debug_assertions` enabled, if this is called more than `len` times
+    /// or after `drop_rest`
+    #[inline]
+    pub unsafe fn next_unchecked(&mut self) -> T {
+        #[cfg(debug_assertions)]
+        {
+            assert!(!self.dropped, "read from the input after it was dropped");
+            assert!(
+                self.reads < self.len,
+                "read past the end of the input ({})",
+                self.len
+            );
+            self.reads += 1;
+        }
+
+        let ptr = self.ptr;
+        self.ptr = self.ptr.add(1);
+        ptr.read()
+    }
+
+    /// Take the allocation of the input to write the output into,
+    /// afterwards the input won't free it's allocation
+    ///
+    /// # Safety
+    ///
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<V>::new()`
+    /// and this must be called at most once
+    ///
+    /// # Panic
+    ///
+    /// With `debug_assertions` enabled, if the layouts don't match
+    /// or the output was already taken
+    #[inline]
+    pub unsafe fn take_output<V>(&mut self) -> Output<V> {
+        debug_assert!(Layout::new::<T>() == Layout::new::<V>());
+        debug_assert!(self.drop_alloc, "the output was taken twice");
+
+        self.drop_alloc = false;
+        Output::new(self.start as *mut V, self.cap)
+    }
+
+    /// Drop the elements that were not read, and free the
+    /// allocation if the output wasn't taken
+    ///
+    /// # Safety
+    ///
+    /// `len` must be the number of elements that were read, and
+    /// this must be called at most once
+    ///
+    /// # Panic
+    ///
+    /// With `debug_assertions` enabled, if `len` is not the number
+    /// of elements read, or this was already called
+    pub unsafe fn drop_rest(&mut self, len: usize) {
+        #[cfg(debug_assertions)]
+        {
+            assert!(!self.dropped, "the input was dropped twice");
+            assert_eq!(
+                self.reads, len,
+                "dropped the input after reading {} elements, but expected {}",
+                self.reads, len
+            );
+            self.dropped = true;
+        }
+
+        let _alloc = self
+            .drop_alloc
+            .then(|| RawAllocGuard::new(self.start, self.cap));
+
+        cleanup::drop_slice(self.ptr, self.len - len);
+    }
 }
 
 impl<T> From<Vec<T>> for Input<T> {
@@ -68,11 +273,169 @@ impl<T> From<Vec<T>> for Input<T> {
             cap: vec.capacity(),
             drop_alloc: true,
             drop: PhantomData,
+            #[cfg(debug_assertions)]
+            reads: 0,
+            #[cfg(debug_assertions)]
+            dropped: false,
+        }
+    }
+}
+
+/// The raw parts of an [`Input`], with a C-compatible layout
+///
+/// This allows suspending a transform, handing the buffer across an FFI
+/// boundary, and resuming it later with [`Input::from_raw_parts`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawInput<T> {
+    /// The start of the allocation
+    pub start: *mut T,
+    /// The next element to read
+    pub ptr: *mut T,
+    /// The number of initialized elements, starting at `start`
+    pub len: usize,
+    /// The capacity of the allocation
+    pub cap: usize,
+    /// The number of elements that were read, for zero-sized types
+    /// this is only tracked with `debug_assertions` enabled
+    pub read: usize,
+    /// If the input is responsible for freeing the allocation
+    pub owns_alloc: bool,
+}
+
+/// The raw parts of an [`Output`], with a C-compatible layout
+///
+/// This allows suspending a transform, handing the buffer across an FFI
+/// boundary, and resuming it later with [`Output::from_raw_parts`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawOutput<T> {
+    /// The start of the allocation
+    pub start: *mut T,
+    /// The next element to write
+    pub ptr: *mut T,
+    /// The capacity of the allocation
+    pub cap: usize,
+}
+
+impl<T> Input<T> {
+    /// Decompose the input into it's raw parts, the caller takes over
+    /// the ownership of the unread elements and the allocation
+    pub fn into_raw_parts(self) -> RawInput<T> {
+        #[cfg(debug_assertions)]
+        let read = self.reads;
+        #[cfg(not(debug_assertions))]
+        let read = match std::mem::size_of::<T>() {
+            0 => 0,
+            _ => unsafe { self.ptr.offset_from(self.start) as usize },
+        };
+
+        RawInput {
+            start: self.start,
+            ptr: self.ptr,
+            len: self.len,
+            cap: self.cap,
+            read,
+            owns_alloc: self.drop_alloc,
+        }
+    }
+
+    /// Rebuild an input from it's raw parts
+    ///
+    /// # Safety
+    ///
+    /// `raw` must have come from [`Input::into_raw_parts`], and the elements
+    /// from `ptr` up to `len` must still be initialized and not read since
+    pub unsafe fn from_raw_parts(raw: RawInput<T>) -> Self {
+        Self {
+            start: raw.start,
+            ptr: raw.ptr,
+            len: raw.len,
+            cap: raw.cap,
+            drop_alloc: raw.owns_alloc,
+            drop: PhantomData,
+            #[cfg(debug_assertions)]
+            reads: raw.read,
+            #[cfg(debug_assertions)]
+            dropped: false,
         }
     }
 }
 
+impl<T> Output<T> {
+    /// Decompose the output into it's raw parts, the caller takes over
+    /// the ownership of the written elements and the allocation
+    pub fn into_raw_parts(self) -> RawOutput<T> {
+        RawOutput {
+            start: self.start,
+            ptr: self.ptr,
+            cap: self.cap,
+        }
+    }
+
+    /// Rebuild an output from it's raw parts
+    ///
+    /// # Safety
+    ///
+    /// `raw` must have come from [`Output::into_raw_parts`], the elements from
+    /// `start` up to `ptr` must be initialized, and `ptr` can't be past `cap`
+    pub unsafe fn from_raw_parts(raw: RawOutput<T>) -> Self {
+        Self {
+            start: raw.start,
+            ptr: raw.ptr,
+            cap: raw.cap,
+            drop: PhantomData,
+            #[cfg(debug_assertions)]
+            writes: match std::mem::size_of::<T>() {
+                0 => 0,
+                _ => raw.ptr.offset_from(raw.start) as usize,
+            },
+        }
+    }
+
+    /// Finish the output, the written elements become the elements of the vector
+    ///
+    /// # Safety
+    ///
+    /// The input that the output was taken from must not be used afterwards,
+    /// other than to drop it's remaining elements with [`Input::drop_rest`]. For
+    /// zero-sized types, use `Vec::from_raw_parts` with the number of elements written
+    pub unsafe fn into_vec(self) -> Vec<T> {
+        Vec::from_raw_parts(self.start, self.len(), self.cap)
+    }
+
+    /// The number of elements written, this is always zero for zero-sized types
+    pub(crate) fn len(&self) -> usize {
+        match std::mem::size_of::<T>() {
+            0 => 0,
+            _ => unsafe { self.ptr.offset_from(self.start) as usize },
+        }
+    }
+
+    /// Reserve capacity for at least `additional` more elements,
+    /// this may move the allocation to grow it
+    ///
+    /// # Safety
+    ///
+    /// The output must own the whole allocation, i.e. no input may read from it anymore,
+    /// and `T` must not be a zero-sized type
+    pub(crate) unsafe fn reserve(&mut self, additional: usize) {
+        debug_assert!(std::mem::size_of::<T>() != 0);
+
+        let len = self.len();
+        let mut vec = ManuallyDrop::new(Vec::from_raw_parts(self.start, len, self.cap));
+        vec.reserve(additional);
+
+        self.start = vec.as_mut_ptr();
+        self.ptr = self.start.add(len);
+        self.cap = vec.capacity();
+    }
+}
+
 /// Extension methods for `Vec<T>`
+///
+/// This is implemented for every container that implements [`RawParts`],
+/// the output is always a `Vec<_>`
 pub trait VecExt: Sized {
     /// The type that the `Vec<T>` stores
     type T;
@@ -99,6 +462,65 @@ pub trait VecExt: Sized {
     /// and the rest of the input vector. Thre error will be returned as a `Result`
     fn try_map<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(self, f: F) -> Result<Vec<U>, R::Error>;
 
+    /// The same as `VecExt::map`, but the mapping function takes a reference
+    ///
+    /// Each element is dropped right after the mapping function returns
+    fn map_ref<U, F: FnMut(&Self::T) -> U>(self, mut f: F) -> Vec<U> {
+        self.map(move |x| f(&x))
+    }
+
+    /// The same as `VecExt::try_map`, but the mapping function takes a reference
+    ///
+    /// Each element is dropped right after the mapping function returns
+    fn try_map_ref<U, R: Try<Ok = U>, F: FnMut(&Self::T) -> R>(
+        self,
+        mut f: F,
+    ) -> Result<Vec<U>, R::Error> {
+        self.try_map(move |x| f(&x))
+    }
+
+    /// The same as `VecExt::map_ref`, but `inspect` is called with the index,
+    /// the input, and the output after each element is mapped
+    ///
+    /// This is useful for debugging, and if `inspect` does nothing it compiles
+    /// down to the same code as `VecExt::map_ref`
+    fn map_inspect<U, F: FnMut(&Self::T) -> U, I: FnMut(usize, &Self::T, &U)>(
+        self,
+        mut f: F,
+        mut inspect: I,
+    ) -> Vec<U> {
+        let mut index = 0;
+
+        self.map(move |x| {
+            let y = f(&x);
+            inspect(index, &x, &y);
+            index += 1;
+            y
+        })
+    }
+
+    /// The same as `VecExt::zip_with`, but the combining function takes references
+    /// and `inspect` is called with the index, the inputs, and the output after each
+    /// pair of elements is combined
+    ///
+    /// This is useful for debugging, and if `inspect` does nothing it compiles
+    /// down to the same code as `VecExt::zip_with`
+    fn zip_inspect<U, V, F: FnMut(&Self::T, &U) -> V, I: FnMut(usize, (&Self::T, &U), &V)>(
+        self,
+        other: Vec<U>,
+        mut f: F,
+        mut inspect: I,
+    ) -> Vec<V> {
+        let mut index = 0;
+
+        self.zip_with(other, move |x, y| {
+            let z = f(&x, &y);
+            inspect(index, (&x, &y), &z);
+            index += 1;
+            z
+        })
+    }
+
     /// Zip a vector to another vector and combine them, the result will be returned,
     /// the allocation will be reused if possible, the larger allocation of the input vectors
     /// will be used if all of `T`, `U`, and `V` have the same allocation layouts.
@@ -128,72 +550,1002 @@ pub trait VecExt: Sized {
     ///
     /// if layouts are not compatible, then return `Vec::new()`
     fn drop_and_reuse<U>(self) -> Vec<U>;
+
+    /// Map the elements of the vector in the given range in place
+    ///
+    /// Each element is moved out of the vector, passed to `f`, and the result is
+    /// written back into the same slot. If `f` panics, the element that was being
+    /// mapped is lost and the rest of the vector is shifted down to close the gap.
+    ///
+    /// # Panic
+    ///
+    /// if the range is out of bounds then this function will panic
+    fn map_range<R: RangeBounds<usize>, F: FnMut(Self::T) -> Self::T>(&mut self, range: R, f: F);
+
+    /// Replace the given range of the vector with the `replacement` vector, and map
+    /// the rest of the elements, will try and reuse the allocation if the
+    /// allocation layouts of the two types match, i.e. if
+    /// `std::alloc::Layout::<T>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused
+    ///
+    /// The elements in the range are dropped, and the allocation of `replacement` is freed
+    ///
+    /// # Panic
+    ///
+    /// if the range is out of bounds then this function will panic
+    fn splice_map<U, R: RangeBounds<usize>, F: FnMut(Self::T) -> U>(
+        self,
+        range: R,
+        replacement: Vec<U>,
+        f: F,
+    ) -> Vec<U>;
+
+    /// Filter and map the elements of the vector in place
+    ///
+    /// Each element is moved out of the vector and passed to `f`, if `f` returns
+    /// `Some` then the new value is kept, otherwise it is removed. The kept elements
+    /// are compacted to the front of the vector, keeping their order.
+    ///
+    /// If `f` panics, the element that was being mapped is lost, but
+    /// all other elements are kept
+    fn retain_map<F: FnMut(Self::T) -> Option<Self::T>>(&mut self, f: F);
+
+    /// Convert the vector into an iterator that remembers it's allocation
+    ///
+    /// The `map`, `filter`, and `zip` combinators of [`ReuseIter`] keep track of the
+    /// allocation, so that [`ReuseIter::collect_reuse`] can write the output into it
+    fn into_reuse_iter(self) -> ReuseIter<Source<Self::T>>;
+
+    /// Drops all of the values in the vector and deposits the
+    /// allocation into the thread-local [`RecycleBin`]
+    ///
+    /// If the thread-local bin is full or isn't available (i.e. it is in use,
+    /// or being destroyed), then the allocation is freed
+    fn retire(self);
+
+    /// Transpose the row-major `rows` by `cols` matrix stored in the vector, in place
+    ///
+    /// This follows the permutation cycles, so it only needs one bit
+    /// of scratch space per element
+    ///
+    /// # Panic
+    ///
+    /// if `rows * cols` is not the length of the vector
+    fn transpose_in_place(&mut self, rows: usize, cols: usize);
+
+    /// Transpose the row-major `rows` by `cols` matrix stored in the vector, by
+    /// moving the elements into `scratch` and swapping the vectors
+    ///
+    /// Afterwards `scratch` is empty and holds the old allocation, so that it
+    /// can be reused for the next transpose
+    ///
+    /// # Panic
+    ///
+    /// if `rows * cols` is not the length of the vector
+    fn transpose_with(&mut self, rows: usize, cols: usize, scratch: &mut Vec<Self::T>);
+
+    /// Interleave the elements of the two vectors, i.e. `a0, b0, a1, b1, ...`
+    ///
+    /// Once the shorter vector runs out, the rest of the longer vector is appended.
+    /// This reuses the allocation of whichever vector has enough capacity to hold
+    /// both, and otherwise grows the allocation of `self`
+    fn interleave(self, other: Vec<Self::T>) -> Vec<Self::T>;
+
+    /// Map the vector into an exactly sized boxed slice, will try and reuse the
+    /// allocation if the output elements fit into the input elements, i.e. if
+    /// `std::mem::align_of::<T>() == std::mem::align_of::<U>()` and
+    /// `std::mem::size_of::<T>() >= std::mem::size_of::<U>()`
+    ///
+    /// When reusing, the elements are mapped in place and then the allocation
+    /// is shrunk with `realloc`, which can often be done without copying.
+    /// Otherwise a new allocation of exactly the right size is made
+    fn map_into_boxed_slice<U, F: FnMut(Self::T) -> U>(self, f: F) -> Box<[U]>;
+
+    /// Map the vector directly into the allocation of an `Arc<[U]>`
+    ///
+    /// The allocation of the vector can't be reused, because it doesn't have
+    /// space for the reference counts, but unlike `vec.map(f).into()` the mapped
+    /// elements are only written once
+    fn map_into_shared<U, F: FnMut(Self::T) -> U>(self, f: F) -> Arc<[U]>;
+
+    /// Map the vector directly into the allocation of an `Rc<[U]>`
+    ///
+    /// See [`VecExt::map_into_shared`] for details
+    fn map_into_rc<U, F: FnMut(Self::T) -> U>(self, f: F) -> Rc<[U]>;
+
+    /// Zip the two vectors into the provided buffer, without allocating
+    ///
+    /// This writes up to `out.len()` elements, and returns the number of elements
+    /// written. All other elements of the two vectors are dropped.
+    ///
+    /// If `f` panics, then the elements already written into `out` are dropped
+    fn zip_with_into_slice<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        out: &mut [MaybeUninit<V>],
+        f: F,
+    ) -> usize;
+
+    /// Combine the elements of the two vectors pairwise, writing each component
+    /// of the result back into the slot it came from
+    ///
+    /// If one vector is longer than the other, the extra elements are left untouched.
+    /// If `f` panics, the pair of elements that was being combined is lost and the rest
+    /// of both vectors is shifted down to close the gap.
+    fn zip_with_swap<U, F: FnMut(Self::T, U) -> (Self::T, U)>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> (Vec<Self::T>, Vec<U>);
+
+    /// Map each element of the vector to `K` elements, will try and reuse the
+    /// allocation if it is large enough to hold the output, i.e. if
+    /// `std::mem::align_of::<T>() == std::mem::align_of::<U>()` and the capacity
+    /// of the vector in bytes is enough to hold `K * len` elements of `U`
+    ///
+    /// If `K * size_of::<U>() <= size_of::<T>()` the elements are expanded front-to-back,
+    /// otherwise they are expanded back-to-front, so no unread element is overwritten
+    fn map_expand<U, F: FnMut(Self::T) -> [U; K], const K: usize>(self, f: F) -> Vec<U>;
+
+    /// Mutate each element of the vector in place, stopping at the first failure
+    ///
+    /// On failure, `undo` is called on every element that was already mutated,
+    /// in reverse order, before the error is returned. The element that `f` failed
+    /// on is not passed to `undo`, so `f` must leave it unchanged on failure.
+    ///
+    /// If `f` or `undo` panics, nothing is rolled back
+    fn try_map_mut<R: Try<Ok = ()>, F: FnMut(&mut Self::T) -> R, G: FnMut(&mut Self::T)>(
+        &mut self,
+        f: F,
+        undo: G,
+    ) -> Result<(), R::Error>;
+
+    /// The same as `VecExt::map`, but if the allocation can't be reused,
+    /// the new allocation is made with `Vec::try_reserve_exact`, and
+    /// an [`AllocError`] is returned instead of aborting on failure
+    ///
+    /// No elements are mapped if the allocation fails. The process-wide [`ReuseConfig`]
+    /// decides if the allocation is reused, but the output is never shrunk
+    fn try_map_falloc<U, F: FnMut(Self::T) -> U>(self, f: F) -> Result<Vec<U>, AllocError>;
+
+    /// The same as `VecExt::zip_with`, but if neither allocation can be reused,
+    /// the new allocation is made with `Vec::try_reserve_exact`, and
+    /// an [`AllocError`] is returned instead of aborting on failure
+    ///
+    /// No elements are zipped if the allocation fails. The process-wide [`ReuseConfig`]
+    /// decides if an allocation is reused, but the output is never shrunk
+    fn try_zip_with_falloc<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Result<Vec<V>, AllocError>;
+
+    /// The same as `VecExt::map`, but the elements are mapped from the back to the front
+    ///
+    /// The output is in the same order as the input, only the order in which
+    /// the mapping function is called is reversed. If the mapping function panics,
+    /// the output that was already created is dropped before the rest of the input
+    fn map_rev<U, F: FnMut(Self::T) -> U>(self, f: F) -> Vec<U>;
+
+    /// The same as `VecExt::zip_with`, but the elements are combined from the back to the front
+    ///
+    /// The excess elements of the longer vector are dropped first, then the output
+    /// is in the same order as the inputs, only the order in which the function is
+    /// called is reversed
+    fn zip_with_rev<U, V, F: FnMut(Self::T, U) -> V>(self, other: Vec<U>, f: F) -> Vec<V>;
+
+    /// Map the first `n` elements of the vector, and return the
+    /// rest of the elements untouched
+    ///
+    /// If the allocation layouts of `T` and `U` match, then the output reuses
+    /// the allocation, and the rest are moved into a new vector. Otherwise,
+    /// the rest are moved to the front of the allocation, and the output
+    /// is a new vector. If `n` is larger than the length of the vector,
+    /// all of the elements are mapped
+    fn take_map<U, F: FnMut(Self::T) -> U>(self, n: usize, f: F) -> (Vec<U>, Vec<Self::T>);
+
+    /// The same as `VecExt::zip_with`, but the elements are split into `num_threads`
+    /// chunks, which are processed in parallel on scoped threads
+    ///
+    /// The allocation of either vector is reused if it's layout matches the output.
+    /// If `num_threads` is 0, then `std::thread::available_parallelism` is used.
+    /// If the function panics on any thread, all of the elements are dropped and
+    /// the panic is resumed after all of the threads finish
+    fn zip_with_threads<U: Send, V: Send, F: Fn(Self::T, U) -> V + Sync>(
+        self,
+        other: Vec<U>,
+        f: F,
+        num_threads: usize,
+    ) -> Vec<V>
+    where
+        Self::T: Send;
+
+    /// Combine two vectors over the length of the longer one, the function gets
+    /// `EitherOrBoth::Both` while both vectors have elements, and then `Left` or
+    /// `Right` for the rest of the longer vector
+    ///
+    /// The allocation of the longer vector is reused if it's layout matches the output.
+    /// Otherwise the shorter vector is reused if it's layout matches and it has
+    /// enough capacity
+    fn zip_longest_with<U, V, F: FnMut(EitherOrBoth<Self::T, U>) -> V>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<V>;
+
+    /// Merge two vectors that are sorted by `cmp` into one sorted vector
+    ///
+    /// The merge is stable, so elements of `self` come before equal elements of `other`.
+    /// The allocation of the longer vector is reused if it has enough capacity for
+    /// both vectors, otherwise the shorter one is, and if neither fits then the
+    /// output is a new vector
+    fn merge_with<F: FnMut(&Self::T, &Self::T) -> Ordering>(
+        self,
+        other: Vec<Self::T>,
+        cmp: F,
+    ) -> Vec<Self::T>;
+
+    /// The same as `VecExt::merge_with`, but the vectors are sorted by the key from `f`
+    fn merge_by_key<K: Ord, F: FnMut(&Self::T) -> K>(
+        self,
+        other: Vec<Self::T>,
+        f: F,
+    ) -> Vec<Self::T>;
+
+    /// Map each element with `f`, and put an element from `sep_fn` between each
+    /// pair of outputs, so `n` elements become `2n - 1`
+    ///
+    /// The allocation is reused if it has enough bytes for the output, the alignments
+    /// of `T` and `U` match, and `U` is at least half the size of `T`, otherwise
+    /// the output is allocated once. The output is filled from the back to the
+    /// front, so the functions are called in reverse order
+    fn intersperse_map<U, S: FnMut() -> U, F: FnMut(Self::T) -> U>(self, sep_fn: S, f: F)
+        -> Vec<U>;
+
+    /// Combine every `stride`-th element of the vector, starting at `col`, with the
+    /// elements of `other`, and write the results back in their place
+    ///
+    /// This transforms one column of row-major data that has `stride` columns. The
+    /// other elements are untouched and the allocation is always reused. If `other`
+    /// has more elements than the column, the excess elements are dropped, and if it
+    /// has fewer, the rest of the column is untouched
+    ///
+    /// # Panic
+    ///
+    /// If `col` isn't less than `stride`
+    fn zip_column_with<U, F: FnMut(Self::T, U) -> Self::T>(
+        self,
+        stride: usize,
+        col: usize,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<Self::T>;
+
+    /// Map every `stride`-th element of the vector, starting at `col`,
+    /// and write the results back in their place
+    ///
+    /// # Panic
+    ///
+    /// If `col` isn't less than `stride`
+    fn map_column<F: FnMut(Self::T) -> Self::T>(
+        self,
+        stride: usize,
+        col: usize,
+        f: F,
+    ) -> Vec<Self::T>;
+
+    /// Map the elements at `indices`, in the order of `indices`, so the output
+    /// is `f(vec[indices[i]])` for each `i`
+    ///
+    /// The elements that aren't in `indices` are dropped. Each index may only appear
+    /// once, otherwise an error is returned and the vector is dropped, as is the case
+    /// if an index is out of bounds. The allocation is reused if the layouts of
+    /// `T` and `U` match, the elements are moved along the cycles of the gather
+    fn gather_map<U, F: FnMut(Self::T) -> U>(
+        self,
+        indices: &[usize],
+        f: F,
+    ) -> Result<Vec<U>, GatherError>;
+
+    /// Combine the elements of two vectors that have the same key, instead of the
+    /// same position
+    ///
+    /// The `k`-th element of `self` with some key is paired with the `k`-th element of
+    /// `other` with the same key, and the elements that aren't paired are dropped. The
+    /// output is in the order of `self`. A hash index is built over the shorter vector,
+    /// and the allocation of `self` is reused if it's layout matches the output
+    fn join_with<U, K: Hash + Eq, V, KA, KB, F>(
+        self,
+        other: Vec<U>,
+        key_a: KA,
+        key_b: KB,
+        f: F,
+    ) -> Vec<V>
+    where
+        KA: FnMut(&Self::T) -> K,
+        KB: FnMut(&U) -> K,
+        F: FnMut(Self::T, U) -> V;
+
+    /// Combine each element of the vector with each element of `other`, the output
+    /// is in row-major order, so `f(&self[i], &other[j])` is at `i * other.len() + j`
+    ///
+    /// The allocation of either vector is reused if it has enough bytes for the whole
+    /// output and the alignments match, and the outputs are large enough to not
+    /// overwrite the inputs that are still needed. Otherwise the output is allocated
+    /// once. The output is filled from the back to the front, so the function is
+    /// called in reverse order
+    fn cartesian_with<U, V, F: FnMut(&Self::T, &U) -> V>(self, other: Vec<U>, f: F) -> Vec<V>;
+
+    /// Run-length encode the vector, each run of equal elements becomes the first
+    /// element of the run and the length of the run, the rest of the run is dropped
+    ///
+    /// The allocation is reused if it can hold `(T, usize)`s, see [`scale_capacity`](crate::scale_capacity),
+    /// and the runs are long enough that each output ends before the next run starts.
+    /// If a run doesn't fit, the runs so far are moved into a new allocation
+    fn rle_encode(self) -> Vec<(Self::T, usize)>
+    where
+        Self::T: PartialEq;
+
+    /// Combine each pair of adjacent elements, so the vector is halved, the last
+    /// element of an odd length vector is kept as is or dropped, depending on `odd`
+    ///
+    /// The results are written to the front of the allocation, which is always reused,
+    /// so a tree reduction only allocates once
+    fn reduce_pairs<F: FnMut(Self::T, Self::T) -> Self::T>(
+        self,
+        odd: OddPolicy,
+        f: F,
+    ) -> Vec<Self::T>;
+
+    /// Map each element along with an accumulator, `f` gets the current accumulator
+    /// and the element, and returns the next accumulator and the output
+    ///
+    /// Will try and reuse the allocation if the allocation layouts match, the same as
+    /// `VecExt::map`
+    fn scan_in_place<U, Acc, F: FnMut(&Acc, Self::T) -> (Acc, U)>(self, init: Acc, f: F) -> Vec<U>;
+
+    /// Replace each element with the sum of it and all of the elements before it,
+    /// the allocation is always reused
+    fn prefix_sum(self) -> Vec<Self::T>
+    where
+        Self::T: Copy + Add<Output = Self::T>;
+
+    /// Replace each element with the sum of all of the elements before it, the first
+    /// element becomes `T::default()`, the allocation is always reused
+    fn exclusive_prefix_sum(self) -> Vec<Self::T>
+    where
+        Self::T: Copy + Default + Add<Output = Self::T>;
+
+    /// Count how many elements fall into each bucket, the elements are dropped
+    /// as they are counted
+    ///
+    /// The allocation is reused for the counts if it can hold `usize`s, see
+    /// [`scale_capacity`](crate::scale_capacity), and the counts fit in it's
+    /// spare capacity. Otherwise the counts are allocated
+    ///
+    /// # Panic
+    ///
+    /// If `f` returns a bucket that isn't less than `buckets`
+    fn histogram<F: FnMut(&Self::T) -> usize>(self, buckets: usize, f: F) -> Vec<usize>;
+
+    /// Count how many elements fall into each bucket, adding to the existing counts,
+    /// the elements are dropped as they are counted
+    ///
+    /// # Panic
+    ///
+    /// If `f` returns a bucket that isn't less than `counts.len()`
+    fn count_into<F: FnMut(&Self::T) -> usize>(self, counts: &mut [usize], f: F);
+
+    /// Map each chunk of `N` elements by value, and pass the last elements that
+    /// don't fill a chunk to `rem` as a vector, if there are any
+    ///
+    /// The allocation is reused if it can hold the outputs, see
+    /// [`scale_capacity`](crate::scale_capacity), and `U` isn't larger than a chunk
+    ///
+    /// # Panic
+    ///
+    /// If `N` is zero
+    fn chunk_exact_map<const N: usize, U, F, R>(self, f: F, rem: R) -> Vec<U>
+    where
+        F: FnMut([Self::T; N]) -> U,
+        R: FnMut(Vec<Self::T>) -> Option<U>;
+
+    /// Zip a vector to another vector and combine them, while updating an accumulator,
+    /// the output and the final accumulator are returned
+    ///
+    /// The allocation is reused the same as `VecExt::zip_with`
+    fn zip_fold<U, V, Acc, F: FnMut(&mut Acc, Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        mut acc: Acc,
+        mut f: F,
+    ) -> (Vec<V>, Acc) {
+        let vec = self.zip_with(other, |x, y| f(&mut acc, x, y));
+        (vec, acc)
+    }
+
+    /// Split the vector into runs of adjacent elements with equal keys, and fold
+    /// each run into a single output, `fold` gets the key of the run and a draining
+    /// iterator over it, any elements left in the iterator are dropped
+    ///
+    /// The allocation is reused if it can hold `U`s, see [`scale_capacity`](crate::scale_capacity),
+    /// and `U` is no larger than `T`, since each output is written after its run is read.
+    /// Otherwise the outputs are collected into a new allocation
+    fn group_adjacent_map<K, U, F, G>(self, key: F, fold: G) -> Vec<U>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::T) -> K,
+        G: FnMut(K, Drain<'_, Self::T>) -> U;
+
+    /// The same as `VecExt::map`, but if the allocation isn't reused,
+    /// then it's returned as a [`RecycledAlloc`] instead of being freed
+    fn map_recycle<U, F: FnMut(Self::T) -> U>(self, mut f: F) -> (Vec<U>, Option<RecycledAlloc>) {
+        use std::convert::Infallible;
+
+        match self.try_map_recycle(move |x| Ok::<_, Infallible>(f(x))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// The same as `VecExt::try_map`, but if the allocation isn't reused,
+    /// then it's returned as a [`RecycledAlloc`] instead of being freed
+    ///
+    /// On early return the allocation is freed
+    fn try_map_recycle<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(
+        self,
+        f: F,
+    ) -> Result<(Vec<U>, Option<RecycledAlloc>), R::Error>;
+
+    /// The same as `VecExt::zip_with`, but the allocation of the input that isn't
+    /// reused is returned as a [`RecycledAlloc`] instead of being freed
+    ///
+    /// If neither input is reused, then the larger allocation is returned,
+    /// and the other one is freed
+    fn zip_with_recycle<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        mut f: F,
+    ) -> (Vec<V>, Option<RecycledAlloc>) {
+        use std::convert::Infallible;
+
+        match self.try_zip_with_recycle(other, move |x, y| Ok::<_, Infallible>(f(x, y))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// The same as `VecExt::try_zip_with`, but the allocation of the input that isn't
+    /// reused is returned as a [`RecycledAlloc`] instead of being freed
+    ///
+    /// If neither input is reused, then the larger allocation is returned,
+    /// and the other one is freed. On early return both allocations are freed
+    fn try_zip_with_recycle<U, V, R: Try<Ok = V>, F: FnMut(Self::T, U) -> R>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Result<(Vec<V>, Option<RecycledAlloc>), R::Error>;
+
+    /// The same as `VecExt::map`, but the allocation is reused according to `config`
+    /// instead of the process-wide [`ReuseConfig`]
+    fn map_with_config<U, F: FnMut(Self::T) -> U>(self, config: ReuseConfig, mut f: F) -> Vec<U> {
+        use std::convert::Infallible;
+
+        match self.try_map_with_config(config, move |x| Ok::<_, Infallible>(f(x))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// The same as `VecExt::try_map`, but the allocation is reused according to `config`
+    /// instead of the process-wide [`ReuseConfig`]
+    fn try_map_with_config<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(
+        self,
+        config: ReuseConfig,
+        f: F,
+    ) -> Result<Vec<U>, R::Error>;
+
+    /// The same as `VecExt::zip_with`, but the allocation is reused according to `config`
+    /// instead of the process-wide [`ReuseConfig`]
+    fn zip_with_with_config<U, V, F: FnMut(Self::T, U) -> V>(
+        self,
+        other: Vec<U>,
+        config: ReuseConfig,
+        mut f: F,
+    ) -> Vec<V> {
+        use std::convert::Infallible;
+
+        match self.try_zip_with_with_config(other, config, move |x, y| Ok::<_, Infallible>(f(x, y)))
+        {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// The same as `VecExt::try_zip_with`, but the allocation is reused according to `config`
+    /// instead of the process-wide [`ReuseConfig`]
+    fn try_zip_with_with_config<U, V, R: Try<Ok = V>, F: FnMut(Self::T, U) -> R>(
+        self,
+        other: Vec<U>,
+        config: ReuseConfig,
+        f: F,
+    ) -> Result<Vec<V>, R::Error>;
+
+    /// Map a vector to another vector and filter out the `None`s in the same pass,
+    /// will try and reuse the allocation if the allocation layouts of `T` and `U` match
+    ///
+    /// The mapping function can be fallible, and on early return, it will drop all previous values,
+    /// and the rest of the input vector. The error will be returned as a `Result`
+    ///
+    /// ```rust
+    /// use vec_utils::VecExt;
+    ///
+    /// let vec = vec!["1", "", "3"];
+    /// let vec = vec.try_filter_map(|x| match x {
+    ///     "" => Ok(None),
+    ///     x => x.parse::<u32>().map(Some),
+    /// });
+    /// assert_eq!(vec, Ok(vec![1, 3]));
+    ///
+    /// assert!(vec!["1", "x"].try_filter_map(|x| x.parse::<u32>().map(Some)).is_err());
+    /// ```
+    fn try_filter_map<U, R: Try<Ok = Option<U>>, F: FnMut(Self::T) -> R>(
+        self,
+        f: F,
+    ) -> Result<Vec<U>, R::Error>;
+
+    /// The same as `VecExt::try_filter_map`, but the allocation is reused according to `config`
+    /// instead of the process-wide [`ReuseConfig`]
+    fn try_filter_map_with_config<U, R: Try<Ok = Option<U>>, F: FnMut(Self::T) -> R>(
+        self,
+        config: ReuseConfig,
+        f: F,
+    ) -> Result<Vec<U>, R::Error>;
+
+    /// Map each element of the vector to any number of elements and flatten them,
+    /// will try and reuse the allocation if the allocation layouts of `T` and `U` match
+    ///
+    /// The output is written into the allocation of the vector as long as it doesn't
+    /// catch up with the unread elements. If it does, the unread elements are moved
+    /// into a new allocation, and the output grows like a `Vec<U>` would
+    ///
+    /// The allocation is only reused if the process-wide [`ReuseConfig`] allows it,
+    /// the length of the output is predicted from the length of the vector
+    ///
+    /// ```rust
+    /// use vec_utils::VecExt;
+    ///
+    /// let vec = vec![1u32, 0, 3];
+    /// let vec = vec.flat_map_reuse(|x| std::iter::repeat(x as f32).take(x as usize));
+    /// assert_eq!(vec, [1.0, 3.0, 3.0, 3.0]);
+    /// ```
+    fn flat_map_reuse<U, I: IntoIterator<Item = U>, F: FnMut(Self::T) -> I>(self, f: F) -> Vec<U>;
 }
 
-impl<T> VecExt for Vec<T> {
+impl<T, C: RawParts<Item = T>> VecExt for C {
     type T = T;
 
     fn try_map<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(self, f: F) -> Result<Vec<U>, R::Error> {
+        self.try_map_with_config(reuse_config(), f)
+    }
+
+    fn try_zip_with<U, V, R: Try<Ok = V>, F: FnMut(Self::T, U) -> R>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Result<Vec<V>, R::Error> {
+        self.try_zip_with_with_config(other, reuse_config(), f)
+    }
+
+    fn drop_and_reuse<U>(self) -> Vec<U> {
+        let mut this = self.into_vec();
+        this.clear();
+
+        // no more elements in the vector
+        this.map(|_| unsafe { std::hint::unreachable_unchecked() })
+    }
+
+    fn map_range<R: RangeBounds<usize>, F: FnMut(T) -> T>(&mut self, range: R, f: F) {
+        self.with_vec(move |vec| in_place::map_range(vec, range, f))
+    }
+
+    fn splice_map<U, R: RangeBounds<usize>, F: FnMut(T) -> U>(
+        self,
+        range: R,
+        replacement: Vec<U>,
+        f: F,
+    ) -> Vec<U> {
+        in_place::splice_map(self.into_vec(), range, replacement, f)
+    }
+
+    fn retain_map<F: FnMut(T) -> Option<T>>(&mut self, f: F) {
+        self.with_vec(move |vec| in_place::retain_map(vec, f))
+    }
+
+    fn into_reuse_iter(self) -> ReuseIter<Source<T>> {
+        ReuseIter::new(self.into_vec())
+    }
+
+    fn retire(self) {
+        RecycleBin::deposit_local(self.into_vec())
+    }
+
+    fn transpose_in_place(&mut self, rows: usize, cols: usize) {
+        self.with_vec(move |vec| transpose::transpose_in_place(vec, rows, cols))
+    }
+
+    fn transpose_with(&mut self, rows: usize, cols: usize, scratch: &mut Vec<T>) {
+        self.with_vec(move |vec| transpose::transpose_with(vec, rows, cols, scratch))
+    }
+
+    fn interleave(self, other: Vec<T>) -> Vec<T> {
+        interleave::interleave(self.into_vec(), other)
+    }
+
+    fn map_into_boxed_slice<U, F: FnMut(T) -> U>(self, f: F) -> Box<[U]> {
+        exact::map_into_boxed_slice(self.into_vec(), f)
+    }
+
+    fn map_into_shared<U, F: FnMut(T) -> U>(self, f: F) -> Arc<[U]> {
+        exact::map_into_arc(self.into_vec(), f)
+    }
+
+    fn map_into_rc<U, F: FnMut(T) -> U>(self, f: F) -> Rc<[U]> {
+        exact::map_into_rc(self.into_vec(), f)
+    }
+
+    fn zip_with_into_slice<U, V, F: FnMut(T, U) -> V>(
+        self,
+        other: Vec<U>,
+        out: &mut [MaybeUninit<V>],
+        f: F,
+    ) -> usize {
+        exact::zip_with_into_slice(self.into_vec(), other, out, f)
+    }
+
+    fn zip_with_swap<U, F: FnMut(T, U) -> (T, U)>(self, other: Vec<U>, f: F) -> (Vec<T>, Vec<U>) {
+        in_place::zip_with_swap(self.into_vec(), other, f)
+    }
+
+    fn map_expand<U, F: FnMut(T) -> [U; K], const K: usize>(self, f: F) -> Vec<U> {
+        expand::map_expand(self.into_vec(), f)
+    }
+
+    fn try_map_mut<R: Try<Ok = ()>, F: FnMut(&mut T) -> R, G: FnMut(&mut T)>(
+        &mut self,
+        f: F,
+        undo: G,
+    ) -> Result<(), R::Error> {
+        self.with_vec(move |vec| in_place::try_map_mut(vec, f, undo))
+    }
+
+    fn try_map_falloc<U, F: FnMut(T) -> U>(self, f: F) -> Result<Vec<U>, AllocError> {
+        falloc::try_map_falloc(self.into_vec(), f)
+    }
+
+    fn try_zip_with_falloc<U, V, F: FnMut(T, U) -> V>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Result<Vec<V>, AllocError> {
+        falloc::try_zip_with_falloc(self.into_vec(), other, f)
+    }
+
+    fn map_rev<U, F: FnMut(T) -> U>(self, f: F) -> Vec<U> {
+        rev::map_rev(self.into_vec(), f)
+    }
+
+    fn zip_with_rev<U, V, F: FnMut(T, U) -> V>(self, other: Vec<U>, f: F) -> Vec<V> {
+        rev::zip_with_rev(self.into_vec(), other, f)
+    }
+
+    fn take_map<U, F: FnMut(T) -> U>(self, n: usize, f: F) -> (Vec<U>, Vec<T>) {
+        take::take_map(self.into_vec(), n, f)
+    }
+
+    fn zip_with_threads<U: Send, V: Send, F: Fn(T, U) -> V + Sync>(
+        self,
+        other: Vec<U>,
+        f: F,
+        num_threads: usize,
+    ) -> Vec<V>
+    where
+        T: Send,
+    {
+        threads::zip_with_threads(self.into_vec(), other, f, num_threads)
+    }
+
+    fn zip_longest_with<U, V, F: FnMut(EitherOrBoth<T, U>) -> V>(
+        self,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<V> {
+        longest::zip_longest_with(self.into_vec(), other, f)
+    }
+
+    fn merge_with<F: FnMut(&T, &T) -> Ordering>(self, other: Vec<T>, cmp: F) -> Vec<T> {
+        merge::merge_with(self.into_vec(), other, cmp)
+    }
+
+    fn merge_by_key<K: Ord, F: FnMut(&T) -> K>(self, other: Vec<T>, f: F) -> Vec<T> {
+        merge::merge_by_key(self.into_vec(), other, f)
+    }
+
+    fn intersperse_map<U, S: FnMut() -> U, F: FnMut(T) -> U>(self, sep_fn: S, f: F) -> Vec<U> {
+        intersperse::intersperse_map(self.into_vec(), sep_fn, f)
+    }
+
+    fn zip_column_with<U, F: FnMut(T, U) -> T>(
+        self,
+        stride: usize,
+        col: usize,
+        other: Vec<U>,
+        f: F,
+    ) -> Vec<T> {
+        strided::zip_column_with(self.into_vec(), stride, col, other, f)
+    }
+
+    fn map_column<F: FnMut(T) -> T>(self, stride: usize, col: usize, f: F) -> Vec<T> {
+        strided::map_column(self.into_vec(), stride, col, f)
+    }
+
+    fn gather_map<U, F: FnMut(T) -> U>(
+        self,
+        indices: &[usize],
+        f: F,
+    ) -> Result<Vec<U>, GatherError> {
+        gather::gather_map(self.into_vec(), indices, f)
+    }
+
+    fn join_with<U, K: Hash + Eq, V, KA, KB, F>(
+        self,
+        other: Vec<U>,
+        key_a: KA,
+        key_b: KB,
+        f: F,
+    ) -> Vec<V>
+    where
+        KA: FnMut(&T) -> K,
+        KB: FnMut(&U) -> K,
+        F: FnMut(T, U) -> V,
+    {
+        join::join_with(self.into_vec(), other, key_a, key_b, f)
+    }
+
+    fn cartesian_with<U, V, F: FnMut(&T, &U) -> V>(self, other: Vec<U>, f: F) -> Vec<V> {
+        cartesian::cartesian_with(self.into_vec(), other, f)
+    }
+
+    fn rle_encode(self) -> Vec<(T, usize)>
+    where
+        T: PartialEq,
+    {
+        rle::rle_encode(self.into_vec())
+    }
+
+    fn reduce_pairs<F: FnMut(T, T) -> T>(self, odd: OddPolicy, f: F) -> Vec<T> {
+        pairs::reduce_pairs(self.into_vec(), odd, f)
+    }
+
+    fn scan_in_place<U, Acc, F: FnMut(&Acc, T) -> (Acc, U)>(self, init: Acc, f: F) -> Vec<U> {
+        scan::scan_in_place(self.into_vec(), init, f)
+    }
+
+    fn prefix_sum(self) -> Vec<T>
+    where
+        T: Copy + Add<Output = T>,
+    {
+        scan::prefix_sum(self.into_vec())
+    }
+
+    fn exclusive_prefix_sum(self) -> Vec<T>
+    where
+        T: Copy + Default + Add<Output = T>,
+    {
+        scan::exclusive_prefix_sum(self.into_vec())
+    }
+
+    fn histogram<F: FnMut(&T) -> usize>(self, buckets: usize, f: F) -> Vec<usize> {
+        histogram::histogram(self.into_vec(), buckets, f)
+    }
+
+    fn count_into<F: FnMut(&T) -> usize>(self, counts: &mut [usize], f: F) {
+        histogram::count_into(self.into_vec(), counts, f)
+    }
+
+    fn chunk_exact_map<const N: usize, U, F, R>(self, f: F, rem: R) -> Vec<U>
+    where
+        F: FnMut([T; N]) -> U,
+        R: FnMut(Vec<T>) -> Option<U>,
+    {
+        chunks::chunk_exact_map(self.into_vec(), f, rem)
+    }
+
+    fn group_adjacent_map<K, U, F, G>(self, key: F, fold: G) -> Vec<U>
+    where
+        K: PartialEq,
+        F: FnMut(&T) -> K,
+        G: FnMut(K, Drain<'_, T>) -> U,
+    {
+        group::group_adjacent_map(self.into_vec(), key, fold)
+    }
+
+    fn try_map_recycle<U, R: Try<Ok = U>, F: FnMut(T) -> R>(
+        self,
+        f: F,
+    ) -> Result<(Vec<U>, Option<RecycledAlloc>), R::Error> {
+        let mut this = self.into_vec();
+        let config = reuse_config();
+
+        if Layout::new::<T>() == Layout::new::<U>() && config.reuses(this.len(), this.capacity()) {
+            return Ok((this.try_map_with_config(config, f)?, None));
+        }
+
+        let len = this.len();
+        let iter = this.drain(..).map(faults::hook(f)).map(R::into_result);
+        let output = config.try_collect(iter, len)?;
+
+        Ok((output, RecycledAlloc::new(this)))
+    }
+
+    fn try_zip_with_recycle<U, V, R: Try<Ok = V>, F: FnMut(T, U) -> R>(
+        self,
+        mut other: Vec<U>,
+        mut f: F,
+    ) -> Result<(Vec<V>, Option<RecycledAlloc>), R::Error> {
+        let mut this = self.into_vec();
+        let config = reuse_config();
+
+        let len = this.len().min(other.len());
+        let left = Layout::new::<T>() == Layout::new::<V>() && config.reuses(len, this.capacity());
+        let right =
+            Layout::new::<U>() == Layout::new::<V>() && config.reuses(len, other.capacity());
+
+        if left && (!right || this.capacity() >= other.capacity()) {
+            this.truncate(len);
+
+            let mut rest = other.drain(..len);
+            let output = this.try_map_with_config(config, |x| match rest.next() {
+                Some(y) => f(x, y),
+                None => unreachable!(),
+            })?;
+            drop(rest);
+
+            Ok((output, RecycledAlloc::new(other)))
+        } else if right {
+            other.truncate(len);
+
+            let mut rest = this.drain(..len);
+            let output = other.try_map_with_config(config, |y| match rest.next() {
+                Some(x) => f(x, y),
+                None => unreachable!(),
+            })?;
+            drop(rest);
+
+            Ok((output, RecycledAlloc::new(this)))
+        } else {
+            let output = config.try_collect(
+                this.drain(..)
+                    .zip(other.drain(..))
+                    .map(faults::hook(|(x, y)| f(x, y)))
+                    .map(R::into_result),
+                len,
+            )?;
+
+            let spare = match (RecycledAlloc::new(this), RecycledAlloc::new(other)) {
+                (Some(a), Some(b)) if a.layout().size() < b.layout().size() => Some(b),
+                (a, b) => a.or(b),
+            };
+
+            Ok((output, spare))
+        }
+    }
+
+    fn try_map_with_config<U, R: Try<Ok = U>, F: FnMut(T) -> R>(
+        self,
+        config: ReuseConfig,
+        f: F,
+    ) -> Result<Vec<U>, R::Error> {
         // try_zip_with! { self => |x| { f(x) } }
 
-        if Layout::new::<T>() == Layout::new::<U>() {
+        let this = self.into_vec();
+        let _span = trace::map::<T, U>(this.len(), this.capacity(), &config);
+
+        if Layout::new::<T>() == Layout::new::<U>() && config.reuses(this.len(), this.capacity()) {
             let iter = MapIter {
                 init_len: 0,
-                data: Input::from(self),
+                data: Input::from(this),
                 drop: PhantomData,
             };
 
-            iter.try_into_vec(f)
+            iter.try_into_vec(f).map(|vec| config.finish(vec))
         } else {
-            self.into_iter().map(f).map(R::into_result).collect()
+            let len = this.len();
+            let iter = this.into_iter().map(faults::hook(f)).map(R::into_result);
+
+            config.try_collect(iter, len)
         }
     }
 
-    fn try_zip_with<U, V, R: Try<Ok = V>, F: FnMut(Self::T, U) -> R>(
+    fn try_zip_with_with_config<U, V, R: Try<Ok = V>, F: FnMut(T, U) -> R>(
         self,
         other: Vec<U>,
+        config: ReuseConfig,
         mut f: F,
     ) -> Result<Vec<V>, R::Error> {
         // try_zip_with! { self, other => |x, y| { f(x, y) } }
 
-        let len = self.len().min(other.len());
+        let this = self.into_vec();
+
+        let len = this.len().min(other.len());
+        let _span = trace::zip_with::<T, U, V>(len, [this.capacity(), other.capacity()], &config);
+
         match (
-            Layout::new::<T>() == Layout::new::<V>(),
-            Layout::new::<U>() == Layout::new::<V>(),
-            self.capacity() >= other.capacity(),
+            Layout::new::<T>() == Layout::new::<V>() && config.reuses(len, this.capacity()),
+            Layout::new::<U>() == Layout::new::<V>() && config.reuses(len, other.capacity()),
+            this.capacity() >= other.capacity(),
         ) {
             (true, true, true) | (true, false, _) => ZipWithIter {
                 init_len: len,
                 min_len: len,
                 drop: PhantomData,
 
-                left: Input::from(self),
+                left: Input::from(this),
                 right: Input::from(other),
             }
-            .try_into_vec(f),
+            .try_into_vec(f)
+            .map(|vec| config.finish(vec)),
             (true, true, false) | (false, true, _) => ZipWithIter {
                 init_len: len,
                 min_len: len,
                 drop: PhantomData,
 
                 left: Input::from(other),
-                right: Input::from(self),
+                right: Input::from(this),
             }
-            .try_into_vec(move |y, x| f(x, y)),
-            (false, false, _) => self
-                .into_iter()
-                .zip(other.into_iter())
-                .map(move |(x, y)| f(x, y))
-                .map(R::into_result)
-                .collect(),
+            .try_into_vec(move |y, x| f(x, y))
+            .map(|vec| config.finish(vec)),
+            (false, false, _) => config.try_collect(
+                this.into_iter()
+                    .zip(other)
+                    .map(faults::hook(move |(x, y)| f(x, y)))
+                    .map(R::into_result),
+                len,
+            ),
         }
     }
 
-    fn drop_and_reuse<U>(mut self) -> Vec<U> {
-        self.clear();
+    fn try_filter_map<U, R: Try<Ok = Option<U>>, F: FnMut(T) -> R>(
+        self,
+        f: F,
+    ) -> Result<Vec<U>, R::Error> {
+        self.try_filter_map_with_config(reuse_config(), f)
+    }
 
-        // no more elements in the vector
-        self.map(|_| unsafe { std::hint::unreachable_unchecked() })
+    fn try_filter_map_with_config<U, R: Try<Ok = Option<U>>, F: FnMut(T) -> R>(
+        self,
+        config: ReuseConfig,
+        f: F,
+    ) -> Result<Vec<U>, R::Error> {
+        let this = self.into_vec();
+
+        let len = this.len();
+
+        if Layout::new::<T>() == Layout::new::<U>() && config.reuses(len, this.capacity()) {
+            unsafe { in_place::try_filter_map(this, faults::hook(f)) }.map(|vec| config.finish(vec))
+        } else {
+            config.try_collect(
+                this.into_iter()
+                    .map(faults::hook(f))
+                    .map(R::into_result)
+                    .filter_map(Result::transpose),
+                len,
+            )
+        }
+    }
+
+    fn flat_map_reuse<U, I: IntoIterator<Item = U>, F: FnMut(T) -> I>(self, f: F) -> Vec<U> {
+        flat_map::flat_map_reuse(self.into_vec(), reuse_config(), f)
     }
 }
 
@@ -214,7 +1566,9 @@ impl<T, U> MapIter<T, U> {
         // does a pointer walk, easy for LLVM to optimize
         while self.init_len < self.data.len {
             unsafe {
-                let value = r#try!(f(self.data.ptr.read()));
+                let input = self.data.ptr.read();
+                faults::closure();
+                let value = r#try!(f(input));
 
                 (self.data.ptr as *mut U).write(value);
 
@@ -240,22 +1594,18 @@ impl<T, U> MapIter<T, U> {
 impl<T, U> Drop for MapIter<T, U> {
     fn drop(&mut self) {
         unsafe {
+            // free the allocation
+            let _alloc = RawAllocGuard::new(self.data.start, self.data.cap);
+
             // destroy the initialized output
             defer! {
-                Vec::from_raw_parts(
-                    self.data.start as *mut U,
-                    self.init_len,
-                    self.data.cap
-                );
+                cleanup::drop_slice(self.data.start as *mut U, self.init_len);
             }
 
             // offset by 1 because self.ptr is pointing to
             // memory that was just read from, dropping that
             // would lead to a double free
-            std::ptr::drop_in_place(std::slice::from_raw_parts_mut(
-                self.data.ptr.add(1),
-                self.data.len - self.init_len - 1,
-            ));
+            cleanup::drop_slice(self.data.ptr.add(1), self.data.len - self.init_len - 1);
         }
     }
 }
@@ -303,7 +1653,9 @@ impl<T, U, V> ZipWithIter<T, U, V> {
                 self.left.ptr = self.left.ptr.add(1);
                 self.right.ptr = self.right.ptr.add(1);
 
-                let value = r#try!(f(left.read(), right.read()));
+                let (left, right) = (left.read(), right.read());
+                faults::closure();
+                let value = r#try!(f(left, right));
 
                 out.write(value);
             }
@@ -322,23 +1674,15 @@ impl<T, U, V> ZipWithIter<T, U, V> {
             // old vecs properly
 
             // cleans up the right vec
-            defer! {
-                Vec::from_raw_parts(vec.right.start, 0, vec.right.cap);
-            }
+            let _right = RawAllocGuard::new(vec.right.start, vec.right.cap);
 
             // drops the remaining elements of the right vec
             defer! {
-                std::ptr::drop_in_place(std::slice::from_raw_parts_mut(
-                    vec.right.ptr,
-                    vec.right.len - vec.init_len
-                ));
+                cleanup::drop_slice(vec.right.ptr, vec.right.len - vec.init_len);
             }
 
             // drop the remaining elements of the left vec
-            std::ptr::drop_in_place(std::slice::from_raw_parts_mut(
-                vec.left.ptr,
-                vec.left.len - vec.init_len,
-            ));
+            cleanup::drop_slice(vec.left.ptr, vec.left.len - vec.init_len);
         }
 
         Ok(output)
@@ -353,27 +1697,22 @@ impl<T, U, V> Drop for ZipWithIter<T, U, V> {
             // This will happen last
             //
             // frees the allocated memory, but does not run destructors
-            defer! {
-                Vec::from_raw_parts(self.left.start, 0, self.left.cap);
-                Vec::from_raw_parts(self.right.start, 0, self.right.cap);
-            }
+            let _left = RawAllocGuard::new(self.left.start, self.left.cap);
+            let _right = RawAllocGuard::new(self.right.start, self.right.cap);
 
             // The order of the next two defers don't matter for correctness
             //
             // They free the remaining parts of the two input vectors
             defer! {
-                std::ptr::drop_in_place(std::slice::from_raw_parts_mut(self.right.ptr, self.right.len - len));
+                cleanup::drop_slice(self.right.ptr, self.right.len - len);
             }
 
             defer! {
-                std::ptr::drop_in_place(std::slice::from_raw_parts_mut(self.left.ptr, self.left.len - len));
+                cleanup::drop_slice(self.left.ptr, self.left.len - len);
             }
 
             // drop the output that we already calculated
-            std::ptr::drop_in_place(std::slice::from_raw_parts_mut(
-                self.left.start as *mut V,
-                len - 1,
-            ));
+            cleanup::drop_slice(self.left.start as *mut V, len - 1);
         }
     }
 }
@@ -0,0 +1,145 @@
+use super::general_zip::{check_first_layout, into_iterator};
+use super::{try_zip_assign_impl, try_zip_with_impl, Tuple, TupleElem};
+use crate::raw::convert_alloc;
+use crate::{RecycleBin, Try};
+
+/// Which input allocation a [`ZipBuilder`] reuses for it's output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReusePolicy {
+    /// Reuse the compatible input with the largest capacity,
+    /// the same as `zip_with!`
+    #[default]
+    LargestCapacity,
+
+    /// Reuse the first input if it is compatible,
+    /// otherwise fall back to `LargestCapacity`
+    First,
+
+    /// Never reuse any of the inputs, always allocate a new vector
+    Never,
+}
+
+/// Start building a zip of multiple vectors, a non-macro alternative to `zip_with!`
+///
+/// ```rust
+/// let a = vec![1, 2, 3];
+/// let b = vec![4, 5, 6];
+/// let c = vec![7, 8, 9];
+///
+/// let sum = vec_utils::zip(a).and(b).and(c).map(|(a, (b, c))| a + b + c);
+///
+/// assert_eq!(sum, [12, 15, 18]);
+/// ```
+pub fn zip<A: TupleElem>(a: A) -> ZipBuilder<(A,)> {
+    ZipBuilder {
+        list: (a,),
+        policy: ReusePolicy::default(),
+        donor: None,
+    }
+}
+
+/// A builder for zipping multiple vectors, created by [`zip`]
+///
+/// The mapping function takes the elements as a nested tuple,
+/// i.e. `(a, (b, (c, d)))` for four vectors
+pub struct ZipBuilder<L, D = ()> {
+    list: L,
+    policy: ReusePolicy,
+    donor: Option<Vec<D>>,
+}
+
+/// Appends an element to the end of a cons-list
+pub trait Append<X> {
+    /// The cons-list with `X` at the end
+    type Output;
+
+    /// Append `x` to the end of the cons-list
+    fn append(self, x: X) -> Self::Output;
+}
+
+impl<A, X> Append<X> for (A,) {
+    type Output = (A, (X,));
+
+    fn append(self, x: X) -> Self::Output {
+        (self.0, (x,))
+    }
+}
+
+impl<A, T: Append<X>, X> Append<X> for (A, T) {
+    type Output = (A, T::Output);
+
+    fn append(self, x: X) -> Self::Output {
+        (self.0, self.1.append(x))
+    }
+}
+
+impl<L, D> ZipBuilder<L, D> {
+    /// Add another vector to the zip
+    pub fn and<X: TupleElem>(self, x: X) -> ZipBuilder<L::Output, D>
+    where
+        L: Append<X>,
+    {
+        ZipBuilder {
+            list: self.list.append(x),
+            policy: self.policy,
+            donor: self.donor,
+        }
+    }
+
+    /// Choose which input allocation is reused for the output
+    pub fn policy(self, policy: ReusePolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Collect the output into the allocation of `donor` instead of reusing
+    /// one of the inputs, see [`CollectWith::collect_with`](crate::CollectWith::collect_with)
+    /// for when the donor allocation can be used
+    ///
+    /// This overrides the [`ReusePolicy`]
+    pub fn output_from<E>(self, donor: Vec<E>) -> ZipBuilder<L, E> {
+        ZipBuilder {
+            list: self.list,
+            policy: self.policy,
+            donor: Some(donor),
+        }
+    }
+}
+
+impl<L: Tuple, D> ZipBuilder<L, D> {
+    /// Combine the elements of the vectors
+    pub fn map<V, F: FnMut(L::Item) -> V>(self, mut f: F) -> Vec<V> {
+        match self.try_map(move |x| Ok::<_, std::convert::Infallible>(f(x))) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Combine the elements of the vectors, short-circuiting on the failure case
+    pub fn try_map<R: Try, F: FnMut(L::Item) -> R>(self, f: F) -> Result<Vec<R::Ok>, R::Error> {
+        if let Some(donor) = self.donor {
+            let iter = into_iterator(self.list).map(f);
+
+            let mut output = convert_alloc(donor)
+                .ok()
+                .or_else(|| RecycleBin::take_local(iter.size_hint().0))
+                .unwrap_or_default();
+
+            for value in iter {
+                output.push(value.into_result()?);
+            }
+
+            return Ok(output);
+        }
+
+        match self.policy {
+            ReusePolicy::First if check_first_layout::<L, R::Ok>() => {
+                try_zip_assign_impl(self.list, f)
+            }
+            ReusePolicy::First | ReusePolicy::LargestCapacity => try_zip_with_impl(self.list, f),
+            ReusePolicy::Never => into_iterator(self.list)
+                .map(f)
+                .map(R::into_result)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::alloc::Layout;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+
+/// Owns the live prefixes of the inputs and the written suffix of the output
+struct Product<T, U, V> {
+    a: *mut T,
+    a_len: usize,
+    b: *mut U,
+    b_len: usize,
+    out: *mut V,
+    start: usize,
+    len: usize,
+}
+
+impl<T, U, V> Drop for Product<T, U, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let (a, a_len, b, b_len) = (self.a, self.a_len, self.b, self.b_len);
+
+            defer! {
+                cleanup::drop_slice(b, b_len);
+            }
+
+            defer! {
+                cleanup::drop_slice(a, a_len);
+            }
+
+            cleanup::drop_slice(self.out.add(self.start), self.len - self.start);
+        }
+    }
+}
+
+fn into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+pub(crate) fn cartesian_with<T, U, V, F: FnMut(&T, &U) -> V>(
+    a: Vec<T>,
+    b: Vec<U>,
+    mut f: F,
+) -> Vec<V> {
+    let (n, m) = (a.len(), b.len());
+    let len = n.checked_mul(m).expect("capacity overflow");
+
+    let fits = |cap: usize, layout: Layout| {
+        raw::scale(cap, layout, Layout::new::<V>())
+            .ok()
+            .filter(|&cap| cap >= len)
+    };
+
+    // the rows are written from the back to the front, and each element of `a` is
+    // moved out before it's row is written, so a row of outputs can't reach the
+    // elements of `a` before it if it's at least as large as an element of `a`.
+    // The elements of `b` are needed for every row, so in the first row each
+    // element of `b` is dropped just before it's overwritten
+    let reuse_a =
+        fits(a.capacity(), Layout::new::<T>()).filter(|_| m * size_of::<V>() >= size_of::<T>());
+    let reuse_b = fits(b.capacity(), Layout::new::<U>())
+        .filter(|_| reuse_a.is_none() && size_of::<V>() >= size_of::<U>());
+
+    let (a_ptr, _, a_cap) = into_raw_parts(a);
+    let (b_ptr, _, b_cap) = into_raw_parts(b);
+
+    let (out_ptr, out_cap) = match (reuse_a, reuse_b) {
+        (Some(cap), _) => (a_ptr as *mut V, cap),
+        (_, Some(cap)) => (b_ptr as *mut V, cap),
+        _ => {
+            let (ptr, _, cap) = into_raw_parts(Vec::<V>::with_capacity(len));
+            (ptr, cap)
+        }
+    };
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = reuse_a.is_none().then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = reuse_b.is_none().then(|| RawAllocGuard::new(b_ptr, b_cap));
+
+        let mut product = Product {
+            a: a_ptr,
+            a_len: n,
+            b: b_ptr,
+            b_len: m,
+            out: out_ptr,
+            start: len,
+            len,
+        };
+
+        while let Some(i) = product.a_len.checked_sub(1) {
+            product.a_len = i;
+            let x = a_ptr.add(i).read();
+
+            for j in (0..m).rev() {
+                let value = f(&x, &*b_ptr.add(j));
+
+                if i == 0 && reuse_b.is_some() {
+                    product.b_len = j;
+                    b_ptr.add(j).drop_in_place();
+                }
+
+                out_ptr.add(i * m + j).write(value);
+                product.start = i * m + j;
+            }
+        }
+
+        // drops the elements of `b`, unless they were already dropped
+        product.start = len;
+        drop(product);
+
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
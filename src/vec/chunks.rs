@@ -0,0 +1,106 @@
+use std::alloc::Layout;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+use crate::RecycleBin;
+
+/// Owns the written prefix of the output and the unread suffix of the input
+struct Chunks<T, U> {
+    input: *mut T,
+    read: usize,
+    len: usize,
+    output: *mut U,
+    written: usize,
+}
+
+impl<T, U> Drop for Chunks<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let (input, read, len) = (self.input, self.read, self.len);
+
+            defer! {
+                cleanup::drop_slice(input.add(read), len - read);
+            }
+
+            cleanup::drop_slice(self.output, self.written);
+        }
+    }
+}
+
+pub(crate) fn chunk_exact_map<T, U, const N: usize, F, R>(
+    vec: Vec<T>,
+    mut f: F,
+    mut rem: R,
+) -> Vec<U>
+where
+    F: FnMut([T; N]) -> U,
+    R: FnMut(Vec<T>) -> Option<U>,
+{
+    assert!(N != 0, "the chunk size must be non-zero");
+
+    let mut vec = ManuallyDrop::new(vec);
+    let (in_ptr, len, in_cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    let chunks = len / N;
+    let out_len = chunks + usize::from(len % N != 0);
+
+    // output `k` is written after chunk `k` is read, so it
+    // must not be larger than a chunk to not reach chunk `k + 1`
+    let reused_cap = raw::scale(in_cap, Layout::new::<T>(), Layout::new::<U>())
+        .ok()
+        .filter(|&cap| cap >= out_len && size_of::<U>() <= N * size_of::<T>());
+
+    let (out_ptr, out_cap) = match reused_cap {
+        Some(cap) => (in_ptr as *mut U, cap),
+        None => {
+            let mut output = ManuallyDrop::new(Vec::<U>::with_capacity(out_len));
+            (output.as_mut_ptr(), output.capacity())
+        }
+    };
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _in_alloc = reused_cap
+            .is_none()
+            .then(|| RawAllocGuard::new(in_ptr, in_cap));
+
+        let mut state = Chunks {
+            input: in_ptr,
+            read: 0,
+            len,
+            output: out_ptr,
+            written: 0,
+        };
+
+        for k in 0..chunks {
+            let chunk = in_ptr.add(k * N).cast::<[T; N]>().read();
+            state.read += N;
+
+            out_ptr.add(k).write(f(chunk));
+            state.written += 1;
+        }
+
+        if state.read < len {
+            let rest = len - state.read;
+            let mut tail =
+                RecycleBin::take_local::<T>(rest).unwrap_or_else(|| Vec::with_capacity(rest));
+            tail.as_mut_ptr()
+                .copy_from_nonoverlapping(in_ptr.add(state.read), rest);
+            tail.set_len(rest);
+            state.read = len;
+
+            if let Some(value) = rem(tail) {
+                out_ptr.add(state.written).write(value);
+                state.written += 1;
+            }
+        }
+
+        let written = state.written;
+        std::mem::forget(state);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, written, out_cap)
+    }
+}
@@ -0,0 +1,166 @@
+use std::alloc::{self, Layout};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::cleanup;
+
+/// Drops the mapped and unmapped elements and frees the allocation
+/// if `f` panics while mapping in place
+struct ShrinkGuard<T, U> {
+    src: *mut T,
+    dest: *mut U,
+    // the number of elements moved out of the vector
+    read: usize,
+    // the number of elements written into the vector
+    written: usize,
+    len: usize,
+    layout: Layout,
+}
+
+impl<T, U> Drop for ShrinkGuard<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            defer! {
+                alloc::dealloc(self.src as *mut u8, self.layout);
+            }
+
+            defer! {
+                cleanup::drop_slice(self.src.add(self.read), self.len - self.read);
+            }
+
+            cleanup::drop_slice(self.dest, self.written);
+        }
+    }
+}
+
+pub(crate) fn map_into_boxed_slice<T, U, F: FnMut(T) -> U>(vec: Vec<T>, mut f: F) -> Box<[U]> {
+    let (t, u) = (Layout::new::<T>(), Layout::new::<U>());
+
+    if t.size() == 0 || u.size() == 0 || t.align() != u.align() || u.size() > t.size() {
+        let mut output = Vec::with_capacity(vec.len());
+        output.extend(vec.into_iter().map(f));
+        return output.into_boxed_slice();
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+    let len = vec.len();
+    let src = vec.as_mut_ptr();
+
+    let mut guard = ShrinkGuard {
+        src,
+        dest: src as *mut U,
+        read: 0,
+        written: 0,
+        len,
+        layout: unsafe { Layout::from_size_align_unchecked(vec.capacity() * t.size(), t.align()) },
+    };
+
+    unsafe {
+        // element `i` of the output ends before element `i + 1` of the input
+        // starts, so the output never overwrites any unread input
+        while guard.read < len {
+            let value = src.add(guard.read).read();
+            guard.read += 1;
+
+            guard.dest.add(guard.written).write(f(value));
+            guard.written += 1;
+        }
+
+        let guard = ManuallyDrop::new(guard);
+        let size = len * u.size();
+
+        let ptr = if size == guard.layout.size() {
+            src as *mut u8
+        } else if len == 0 {
+            alloc::dealloc(src as *mut u8, guard.layout);
+            return Box::new([]);
+        } else {
+            let ptr = alloc::realloc(src as *mut u8, guard.layout, size);
+
+            if ptr.is_null() {
+                alloc::handle_alloc_error(Layout::from_size_align_unchecked(size, u.align()))
+            }
+
+            ptr
+        };
+
+        Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr as *mut U, len))
+    }
+}
+
+/// Drops the initialized prefix of the slice if we panic while filling it
+pub(crate) struct SliceGuard<'a, U> {
+    pub(crate) slice: &'a mut [MaybeUninit<U>],
+    pub(crate) written: usize,
+}
+
+impl<U> SliceGuard<'_, U> {
+    /// # Safety
+    ///
+    /// `written` must be less than the length of the slice
+    pub(crate) unsafe fn push(&mut self, value: U) {
+        self.slice.get_unchecked_mut(self.written).write(value);
+        self.written += 1;
+    }
+
+    /// Stop tracking the initialized prefix, and return it's length
+    pub(crate) fn finish(self) -> usize {
+        ManuallyDrop::new(self).written
+    }
+}
+
+impl<U> Drop for SliceGuard<'_, U> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.slice.as_mut_ptr() as *mut U, self.written) }
+    }
+}
+
+fn fill<T, U, F: FnMut(T) -> U>(slice: &mut [MaybeUninit<U>], vec: Vec<T>, f: F) {
+    let mut guard = SliceGuard { slice, written: 0 };
+
+    // the slice has the same length as the vector
+    for value in vec.into_iter().map(f) {
+        unsafe { guard.push(value) }
+    }
+
+    guard.finish();
+}
+
+pub(crate) fn map_into_arc<T, U, F: FnMut(T) -> U>(vec: Vec<T>, f: F) -> Arc<[U]> {
+    let mut shared = Arc::new_uninit_slice(vec.len());
+
+    // the arc was just created, so it is unique
+    fill(Arc::get_mut(&mut shared).unwrap(), vec, f);
+
+    unsafe { shared.assume_init() }
+}
+
+pub(crate) fn map_into_rc<T, U, F: FnMut(T) -> U>(vec: Vec<T>, f: F) -> Rc<[U]> {
+    let mut shared = Rc::new_uninit_slice(vec.len());
+
+    // the rc was just created, so it is unique
+    fill(Rc::get_mut(&mut shared).unwrap(), vec, f);
+
+    unsafe { shared.assume_init() }
+}
+
+pub(crate) fn zip_with_into_slice<T, U, V, F: FnMut(T, U) -> V>(
+    a: Vec<T>,
+    b: Vec<U>,
+    out: &mut [MaybeUninit<V>],
+    mut f: F,
+) -> usize {
+    let len = out.len();
+    let mut guard = SliceGuard {
+        slice: out,
+        written: 0,
+    };
+
+    // the rest of the inputs are dropped with the iterators
+    for (x, y) in a.into_iter().zip(b).take(len) {
+        unsafe { guard.push(f(x, y)) }
+    }
+
+    guard.finish()
+}
@@ -0,0 +1,102 @@
+use std::alloc::{self, Layout};
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+
+/// Drops the unread input, the written output, and frees the
+/// allocation if `f` panics while expanding in place
+struct ExpandGuard<T, U> {
+    ptr: *mut T,
+    dest: *mut U,
+    layout: Layout,
+    // the unread input elements, `input.0..input.1`
+    input: (usize, usize),
+    // the written output elements, `output.0..output.1`
+    output: (usize, usize),
+}
+
+impl<T, U> Drop for ExpandGuard<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            defer! {
+                alloc::dealloc(self.ptr as *mut u8, self.layout);
+            }
+
+            defer! {
+                cleanup::drop_slice(self.ptr.add(self.input.0), self.input.1 - self.input.0);
+            }
+
+            cleanup::drop_slice(self.dest.add(self.output.0), self.output.1 - self.output.0);
+        }
+    }
+}
+
+pub(crate) fn map_expand<T, U, F: FnMut(T) -> [U; K], const K: usize>(
+    vec: Vec<T>,
+    mut f: F,
+) -> Vec<U> {
+    let (t, u) = (Layout::new::<T>(), Layout::new::<U>());
+    let len = vec.len();
+    let bytes = vec.capacity() * t.size();
+
+    let reusable = t.size() != 0
+        && u.size() != 0
+        && t.align() == u.align()
+        && bytes.is_multiple_of(u.size())
+        && len
+            .checked_mul(K)
+            .is_some_and(|out_len| out_len <= bytes / u.size());
+
+    if !reusable {
+        let mut output = Vec::with_capacity(len.checked_mul(K).expect("capacity overflow"));
+        output.extend(
+            vec.into_iter()
+                .flat_map(move |x| IntoIterator::into_iter(f(x))),
+        );
+        return output;
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+    let ptr = vec.as_mut_ptr();
+    let dest = ptr as *mut [U; K];
+
+    let mut guard = ExpandGuard {
+        ptr,
+        dest: ptr as *mut U,
+        layout: unsafe { Layout::from_size_align_unchecked(bytes, t.align()) },
+        input: (0, len),
+        output: (0, 0),
+    };
+
+    unsafe {
+        if K * u.size() <= t.size() {
+            // output block `i` ends before input element `i + 1` starts,
+            // so filling front-to-back never overwrites any unread input
+            while guard.input.0 < len {
+                let index = guard.input.0;
+                let value = ptr.add(index).read();
+                guard.input.0 += 1;
+
+                dest.add(index).write(f(value));
+                guard.output.1 += K;
+            }
+        } else {
+            // output block `i` starts after input element `i - 1` ends,
+            // so filling back-to-front never overwrites any unread input
+            guard.output = (len * K, len * K);
+
+            while guard.input.1 > 0 {
+                let index = guard.input.1 - 1;
+                let value = ptr.add(index).read();
+                guard.input.1 -= 1;
+
+                dest.add(index).write(f(value));
+                guard.output.0 -= K;
+            }
+        }
+
+        std::mem::forget(guard);
+
+        Vec::from_raw_parts(ptr as *mut U, len * K, bytes / u.size())
+    }
+}
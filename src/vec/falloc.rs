@@ -0,0 +1,97 @@
+use std::alloc::Layout;
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::{reuse_config, FallbackStrategy, RecycleBin, ReuseConfig, VecExt};
+
+/// The error returned by the fallible-allocation variants of `VecExt`
+/// when a new allocation can't be made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AllocError {
+    /// The requested capacity exceeds `isize::MAX` bytes
+    CapacityOverflow,
+    /// The allocator failed to allocate memory with the given layout
+    OutOfMemory(Layout),
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::CapacityOverflow => f.write_str("capacity overflow"),
+            AllocError::OutOfMemory(layout) => write!(
+                f,
+                "failed to allocate {} bytes with an alignment of {}",
+                layout.size(),
+                layout.align()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+impl From<Infallible> for AllocError {
+    fn from(x: Infallible) -> Self {
+        match x {}
+    }
+}
+
+/// Get an empty vector that can hold `len` elements without reallocating,
+/// taking it from the thread-local `RecycleBin` if the config allows it
+fn reserve<T>(len: usize, config: &ReuseConfig) -> Result<Vec<T>, AllocError> {
+    if config.fallback == FallbackStrategy::RecycleBin {
+        if let Some(vec) = RecycleBin::take_local(len) {
+            return Ok(vec);
+        }
+    }
+
+    let layout = Layout::array::<T>(len).map_err(|_| AllocError::CapacityOverflow)?;
+
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(len)
+        .map_err(|_| AllocError::OutOfMemory(layout))?;
+    Ok(vec)
+}
+
+/// The process-wide config, without shrinking the output since
+/// that could reallocate, and fail without returning an error
+fn falloc_config() -> ReuseConfig {
+    ReuseConfig {
+        shrink_on_finish: false,
+        ..reuse_config()
+    }
+}
+
+pub(crate) fn try_map_falloc<T, U, F: FnMut(T) -> U>(
+    vec: Vec<T>,
+    f: F,
+) -> Result<Vec<U>, AllocError> {
+    let config = falloc_config();
+
+    if Layout::new::<T>() == Layout::new::<U>() && config.reuses(vec.len(), vec.capacity()) {
+        return Ok(vec.map_with_config(config, f));
+    }
+
+    let mut output = reserve(vec.len(), &config)?;
+    output.extend(vec.into_iter().map(f));
+    Ok(output)
+}
+
+pub(crate) fn try_zip_with_falloc<T, U, V, F: FnMut(T, U) -> V>(
+    a: Vec<T>,
+    b: Vec<U>,
+    mut f: F,
+) -> Result<Vec<V>, AllocError> {
+    let config = falloc_config();
+    let len = a.len().min(b.len());
+
+    if Layout::new::<T>() == Layout::new::<V>() && config.reuses(len, a.capacity())
+        || Layout::new::<U>() == Layout::new::<V>() && config.reuses(len, b.capacity())
+    {
+        return Ok(a.zip_with_with_config(b, config, f));
+    }
+
+    let mut output = reserve(len, &config)?;
+    output.extend(a.into_iter().zip(b).map(move |(x, y)| f(x, y)));
+    Ok(output)
+}
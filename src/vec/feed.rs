@@ -0,0 +1,121 @@
+use std::alloc::Layout;
+use std::marker::PhantomData;
+
+use crate::raw::convert_alloc;
+use crate::{RecycleBin, VecExt};
+
+/// A map over an input that arrives a chunk at a time, the outputs
+/// of all of the chunks are collected into a single vector
+///
+/// If nothing was written to the output yet, and the layouts of `T` and `U` match,
+/// then a chunk with a larger allocation than the output is mapped in place and becomes
+/// the output. Otherwise the chunk is mapped onto the end of the output, and the emptied
+/// chunk is kept as a spare buffer (see [`FeedMap::take_spare`]) that the next chunk
+/// can be read into
+///
+/// ```rust
+/// use vec_utils::FeedMap;
+///
+/// let mut feed = FeedMap::with_capacity(6, |x: u8| x as char);
+///
+/// feed.feed(b"hel".to_vec());
+///
+/// let mut chunk = feed.take_spare();
+/// assert!(chunk.capacity() >= 3);
+/// chunk.extend_from_slice(b"lo");
+/// feed.feed(chunk);
+///
+/// feed.feed_iter(b"!".iter().copied());
+///
+/// assert_eq!(feed.finish(), ['h', 'e', 'l', 'l', 'o', '!']);
+/// ```
+pub struct FeedMap<T, U, F> {
+    output: Vec<U>,
+    spare: Vec<T>,
+    f: F,
+    input: PhantomData<fn(T)>,
+}
+
+impl<T, U, F: FnMut(T) -> U> FeedMap<T, U, F> {
+    /// Prepare to map the chunks, the output is taken from the first
+    /// chunk if possible, otherwise it's allocated when it's needed
+    pub fn new(f: F) -> Self {
+        Self {
+            output: Vec::new(),
+            spare: Vec::new(),
+            f,
+            input: PhantomData,
+        }
+    }
+
+    /// Prepare to map the chunks into an output with at least `capacity` elements,
+    /// which is taken from the thread-local [`RecycleBin`] if possible
+    pub fn with_capacity(capacity: usize, f: F) -> Self {
+        Self {
+            output: RecycleBin::take_local(capacity)
+                .unwrap_or_else(|| Vec::with_capacity(capacity)),
+            spare: Vec::new(),
+            f,
+            input: PhantomData,
+        }
+    }
+
+    /// Prepare to map the chunks into the allocation of `donor`, if it can
+    /// hold `U`s, see [`scale_capacity`](crate::scale_capacity)
+    ///
+    /// The elements of `donor` are dropped
+    pub fn with_output<D>(donor: Vec<D>, f: F) -> Self {
+        Self {
+            output: convert_alloc(donor).unwrap_or_default(),
+            spare: Vec::new(),
+            f,
+            input: PhantomData,
+        }
+    }
+
+    /// The number of elements in the output
+    pub fn len(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Check if nothing was written to the output yet
+    pub fn is_empty(&self) -> bool {
+        self.output.is_empty()
+    }
+
+    /// Map the next chunk of the input onto the end of the output
+    ///
+    /// If the chunk isn't mapped in place, it is kept as the spare buffer,
+    /// and the previous spare buffer is dropped
+    pub fn feed(&mut self, mut chunk: Vec<T>) {
+        if self.output.is_empty()
+            && self.output.capacity() < chunk.capacity()
+            && Layout::new::<T>() == Layout::new::<U>()
+        {
+            self.output = chunk.map(&mut self.f);
+            return;
+        }
+
+        self.output.reserve(chunk.len());
+        self.output.extend(chunk.drain(..).map(&mut self.f));
+        self.spare = chunk;
+    }
+
+    /// Take the spare buffer, which is an empty vector with the allocation
+    /// of the last chunk that wasn't mapped in place
+    ///
+    /// If there is no spare buffer, an empty vector without an allocation is returned
+    pub fn take_spare(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.spare)
+    }
+
+    /// Map the elements of `iter` onto the end of the output
+    pub fn feed_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.output.extend(iter.into_iter().map(&mut self.f));
+    }
+
+    /// Take the output
+    pub fn finish(self) -> Vec<U> {
+        self.output
+    }
+}
@@ -0,0 +1,111 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use super::{Input, Output};
+use crate::raw::RawAllocGuard;
+use crate::{cleanup, faults, ReuseConfig};
+
+/// The state of `flat_map_reuse`, if `f` panics this drops the unread input,
+/// the written output, and frees the allocations
+struct FlatMap<T, U> {
+    input: Input<T>,
+    // the number of elements read from `input`
+    read: usize,
+    output: Output<U>,
+    // if the unread input was moved out of the allocation of `output`
+    spilled: bool,
+}
+
+impl<T, U> Drop for FlatMap<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let _alloc = RawAllocGuard::new(self.output.start, self.output.cap);
+
+            let (start, len) = (self.output.start, self.output.len());
+            defer! {
+                cleanup::drop_slice(start, len);
+            }
+
+            self.input.drop_rest(self.read);
+        }
+    }
+}
+
+impl<T, U> FlatMap<T, U> {
+    /// Move the unread input into a new allocation, so that
+    /// the output owns the whole allocation that it writes into
+    unsafe fn spill(&mut self) {
+        let rest = self.input.len - self.read;
+
+        let mut vec = Vec::with_capacity(rest);
+        std::ptr::copy_nonoverlapping(self.input.ptr, vec.as_mut_ptr(), rest);
+        vec.set_len(rest);
+
+        // the old input doesn't own any elements or the allocation anymore
+        self.input = Input::from(vec);
+        self.read = 0;
+        self.spilled = true;
+    }
+
+    unsafe fn write(&mut self, value: U) {
+        let len = self.output.len();
+
+        // every element before `read` was read, so it can be overwritten
+        if !self.spilled && len == self.read {
+            self.spill();
+        }
+
+        if self.spilled && len == self.output.cap {
+            self.output.reserve(1);
+        }
+
+        self.output.write(value);
+    }
+}
+
+pub(crate) fn flat_map_reuse<T, U, I: IntoIterator<Item = U>, F: FnMut(T) -> I>(
+    vec: Vec<T>,
+    config: ReuseConfig,
+    f: F,
+) -> Vec<U> {
+    let mut f = faults::hook(f);
+
+    // the length of the output is predicted from the length of the input
+    if Layout::new::<T>() != Layout::new::<U>()
+        || std::mem::size_of::<U>() == 0
+        || !config.reuses(vec.len(), vec.capacity())
+    {
+        let iter = vec.into_iter().flat_map(f);
+        let capacity = iter.size_hint().0;
+        return config.collect(iter, capacity);
+    }
+
+    unsafe {
+        let mut input = Input::from(vec);
+        let output = input.take_output::<U>();
+
+        let mut state = FlatMap {
+            input,
+            read: 0,
+            output,
+            spilled: false,
+        };
+
+        while state.read < state.input.len {
+            let value = state.input.next_unchecked();
+            state.read += 1;
+
+            for value in f(value) {
+                state.write(value);
+            }
+        }
+
+        let mut state = ManuallyDrop::new(state);
+
+        // frees the allocation of the input if it was spilled
+        let read = state.read;
+        state.input.drop_rest(read);
+
+        config.finish(std::ptr::read(&state.output).into_vec())
+    }
+}
@@ -0,0 +1,74 @@
+/// Extension methods for `Vec<Vec<T>>`
+pub trait VecVecExt: Sized {
+    /// The type that the inner vectors store
+    type T;
+
+    /// Flatten a vector of vectors into a single vector, will try and reuse
+    /// the inner vector with the largest capacity if it can hold all of the elements
+    ///
+    /// If no inner vector is large enough, then a new vector is allocated with exactly
+    /// enough capacity. In either case, all other inner vectors are freed
+    fn flatten_reuse(self) -> Vec<Self::T>;
+}
+
+impl<T> VecVecExt for Vec<Vec<T>> {
+    type T = T;
+
+    fn flatten_reuse(mut self) -> Vec<T> {
+        let total_len = self.iter().map(Vec::len).sum::<usize>();
+
+        let largest = self
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, vec)| vec.capacity())
+            .map(|(i, _)| i);
+
+        let index = match largest {
+            Some(i) if self[i].capacity() >= total_len => i,
+            _ => {
+                let mut output = Vec::with_capacity(total_len);
+
+                for vec in &mut self {
+                    output.append(vec);
+                }
+
+                return output;
+            }
+        };
+
+        let mut output = std::mem::take(&mut self[index]);
+        let (before, after) = self.split_at_mut(index);
+
+        let before_len = before.iter().map(Vec::len).sum::<usize>();
+        let own_len = output.len();
+
+        // nothing in here can panic, we only move bytes around so the
+        // elements are never in two places at once from the view of a destructor
+        unsafe {
+            let ptr = output.as_mut_ptr();
+
+            output.set_len(0);
+
+            // make space for all of the elements that come before the output vector
+            std::ptr::copy(ptr, ptr.add(before_len), own_len);
+
+            let mut pos = ptr;
+            for vec in before {
+                let len = vec.len();
+                vec.set_len(0);
+
+                std::ptr::copy_nonoverlapping(vec.as_ptr(), pos, len);
+                pos = pos.add(len);
+            }
+
+            output.set_len(before_len + own_len);
+        }
+
+        // there is enough capacity, so this won't reallocate
+        for vec in &mut after[1..] {
+            output.append(vec);
+        }
+
+        output
+    }
+}
@@ -0,0 +1,158 @@
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::resumable::MapState;
+use super::ResumableMap;
+use crate::RawParts;
+
+/// Extension methods for mapping vectors with async functions
+pub trait AsyncVecExt: Sized {
+    /// The type that the `Vec<T>` stores
+    type T;
+
+    /// Map a vector with an async function, awaiting each element in order
+    ///
+    /// Like `VecExt::try_map`, the allocation is reused if the layouts of `T` and `U`
+    /// match. On the first error, all of the outputs and the rest of the input are dropped.
+    /// If the returned future is dropped before it completes, the outputs that were
+    /// already produced and the inputs that weren't consumed yet are dropped
+    ///
+    /// # Panic
+    ///
+    /// The returned future panics if it's polled after it completed, or after
+    /// `f` or the future that it returned panicked
+    ///
+    /// ```rust
+    /// use vec_utils::AsyncVecExt;
+    ///
+    /// async fn parse(v: Vec<&str>) -> Result<Vec<u32>, std::num::ParseIntError> {
+    ///     v.try_map_async(|x| async move { x.parse() }).await
+    /// }
+    /// ```
+    fn try_map_async<U, E, Fut: Future<Output = Result<U, E>>, F: FnMut(Self::T) -> Fut>(
+        self,
+        f: F,
+    ) -> TryMapAsync<Self::T, U, F, Fut>;
+}
+
+impl<T, C: RawParts<Item = T>> AsyncVecExt for C {
+    type T = T;
+
+    fn try_map_async<U, E, Fut: Future<Output = Result<U, E>>, F: FnMut(T) -> Fut>(
+        self,
+        f: F,
+    ) -> TryMapAsync<T, U, F, Fut> {
+        TryMapAsync {
+            state: MapState::new(self.into_vec()),
+            f,
+            pending: None,
+        }
+    }
+}
+
+/// The future returned by [`AsyncVecExt::try_map_async`]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct TryMapAsync<T, U, F, Fut> {
+    // the element that is currently being mapped is owned by this future
+    pending: Option<Fut>,
+    state: MapState<T, U>,
+    f: F,
+}
+
+impl<T, U, E, F: FnMut(T) -> Fut, Fut: Future<Output = Result<U, E>>> Future
+    for TryMapAsync<T, U, F, Fut>
+{
+    type Output = Result<Vec<U>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // only `pending` is structurally pinned, and it is never moved out of
+        let this = unsafe { self.get_unchecked_mut() };
+
+        match this.state {
+            MapState::Finished => panic!("`TryMapAsync` polled after completion"),
+            MapState::Poisoned => panic!("`TryMapAsync` polled after it panicked"),
+            MapState::InPlace(_) | MapState::Fresh(..) => (),
+        }
+
+        loop {
+            if let Some(pending) = this.pending.as_mut() {
+                let pending = unsafe { Pin::new_unchecked(pending) };
+                let cx = &mut *cx;
+                let poll = this.state.poison_on_panic(move || pending.poll(cx));
+
+                let result = match poll {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                this.pending = None;
+
+                match result {
+                    Ok(value) => unsafe { this.state.push(value) },
+                    Err(err) => {
+                        this.state = MapState::Finished;
+                        return Poll::Ready(Err(err));
+                    }
+                }
+            }
+
+            match this.state.next() {
+                Some(value) => {
+                    let f = &mut this.f;
+                    this.pending = Some(this.state.poison_on_panic(move || f(value)));
+                }
+                None => return Poll::Ready(Ok(unsafe { this.state.finish() })),
+            }
+        }
+    }
+}
+
+impl<T, U, F: FnMut(T) -> U> ResumableMap<T, U, F> {
+    /// Run the rest of the map as a future, which maps at most `chunk` elements
+    /// each time it's polled, and then yields to the executor
+    ///
+    /// If the future is dropped before it completes, the outputs that were
+    /// already produced and the rest of the input are dropped
+    ///
+    /// ```rust
+    /// use vec_utils::ResumableMap;
+    ///
+    /// async fn to_bits(v: Vec<f32>) -> Vec<u32> {
+    ///     ResumableMap::new(v, f32::to_bits).run_async(1024).await
+    /// }
+    /// ```
+    pub fn run_async(self, chunk: usize) -> RunAsync<T, U, F> {
+        RunAsync {
+            map: self,
+            chunk: chunk.max(1),
+        }
+    }
+}
+
+/// The future returned by [`ResumableMap::run_async`]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RunAsync<T, U, F> {
+    map: ResumableMap<T, U, F>,
+    chunk: usize,
+}
+
+// nothing is structurally pinned
+impl<T, U, F> Unpin for RunAsync<T, U, F> {}
+
+impl<T, U, F: FnMut(T) -> U> Future for RunAsync<T, U, F> {
+    type Output = Vec<U>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<U>> {
+        let this = self.get_mut();
+
+        match this.map.run_for(this.chunk) {
+            ControlFlow::Break(vec) => Poll::Ready(vec),
+            ControlFlow::Continue(()) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::alloc::Layout;
+use std::convert::Infallible;
+use std::fmt;
+use std::mem::ManuallyDrop;
+
+use crate::raw::RawAllocGuard;
+
+/// The error returned by [`VecExt::gather_map`](crate::VecExt::gather_map)
+/// if the indices can't be gathered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GatherError {
+    /// An index is past the end of the vector
+    OutOfBounds {
+        /// The index
+        index: usize,
+        /// The length of the vector
+        len: usize,
+    },
+    /// An index appears more than once
+    Duplicate(usize),
+}
+
+impl fmt::Display for GatherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            GatherError::OutOfBounds { index, len } => write!(
+                f,
+                "the index {} is out of bounds for a vector of length {}",
+                index, len
+            ),
+            GatherError::Duplicate(index) => write!(f, "the index {} was gathered twice", index),
+        }
+    }
+}
+
+impl std::error::Error for GatherError {}
+
+impl From<Infallible> for GatherError {
+    fn from(x: Infallible) -> Self {
+        match x {}
+    }
+}
+
+const BITS: usize = usize::BITS as usize;
+
+pub(super) struct BitSet(Vec<usize>);
+
+impl BitSet {
+    pub(super) fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(BITS)])
+    }
+
+    pub(super) fn get(&self, index: usize) -> bool {
+        self.0[index / BITS] & (1 << (index % BITS)) != 0
+    }
+
+    pub(super) fn set(&mut self, index: usize) {
+        self.0[index / BITS] |= 1 << (index % BITS);
+    }
+}
+
+/// Tracks which inputs were moved out, and which outputs were written
+/// in place, the rest of the inputs are dropped when this is dropped
+struct Gather<T, U> {
+    input: *mut T,
+    len: usize,
+    consumed: BitSet,
+    output: *mut U,
+    written: BitSet,
+    written_len: usize,
+}
+
+impl<T, U> Gather<T, U> {
+    unsafe fn take(&mut self, index: usize) -> T {
+        self.consumed.set(index);
+        self.input.add(index).read()
+    }
+}
+
+impl<T, U> Drop for Gather<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let (input, len) = (self.input, self.len);
+            let consumed = &mut self.consumed;
+
+            defer! {
+                for i in 0..len {
+                    if !consumed.get(i) {
+                        consumed.set(i);
+                        input.add(i).drop_in_place();
+                    }
+                }
+            }
+
+            for i in 0..self.written_len {
+                if self.written.get(i) {
+                    self.output.add(i).drop_in_place();
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn gather_map<T, U, F: FnMut(T) -> U>(
+    vec: Vec<T>,
+    indices: &[usize],
+    mut f: F,
+) -> Result<Vec<U>, GatherError> {
+    let len = vec.len();
+    let mut used = BitSet::new(len);
+
+    for &index in indices {
+        if index >= len {
+            return Err(GatherError::OutOfBounds { index, len });
+        }
+
+        if used.get(index) {
+            return Err(GatherError::Duplicate(index));
+        }
+
+        used.set(index);
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+    let (ptr, cap) = (vec.as_mut_ptr(), vec.capacity());
+    let out_len = indices.len();
+
+    unsafe {
+        let alloc = RawAllocGuard::new(ptr, cap);
+
+        if Layout::new::<T>() != Layout::new::<U>() {
+            let mut gather = Gather::<T, U> {
+                input: ptr,
+                len,
+                consumed: BitSet::new(len),
+                output: std::ptr::null_mut(),
+                written: BitSet::new(0),
+                written_len: 0,
+            };
+
+            let mut output = Vec::with_capacity(out_len);
+
+            for &index in indices {
+                output.push(f(gather.take(index)));
+            }
+
+            // drops the inputs that weren't gathered
+            drop(gather);
+
+            return Ok(output);
+        }
+
+        let mut gather = Gather {
+            input: ptr,
+            len,
+            consumed: BitSet::new(len),
+            output: ptr as *mut U,
+            written: BitSet::new(out_len),
+            written_len: out_len,
+        };
+
+        // writes the output at `pos`, which must be free, and then follows the chain of
+        // inputs that were just moved out, until it reaches a slot that isn't an output
+        // or was already written. `held` is the first input of a cycle, which was
+        // moved out to make room for the first output
+        let mut fill = |gather: &mut Gather<T, U>, mut pos: usize, mut held: Option<(usize, T)>| loop {
+            let index = indices[pos];
+
+            let value = match held.take() {
+                Some((i, value)) if i == index => value,
+                other => {
+                    held = other;
+                    gather.take(index)
+                }
+            };
+
+            gather.output.add(pos).write(f(value));
+            gather.written.set(pos);
+
+            if index < out_len && !gather.written.get(index) {
+                pos = index;
+            } else {
+                break;
+            }
+        };
+
+        // the slots whose input isn't gathered start a chain
+        for pos in 0..out_len {
+            if !used.get(pos) {
+                gather.consumed.set(pos);
+                ptr.add(pos).drop_in_place();
+                fill(&mut gather, pos, None);
+            }
+        }
+
+        // the rest of the slots are in cycles
+        for pos in 0..out_len {
+            if !gather.written.get(pos) {
+                let held = gather.take(pos);
+                fill(&mut gather, pos, Some((pos, held)));
+            }
+        }
+
+        // the outputs are kept, and the inputs that weren't gathered are dropped
+        gather.written_len = 0;
+        drop(gather);
+
+        std::mem::forget(alloc);
+
+        Ok(Vec::from_raw_parts(ptr as *mut U, out_len, cap))
+    }
+}
@@ -1,11 +1,21 @@
 use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 
-use super::{r#try, Input, Output, Try};
+use super::{r#try, Input, Output, RawParts, Try};
+use crate::raw::RawAllocGuard;
+use crate::{cleanup, faults, reuse_config, ReuseConfig};
 
 use seal::Seal;
 mod seal {
     use super::*;
 
+    /// The implementation details of [`Tuple`](super::Tuple)
+    ///
+    /// # Safety
+    ///
+    /// This trait is only implemented for the cons-lists of `TupleElem`s
+    /// in this module, and has the same requirements as `TupleElem`
     pub unsafe trait Seal {
         const LEN: u64;
 
@@ -21,9 +31,22 @@ mod seal {
 
         fn check_layout<V>() -> bool;
 
-        fn max_cap<V>(data: &Self::Data, depth: &mut u64) -> Option<usize>;
+        fn max_cap<V>(
+            data: &Self::Data,
+            len: usize,
+            config: &ReuseConfig,
+            depth: &mut u64,
+        ) -> Option<usize>;
+
+        unsafe fn take_output<V>(
+            data: &mut Self::Data,
+            len: usize,
+            config: &ReuseConfig,
+        ) -> Option<Output<V>>;
 
-        unsafe fn take_output<V>(data: &mut Self::Data) -> Output<V>;
+        fn check_first_layout<V>() -> bool;
+
+        unsafe fn take_first_output<V>(data: &mut Self::Data) -> Output<V>;
 
         unsafe fn take_output_impl<V>(_: &mut Self::Data, min_cap: u64) -> Output<V>;
 
@@ -48,7 +71,7 @@ pub trait Tuple: Seal {}
 /// * It must be valid to call `next_unchecked` at least `len` times
 /// * `len <= capacity`
 /// * if `next_unchecked` defers to another `T: TupleElem`, then you should not call `T::next_unchecked` more than once
-///     in your own `next_unchecked`
+///   in your own `next_unchecked`
 #[allow(clippy::len_without_is_empty)]
 pub unsafe trait TupleElem {
     /// The items yielded from this element
@@ -82,9 +105,9 @@ pub unsafe trait TupleElem {
     /// Try and create a new output data-segment, if the output segment
     /// is created, then it owns it's allocation. So you must not deallocate
     /// the allocation backing `Output<V>`
-    /// 
+    ///
     /// # Safety
-    /// 
+    ///
     /// `check_layout::<V>` must return true.
     unsafe fn take_output<V>(data: &mut Self::Data) -> Output<V>;
 
@@ -100,7 +123,7 @@ pub unsafe trait TupleElem {
     ///
     /// # Safety
     ///
-    /// This function should only be called once, and 
+    /// This function should only be called once, and
     /// `data` should not be used again
     unsafe fn drop_rest(data: &mut Self::Data, len: usize);
 }
@@ -151,29 +174,29 @@ unsafe impl<A: TupleElem> TupleElem for (A,) {
     }
 }
 
-unsafe impl<A> TupleElem for Vec<A> {
+unsafe impl<A, C: RawParts<Item = A>> TupleElem for C {
     type Item = A;
     type Data = Input<A>;
     type Iter = std::vec::IntoIter<A>;
 
     #[inline(always)]
     fn capacity(data: &Self::Data) -> usize {
-        data.cap
+        data.capacity()
     }
 
     #[inline(always)]
     fn len(&self) -> usize {
-        self.len()
+        self.as_slice().len()
     }
 
     #[inline]
     fn into_data(self) -> Self::Data {
-        Input::from(self)
+        Input::from(self.into_vec())
     }
 
     #[inline]
     fn into_iterator(self) -> Self::Iter {
-        self.into_iter()
+        self.into_vec().into_iter()
     }
 
     #[inline]
@@ -183,28 +206,17 @@ unsafe impl<A> TupleElem for Vec<A> {
 
     #[inline]
     unsafe fn take_output<V>(data: &mut Self::Data) -> Output<V> {
-        debug_assert!(Layout::new::<A>() == Layout::new::<V>());
-
-        data.drop_alloc = false;
-        Output::new(data.start as *mut V, data.cap)
+        data.take_output()
     }
 
     #[inline]
     unsafe fn next_unchecked(data: &mut Self::Data) -> Self::Item {
-        let ptr = data.ptr;
-        data.ptr = data.ptr.add(1);
-        ptr.read()
+        data.next_unchecked()
     }
 
     #[inline]
     unsafe fn drop_rest(data: &mut Self::Data, len: usize) {
-        defer! {
-            if data.drop_alloc {
-                Vec::from_raw_parts(data.start, 0, data.cap);
-            }
-        }
-
-        std::ptr::drop_in_place(std::slice::from_raw_parts_mut(data.ptr, data.len - len));
+        data.drop_rest(len)
     }
 }
 
@@ -237,17 +249,39 @@ unsafe impl<A: TupleElem> Seal for (A,) {
     }
 
     #[inline]
-    fn max_cap<V>(data: &Self::Data, depth: &mut u64) -> Option<usize> {
-        if A::check_layout::<V>() {
+    fn max_cap<V>(
+        data: &Self::Data,
+        len: usize,
+        config: &ReuseConfig,
+        depth: &mut u64,
+    ) -> Option<usize> {
+        let cap = A::capacity(data);
+
+        if A::check_layout::<V>() && config.reuses(len, cap) {
             *depth = Self::LEN;
-            Some(A::capacity(data))
+            Some(cap)
         } else {
             None
         }
     }
 
     #[inline]
-    unsafe fn take_output<V>(data: &mut Self::Data) -> Output<V> {
+    unsafe fn take_output<V>(
+        data: &mut Self::Data,
+        len: usize,
+        config: &ReuseConfig,
+    ) -> Option<Output<V>> {
+        Self::max_cap::<V>(data, len, config, &mut 0)?;
+        Some(A::take_output::<V>(data))
+    }
+
+    #[inline]
+    fn check_first_layout<V>() -> bool {
+        A::check_layout::<V>()
+    }
+
+    #[inline]
+    unsafe fn take_first_output<V>(data: &mut Self::Data) -> Output<V> {
         A::take_output::<V>(data)
     }
 
@@ -297,12 +331,16 @@ unsafe impl<A: TupleElem, T: Seal> Seal for (A, T) {
     }
 
     #[inline]
-    fn max_cap<V>((a, rest): &Self::Data, depth: &mut u64) -> Option<usize> {
-        let cap_rest = T::max_cap::<V>(rest, depth);
-
-        if A::check_layout::<V>() {
-            let cap = A::capacity(a);
-
+    fn max_cap<V>(
+        (a, rest): &Self::Data,
+        len: usize,
+        config: &ReuseConfig,
+        depth: &mut u64,
+    ) -> Option<usize> {
+        let cap_rest = T::max_cap::<V>(rest, len, config, depth);
+        let cap = A::capacity(a);
+
+        if A::check_layout::<V>() && config.reuses(len, cap) {
             if let Some(cap_rest) = cap_rest {
                 if cap_rest > cap {
                     return Some(cap_rest);
@@ -317,11 +355,24 @@ unsafe impl<A: TupleElem, T: Seal> Seal for (A, T) {
     }
 
     #[inline]
-    unsafe fn take_output<V>(data: &mut Self::Data) -> Output<V> {
+    unsafe fn take_output<V>(
+        data: &mut Self::Data,
+        len: usize,
+        config: &ReuseConfig,
+    ) -> Option<Output<V>> {
         let mut depth = 0;
-        let val = Self::max_cap::<V>(data, &mut depth);
-        debug_assert!(val.is_some());
-        Self::take_output_impl(data, depth)
+        Self::max_cap::<V>(data, len, config, &mut depth)?;
+        Some(Self::take_output_impl(data, depth))
+    }
+
+    #[inline]
+    fn check_first_layout<V>() -> bool {
+        A::check_layout::<V>()
+    }
+
+    #[inline]
+    unsafe fn take_first_output<V>((a, _): &mut Self::Data) -> Output<V> {
+        A::take_output::<V>(a)
     }
 
     #[inline]
@@ -366,24 +417,104 @@ struct ZipWithIter<V, In: Tuple> {
 }
 
 /// Does the work of the `try_zip_with` or `zip_with` macros.
+///
+/// Only the inputs that the process-wide [`ReuseConfig`] allows are reused
 pub fn try_zip_with_impl<R: Try, In: Tuple>(
     input: In,
     f: impl FnMut(In::Item) -> R,
 ) -> Result<Vec<R::Ok>, R::Error> {
+    let config = reuse_config();
+    let len = input.remaining_len();
+
     if In::check_layout::<R::Ok>() {
-        let len = input.remaining_len();
         let mut input = input.into_data();
 
+        let output = match unsafe { In::take_output::<R::Ok>(&mut input, len, &config) } {
+            Some(output) => output,
+            None => {
+                // the inputs are already taken apart, so the output
+                // is written into a new allocation instead
+                let mut vec = ManuallyDrop::new(config.allocate::<R::Ok>(len));
+                unsafe { Output::new(vec.as_mut_ptr(), vec.capacity()) }
+            }
+        };
+
         ZipWithIter::<_, In> {
-            output: unsafe { In::take_output::<R::Ok>(&mut input) },
+            output,
             input,
             initial_len: len,
             remaining_len: len,
             should_free_output: true,
         }
         .try_into_vec(f)
+        .map(|vec| config.finish(vec))
     } else {
-        input.into_iterator().map(f).map(R::into_result).collect()
+        config.try_collect(
+            input
+                .into_iterator()
+                .map(faults::hook(f))
+                .map(R::into_result),
+            len,
+        )
+    }
+}
+
+/// The same as `try_zip_with_impl`, but always reuses the allocation of the first element
+///
+/// # Panic
+///
+/// If the layout of the output doesn't match the layout of the first element
+#[doc(hidden)]
+pub fn try_zip_assign_impl<R: Try, In: Tuple>(
+    input: In,
+    f: impl FnMut(In::Item) -> R,
+) -> Result<Vec<R::Ok>, R::Error> {
+    assert!(
+        In::check_first_layout::<R::Ok>(),
+        "the output layout must match the layout of the first vector"
+    );
+
+    let len = input.remaining_len();
+    let mut input = input.into_data();
+
+    ZipWithIter::<_, In> {
+        output: unsafe { In::take_first_output::<R::Ok>(&mut input) },
+        input,
+        initial_len: len,
+        remaining_len: len,
+        should_free_output: true,
+    }
+    .try_into_vec(f)
+}
+
+/// Checks if the output can reuse the allocation of the first element
+pub(crate) fn check_first_layout<In: Tuple, V>() -> bool {
+    In::check_first_layout::<V>()
+}
+
+/// Convert the cons-list into an iterator, without reusing any allocations
+pub(crate) fn into_iterator<In: Tuple>(input: In) -> In::Iter {
+    input.into_iterator()
+}
+
+/// Checks at compile time that the output of `zip_assign` has
+/// the same layout as the vector it is assigned to
+#[doc(hidden)]
+pub struct LayoutCheck<T>(PhantomData<T>);
+
+impl<T> LayoutCheck<T> {
+    pub fn new<C: RawParts<Item = T>>(_: &C) -> Self {
+        Self(PhantomData)
+    }
+
+    pub fn verify<U>(self, _: &Vec<U>) {
+        const {
+            assert!(
+                std::mem::size_of::<T>() == std::mem::size_of::<U>()
+                    && std::mem::align_of::<T>() == std::mem::align_of::<U>(),
+                "the output layout must match the layout of the first vector"
+            )
+        }
     }
 }
 
@@ -399,20 +530,16 @@ impl<V, In: Tuple> ZipWithIter<V, In> {
                 self.remaining_len = remaining_len;
 
                 let input = In::next_unchecked(&mut self.input);
+                faults::closure();
 
-                self.output.ptr.write(r#try!(f(input)));
-                self.output.ptr = self.output.ptr.add(1);
+                self.output.write(r#try!(f(input)));
             }
 
             // We don't want to drop `self` if dropping the excess elements panics
             // as that could lead to double drops
             self.should_free_output = false;
-            
-            let (ptr, len, cap) = (
-                self.output.start,
-                self.initial_len,
-                self.output.cap,
-            );
+
+            let (ptr, len, cap) = (self.output.start, self.initial_len, self.output.cap);
 
             drop(self);
 
@@ -435,10 +562,14 @@ impl<V, In: Tuple> Drop for ZipWithIter<V, In> {
 
         let initialized_len = initial_len - remaining_len;
 
+        // frees the output allocation last, without running destructors
+        let _output =
+            should_free_output.then(|| unsafe { RawAllocGuard::new(output.start, output.cap) });
+
         defer! {
             if should_free_output {
                 unsafe {
-                    Vec::from_raw_parts(output.start, initialized_len - 1, output.cap);
+                    cleanup::drop_slice(output.start, initialized_len - 1);
                 }
             }
         }
@@ -0,0 +1,178 @@
+use std::alloc::Layout;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+
+/// A draining iterator over part of a vector, see [`VecExt::group_adjacent_map`]
+/// and [`ReuseIter::split_tail`]
+///
+/// Any elements that are not yielded are dropped with the iterator
+///
+/// [`VecExt::group_adjacent_map`]: crate::VecExt::group_adjacent_map
+/// [`ReuseIter::split_tail`]: crate::ReuseIter::split_tail
+pub struct Drain<'a, T> {
+    ptr: *mut T,
+    start: usize,
+    end: usize,
+    mark: PhantomData<&'a mut [T]>,
+}
+
+impl<T> Drain<'_, T> {
+    /// # Safety
+    ///
+    /// The elements from `ptr.add(start)` to `ptr.add(end)` must be initialized,
+    /// and owned by the returned iterator for its lifetime
+    pub(crate) unsafe fn new(ptr: *mut T, start: usize, end: usize) -> Self {
+        Self {
+            ptr,
+            start,
+            end,
+            mark: PhantomData,
+        }
+    }
+
+    /// The elements that have not been yielded yet
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr.add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let value = unsafe { self.ptr.add(self.start).read() };
+        self.start += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Drain<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        Some(unsafe { self.ptr.add(self.end).read() })
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+impl<T> FusedIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr.add(self.start), self.end - self.start) }
+    }
+}
+
+/// Owns the written prefix of the output and the unread suffix of the input
+struct Groups<T, U> {
+    input: *mut T,
+    read: usize,
+    len: usize,
+    output: *mut U,
+    written: usize,
+}
+
+impl<T, U> Drop for Groups<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let (input, read, len) = (self.input, self.read, self.len);
+
+            defer! {
+                cleanup::drop_slice(input.add(read), len - read);
+            }
+
+            cleanup::drop_slice(self.output, self.written);
+        }
+    }
+}
+
+pub(crate) fn group_adjacent_map<T, K, U, F, G>(vec: Vec<T>, mut key: F, mut fold: G) -> Vec<U>
+where
+    K: PartialEq,
+    F: FnMut(&T) -> K,
+    G: FnMut(K, Drain<'_, T>) -> U,
+{
+    let mut vec = ManuallyDrop::new(vec);
+    let (in_ptr, len, in_cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    // every run has at least one element, so output `k` is written after at least
+    // `k + 1` elements are read, and never reaches an unread element
+    let reused_cap = raw::scale(in_cap, Layout::new::<T>(), Layout::new::<U>())
+        .ok()
+        .filter(|_| size_of::<U>() <= size_of::<T>());
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = reused_cap.map(|cap| RawAllocGuard::new(in_ptr as *mut U, cap));
+        let _in_alloc = reused_cap
+            .is_none()
+            .then(|| RawAllocGuard::new(in_ptr, in_cap));
+
+        let mut spill = Vec::<U>::new();
+
+        let mut groups = Groups {
+            input: in_ptr,
+            read: 0,
+            len,
+            output: in_ptr as *mut U,
+            written: 0,
+        };
+
+        let mut next_key = if len == 0 { None } else { Some(key(&*in_ptr)) };
+
+        while let Some(run_key) = next_key.take() {
+            let start = groups.read;
+            let mut end = start + 1;
+
+            while end < len {
+                let k = key(&*in_ptr.add(end));
+
+                if k != run_key {
+                    next_key = Some(k);
+                    break;
+                }
+
+                end += 1;
+            }
+
+            // the run is owned by the drain from here on
+            groups.read = end;
+
+            let value = fold(run_key, Drain::new(in_ptr, start, end));
+
+            if reused_cap.is_some() {
+                groups.output.add(groups.written).write(value);
+                groups.written += 1;
+            } else {
+                spill.push(value);
+            }
+        }
+
+        let written = groups.written;
+        std::mem::forget(groups);
+
+        match (reused_cap, out_alloc) {
+            (Some(cap), Some(out_alloc)) => {
+                std::mem::forget(out_alloc);
+                Vec::from_raw_parts(in_ptr as *mut U, written, cap)
+            }
+            _ => spill,
+        }
+    }
+}
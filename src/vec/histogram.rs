@@ -0,0 +1,100 @@
+use std::alloc::Layout;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+use crate::RecycleBin;
+
+fn out_of_bounds(bucket: usize, buckets: usize) -> ! {
+    panic!(
+        "the bucket {} is out of bounds for {} buckets",
+        bucket, buckets
+    )
+}
+
+pub(crate) fn count_into<T, F: FnMut(&T) -> usize>(vec: Vec<T>, counts: &mut [usize], mut f: F) {
+    let buckets = counts.len();
+
+    for value in vec {
+        let bucket = f(&value);
+        drop(value);
+
+        match counts.get_mut(bucket) {
+            Some(count) => *count += 1,
+            None => out_of_bounds(bucket, buckets),
+        }
+    }
+}
+
+/// Owns the unread elements of the input
+struct Rest<T> {
+    ptr: *mut T,
+    read: usize,
+    len: usize,
+}
+
+impl<T> Drop for Rest<T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr.add(self.read), self.len - self.read) }
+    }
+}
+
+pub(crate) fn histogram<T, F: FnMut(&T) -> usize>(
+    vec: Vec<T>,
+    buckets: usize,
+    mut f: F,
+) -> Vec<usize> {
+    let out_cap = raw::scale(vec.capacity(), Layout::new::<T>(), Layout::new::<usize>())
+        .ok()
+        .filter(|&cap| cap >= buckets);
+
+    // the counts are kept in the spare capacity, until the elements are dropped
+    let spare = (vec.capacity() - vec.len()).checked_mul(size_of::<T>());
+    let out_cap =
+        out_cap.filter(|_| spare.is_some_and(|spare| spare / size_of::<usize>() >= buckets));
+
+    let out_cap = match out_cap {
+        Some(out_cap) => out_cap,
+        None => {
+            let mut counts = RecycleBin::take_local(buckets).unwrap_or_default();
+            counts.resize(buckets, 0);
+            count_into(vec, &mut counts, f);
+            return counts;
+        }
+    };
+
+    let mut vec = ManuallyDrop::new(vec);
+    let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    unsafe {
+        let alloc = RawAllocGuard::new(ptr, cap);
+
+        // the alignments match, so the end of the elements is aligned for the counts
+        let counts = ptr.add(len) as *mut usize;
+        counts.write_bytes(0, buckets);
+
+        let mut rest = Rest { ptr, read: 0, len };
+
+        while rest.read < len {
+            let value = ptr.add(rest.read).read();
+            rest.read += 1;
+
+            let bucket = f(&value);
+            drop(value);
+
+            if bucket >= buckets {
+                out_of_bounds(bucket, buckets)
+            }
+
+            *counts.add(bucket) += 1;
+        }
+
+        std::mem::forget(rest);
+        std::mem::forget(alloc);
+
+        let out = ptr as *mut usize;
+        out.copy_from(counts, buckets);
+
+        Vec::from_raw_parts(out, buckets, out_cap)
+    }
+}
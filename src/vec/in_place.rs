@@ -0,0 +1,417 @@
+use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Bound, RangeBounds};
+
+use crate::raw::RawAllocGuard;
+use crate::{cleanup, r#try, Try};
+
+/// Convert a range into `start..end`, panicking with the same conditions as slice indexing
+pub(crate) fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .expect("attempted to index slice from after maximum usize"),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .expect("attempted to index slice up to maximum usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    assert!(
+        start <= end,
+        "slice index starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end index {} out of range for slice of length {}",
+        end,
+        len
+    );
+
+    (start, end)
+}
+
+/// If we panic while an element is moved out of the vector, this closes the hole
+/// by shifting the tail of the vector down, so that the vector stays valid
+/// (without the element that was being processed)
+struct Hole<'a, T> {
+    vec: &'a mut Vec<T>,
+    // the index of the element that was moved out
+    index: usize,
+    // the length of the vector before we started
+    len: usize,
+}
+
+impl<T> Drop for Hole<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let ptr = self.vec.as_mut_ptr().add(self.index);
+
+            std::ptr::copy(ptr.add(1), ptr, self.len - self.index - 1);
+
+            self.vec.set_len(self.len - 1);
+        }
+    }
+}
+
+pub(crate) fn map_range<T, R: RangeBounds<usize>, F: FnMut(T) -> T>(
+    vec: &mut Vec<T>,
+    range: R,
+    mut f: F,
+) {
+    let len = vec.len();
+    let (start, end) = resolve_range(range, len);
+
+    unsafe {
+        // if we panic, the hole will be closed by `Hole`, until then
+        // the elements after `start` are owned by the loop
+        vec.set_len(start);
+
+        let ptr = vec.as_mut_ptr();
+
+        for index in start..end {
+            let hole = Hole { vec, index, len };
+
+            let value = f(ptr.add(index).read());
+            ptr.add(index).write(value);
+
+            std::mem::forget(hole);
+        }
+
+        vec.set_len(len);
+    }
+}
+
+pub(crate) fn zip_with_swap<T, U, F: FnMut(T, U) -> (T, U)>(
+    mut a: Vec<T>,
+    mut b: Vec<U>,
+    mut f: F,
+) -> (Vec<T>, Vec<U>) {
+    let (len_a, len_b) = (a.len(), b.len());
+
+    unsafe {
+        // if we panic, the holes will be closed by `Hole`, until then
+        // the elements are owned by the loop
+        a.set_len(0);
+        b.set_len(0);
+
+        let ptr_a = a.as_mut_ptr();
+        let ptr_b = b.as_mut_ptr();
+
+        for index in 0..len_a.min(len_b) {
+            let hole_a = Hole {
+                vec: &mut a,
+                index,
+                len: len_a,
+            };
+            let hole_b = Hole {
+                vec: &mut b,
+                index,
+                len: len_b,
+            };
+
+            let (x, y) = f(ptr_a.add(index).read(), ptr_b.add(index).read());
+            ptr_a.add(index).write(x);
+            ptr_b.add(index).write(y);
+
+            std::mem::forget(hole_a);
+            std::mem::forget(hole_b);
+        }
+
+        a.set_len(len_a);
+        b.set_len(len_b);
+    }
+
+    (a, b)
+}
+
+pub(crate) fn try_map_mut<T, R: Try<Ok = ()>, F: FnMut(&mut T) -> R, G: FnMut(&mut T)>(
+    slice: &mut [T],
+    mut f: F,
+    mut undo: G,
+) -> Result<(), R::Error> {
+    for index in 0..slice.len() {
+        if let Err(err) = f(&mut slice[index]).into_result() {
+            // roll back in the reverse order that the changes were applied
+            slice[..index].iter_mut().rev().for_each(&mut undo);
+
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Owns the allocation while `splice_map` runs. The elements outside of the
+/// replacement are mapped in order, first the prefix `..start`, then the tail.
+/// If we panic, this drops the mapped outputs, the replacement, and the unmapped
+/// inputs, then frees the allocation
+struct SpliceGuard<T, U> {
+    ptr: *mut T,
+    cap: usize,
+    start: usize,
+    // the number of replacement elements written after `start`
+    filled: usize,
+    // the index that the tail currently starts at
+    tail: usize,
+    tail_len: usize,
+    // the number of elements that were mapped
+    mapped: usize,
+    // the number of elements that were moved out, this is
+    // one more than `mapped` while `f` is running
+    read: usize,
+    drop: PhantomData<U>,
+}
+
+impl<T, U> SpliceGuard<T, U> {
+    /// The position of the `index`-th element that is mapped
+    fn position(&self, index: usize) -> usize {
+        if index < self.start {
+            index
+        } else {
+            self.tail + (index - self.start)
+        }
+    }
+
+    /// Drop the elements in `from..to` (in mapping order) as `V`
+    unsafe fn drop_range<V>(&self, from: usize, to: usize) {
+        let ptr = self.ptr as *mut V;
+        let split = self.start.max(from).min(to);
+
+        defer! {
+            cleanup::drop_slice(ptr.add(self.position(split)), to - split);
+        }
+
+        cleanup::drop_slice(ptr.add(from), split - from);
+    }
+}
+
+impl<T, U> Drop for SpliceGuard<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let _alloc = RawAllocGuard::new(self.ptr, self.cap);
+
+            defer! {
+                self.drop_range::<T>(self.read, self.start + self.tail_len);
+            }
+
+            defer! {
+                cleanup::drop_slice((self.ptr as *mut U).add(self.start), self.filled);
+            }
+
+            self.drop_range::<U>(0, self.mapped);
+        }
+    }
+}
+
+pub(crate) fn splice_map<T, U, R: RangeBounds<usize>, F: FnMut(T) -> U>(
+    mut vec: Vec<T>,
+    range: R,
+    replacement: Vec<U>,
+    mut f: F,
+) -> Vec<U> {
+    let len = vec.len();
+    let (start, end) = resolve_range(range, len);
+
+    if Layout::new::<T>() == Layout::new::<U>() {
+        let tail_len = len - end;
+        let new_len = start + replacement.len() + tail_len;
+
+        // grow first, so that the tail only needs to be moved once
+        if new_len > vec.capacity() {
+            vec.reserve_exact(new_len - len);
+        }
+
+        let mut vec = ManuallyDrop::new(vec);
+        let mut replacement = ManuallyDrop::new(replacement);
+
+        let mut guard = SpliceGuard::<T, U> {
+            ptr: vec.as_mut_ptr(),
+            cap: vec.capacity(),
+            start,
+            filled: 0,
+            tail: end,
+            tail_len,
+            mapped: 0,
+            read: 0,
+            drop: PhantomData,
+        };
+
+        let ptr = guard.ptr;
+
+        unsafe {
+            cleanup::drop_slice(ptr.add(start), end - start);
+
+            // move the tail to just after where the replacement goes
+            guard.tail = start + replacement.len();
+            std::ptr::copy(ptr.add(end), ptr.add(guard.tail), tail_len);
+
+            // then move the replacement into the gap, and free it's allocation
+            std::ptr::copy_nonoverlapping(
+                replacement.as_ptr(),
+                (ptr as *mut U).add(start),
+                replacement.len(),
+            );
+            guard.filled = replacement.len();
+            replacement.set_len(0);
+            ManuallyDrop::drop(&mut replacement);
+
+            while guard.read < start + tail_len {
+                let position = guard.position(guard.read);
+                guard.read += 1;
+
+                let value = f(ptr.add(position).read());
+                (ptr as *mut U).add(position).write(value);
+                guard.mapped += 1;
+            }
+
+            let guard = ManuallyDrop::new(guard);
+
+            Vec::from_raw_parts(ptr as *mut U, new_len, guard.cap)
+        }
+    } else {
+        let mut output = Vec::with_capacity(vec.len() - (end - start) + replacement.len());
+        let mut vec = vec.into_iter();
+
+        output.extend(vec.by_ref().take(start).map(&mut f));
+        vec.by_ref().take(end - start).for_each(drop);
+        output.extend(replacement);
+        output.extend(vec.map(f));
+
+        output
+    }
+}
+
+/// This sets the length of the vector after `retain_map` finishes. If we panic,
+/// this shifts the unprocessed elements down to the end of the kept elements,
+/// so that the vector stays valid
+struct RetainGuard<'a, T> {
+    vec: &'a mut Vec<T>,
+    // the index of the element that is currently moved out,
+    // or `len` if we are done
+    read: usize,
+    // the number of elements that were kept
+    write: usize,
+    // the length of the vector before we started
+    len: usize,
+}
+
+impl<T> Drop for RetainGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            if self.read == self.len {
+                self.vec.set_len(self.write);
+                return;
+            }
+
+            let ptr = self.vec.as_mut_ptr();
+            let rest = self.len - self.read - 1;
+
+            std::ptr::copy(ptr.add(self.read + 1), ptr.add(self.write), rest);
+
+            self.vec.set_len(self.write + rest);
+        }
+    }
+}
+
+pub(crate) fn retain_map<T, F: FnMut(T) -> Option<T>>(vec: &mut Vec<T>, mut f: F) {
+    let len = vec.len();
+
+    unsafe {
+        // the elements are owned by the loop until we are done
+        vec.set_len(0);
+
+        let mut guard = RetainGuard {
+            vec,
+            read: 0,
+            write: 0,
+            len,
+        };
+
+        let ptr = guard.vec.as_mut_ptr();
+
+        while guard.read < len {
+            let value = f(ptr.add(guard.read).read());
+
+            if let Some(value) = value {
+                ptr.add(guard.write).write(value);
+                guard.write += 1;
+            }
+
+            guard.read += 1;
+        }
+    }
+}
+
+/// Owns the allocation while `try_filter_map` runs. If we return early or panic,
+/// this drops the kept outputs and the unprocessed inputs, then frees the allocation
+struct FilterMapGuard<T, U> {
+    ptr: *mut T,
+    // the index of the next element to read
+    read: usize,
+    // the number of outputs that were kept
+    write: usize,
+    len: usize,
+    cap: usize,
+    drop: PhantomData<U>,
+}
+
+impl<T, U> Drop for FilterMapGuard<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let _alloc = RawAllocGuard::new(self.ptr, self.cap);
+
+            defer! {
+                cleanup::drop_slice(self.ptr as *mut U, self.write);
+            }
+
+            cleanup::drop_slice(self.ptr.add(self.read), self.len - self.read);
+        }
+    }
+}
+
+/// # Safety
+///
+/// `Layout::new::<T>() == Layout::new::<U>()`
+pub(crate) unsafe fn try_filter_map<T, U, R: Try<Ok = Option<U>>, F: FnMut(T) -> R>(
+    vec: Vec<T>,
+    mut f: F,
+) -> Result<Vec<U>, R::Error> {
+    let mut vec = ManuallyDrop::new(vec);
+
+    let mut guard = FilterMapGuard::<T, U> {
+        ptr: vec.as_mut_ptr(),
+        read: 0,
+        write: 0,
+        len: vec.len(),
+        cap: vec.capacity(),
+        drop: PhantomData,
+    };
+
+    let ptr = guard.ptr;
+
+    while guard.read < guard.len {
+        let index = guard.read;
+        guard.read += 1;
+
+        // every output consumes at least one input, so this
+        // can't overwrite an element that wasn't read yet
+        if let Some(value) = r#try!(f(ptr.add(index).read())) {
+            (ptr as *mut U).add(guard.write).write(value);
+            guard.write += 1;
+        }
+    }
+
+    let guard = ManuallyDrop::new(guard);
+
+    Ok(Vec::from_raw_parts(ptr as *mut U, guard.write, guard.cap))
+}
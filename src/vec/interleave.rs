@@ -0,0 +1,42 @@
+pub(crate) fn interleave<T>(mut a: Vec<T>, mut b: Vec<T>) -> Vec<T> {
+    let (len_a, len_b) = (a.len(), b.len());
+    let len = len_a.checked_add(len_b).expect("capacity overflow");
+    let pairs = len_a.min(len_b);
+
+    let into_b = a.capacity() < len && b.capacity() >= len;
+
+    if !into_b {
+        a.reserve_exact(len_b);
+    }
+
+    unsafe {
+        let ptr_a = a.as_mut_ptr();
+        let ptr_b = b.as_mut_ptr();
+
+        // nothing can panic from here on, so the elements can be moved directly
+        a.set_len(0);
+        b.set_len(0);
+
+        let (mut output, _other) = if into_b { (b, a) } else { (a, b) };
+        let dest = output.as_mut_ptr();
+
+        // at most one of these copies anything, and the leftover elements only move
+        // towards the back, so they never overwrite any of the pairs
+        std::ptr::copy(ptr_a.add(pairs), dest.add(2 * pairs), len_a - pairs);
+        std::ptr::copy(ptr_b.add(pairs), dest.add(2 * pairs), len_b - pairs);
+
+        // filling back-to-front, pair `i` is written at or after index `i`,
+        // so it only overwrites elements that were already read
+        for i in (0..pairs).rev() {
+            let x = ptr_a.add(i).read();
+            let y = ptr_b.add(i).read();
+
+            dest.add(2 * i).write(x);
+            dest.add(2 * i + 1).write(y);
+        }
+
+        output.set_len(len);
+
+        output
+    }
+}
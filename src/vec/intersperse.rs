@@ -0,0 +1,89 @@
+use std::alloc::Layout;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+
+/// Owns the unread prefix of the input and the written suffix
+/// of the output, only dropped if one of the functions panics
+struct Intersperse<T, U> {
+    input: *mut T,
+    unread: usize,
+    output: *mut U,
+    start: usize,
+    len: usize,
+}
+
+impl<T, U> Drop for Intersperse<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            defer! {
+                cleanup::drop_slice(self.input, self.unread);
+            }
+
+            cleanup::drop_slice(self.output.add(self.start), self.len - self.start);
+        }
+    }
+}
+
+pub(crate) fn intersperse_map<T, U, S: FnMut() -> U, F: FnMut(T) -> U>(
+    vec: Vec<T>,
+    mut sep_fn: S,
+    mut f: F,
+) -> Vec<U> {
+    let mut vec = ManuallyDrop::new(vec);
+    let (in_ptr, in_len, in_cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    let len = in_len
+        .checked_mul(2)
+        .expect("capacity overflow")
+        .saturating_sub(1);
+
+    // output `2i` starts at or after the end of input `i`, so filling from
+    // the back only overwrites inputs that were already read
+    let reused_cap = raw::scale(in_cap, Layout::new::<T>(), Layout::new::<U>())
+        .ok()
+        .filter(|&cap| cap >= len && 2 * size_of::<U>() >= size_of::<T>());
+
+    let (out_ptr, out_cap) = match reused_cap {
+        Some(cap) => (in_ptr as *mut U, cap),
+        None => {
+            let mut output = ManuallyDrop::new(Vec::<U>::with_capacity(len));
+            (output.as_mut_ptr(), output.capacity())
+        }
+    };
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _in_alloc = reused_cap
+            .is_none()
+            .then(|| RawAllocGuard::new(in_ptr, in_cap));
+
+        let mut guard = Intersperse {
+            input: in_ptr,
+            unread: in_len,
+            output: out_ptr,
+            start: len,
+            len,
+        };
+
+        while let Some(i) = guard.unread.checked_sub(1) {
+            let value = in_ptr.add(i).read();
+            guard.unread = i;
+
+            if 2 * i + 1 < len {
+                out_ptr.add(2 * i + 1).write(sep_fn());
+                guard.start = 2 * i + 1;
+            }
+
+            out_ptr.add(2 * i).write(f(value));
+            guard.start = 2 * i;
+        }
+
+        std::mem::forget(guard);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
@@ -0,0 +1,153 @@
+use std::alloc::Layout;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem::ManuallyDrop;
+
+use super::gather::BitSet;
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// Owns the unread suffix of `a`, the unread elements of `b`
+/// and the written prefix of the output
+struct Join<T, U, V> {
+    a: *mut T,
+    a_len: usize,
+    read: usize,
+    b: *mut U,
+    b_len: usize,
+    consumed: BitSet,
+    out: *mut V,
+    written: usize,
+}
+
+impl<T, U, V> Drop for Join<T, U, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let (b, b_len) = (self.b, self.b_len);
+            let (a, read, a_len) = (self.a, self.read, self.a_len);
+            let consumed = &mut self.consumed;
+
+            defer! {
+                for j in 0..b_len {
+                    if !consumed.get(j) {
+                        consumed.set(j);
+                        b.add(j).drop_in_place();
+                    }
+                }
+            }
+
+            defer! {
+                cleanup::drop_slice(a.add(read), a_len - read);
+            }
+
+            cleanup::drop_slice(self.out, self.written);
+        }
+    }
+}
+
+/// Pairs the `k`-th element of `a` with the `k`-th element of `b` that has the same key,
+/// the index is built over the smaller vector
+fn matches<T, U, K: Hash + Eq>(
+    a: &[T],
+    b: &[U],
+    mut key_a: impl FnMut(&T) -> K,
+    mut key_b: impl FnMut(&U) -> K,
+) -> Vec<Option<usize>> {
+    let mut matches = vec![None; a.len()];
+
+    if b.len() <= a.len() {
+        let mut index = HashMap::<K, Vec<usize>>::new();
+
+        // the earliest element is popped first
+        for (j, y) in b.iter().enumerate().rev() {
+            index.entry(key_b(y)).or_default().push(j);
+        }
+
+        for (i, x) in a.iter().enumerate() {
+            matches[i] = index.get_mut(&key_a(x)).and_then(Vec::pop);
+        }
+    } else {
+        let mut index = HashMap::<K, Vec<usize>>::new();
+
+        for (i, x) in a.iter().enumerate().rev() {
+            index.entry(key_a(x)).or_default().push(i);
+        }
+
+        for (j, y) in b.iter().enumerate() {
+            if let Some(i) = index.get_mut(&key_b(y)).and_then(Vec::pop) {
+                matches[i] = Some(j);
+            }
+        }
+    }
+
+    matches
+}
+
+pub(crate) fn join_with<T, U, K: Hash + Eq, V>(
+    a: Vec<T>,
+    b: Vec<U>,
+    key_a: impl FnMut(&T) -> K,
+    key_b: impl FnMut(&U) -> K,
+    mut f: impl FnMut(T, U) -> V,
+) -> Vec<V> {
+    let matches = matches(&a, &b, key_a, key_b);
+    let len = matches.iter().flatten().count();
+
+    let reuse_a = Layout::new::<T>() == Layout::new::<V>();
+
+    let mut a = ManuallyDrop::new(a);
+    let mut b = ManuallyDrop::new(b);
+    let (a_ptr, a_len, a_cap) = (a.as_mut_ptr(), a.len(), a.capacity());
+    let (b_ptr, b_len, b_cap) = (b.as_mut_ptr(), b.len(), b.capacity());
+
+    let (out_ptr, out_cap) = if reuse_a {
+        (a_ptr as *mut V, a_cap)
+    } else {
+        let mut output = ManuallyDrop::new(Vec::<V>::with_capacity(len));
+        (output.as_mut_ptr(), output.capacity())
+    };
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = (!reuse_a).then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = RawAllocGuard::new(b_ptr, b_cap);
+
+        let mut join = Join {
+            a: a_ptr,
+            a_len,
+            read: 0,
+            b: b_ptr,
+            b_len,
+            consumed: BitSet::new(b_len),
+            out: out_ptr,
+            written: 0,
+        };
+
+        // the output is written in the order of `a`, and never gets
+        // ahead of it, so only elements of `a` that were read are overwritten
+        for (i, j) in matches.into_iter().enumerate() {
+            join.read = i + 1;
+            let x = a_ptr.add(i);
+
+            match j {
+                None => x.drop_in_place(),
+                Some(j) => {
+                    let x = x.read();
+                    join.consumed.set(j);
+
+                    out_ptr.add(join.written).write(f(x, b_ptr.add(j).read()));
+                    join.written += 1;
+                }
+            }
+        }
+
+        // drops the elements of `b` that weren't matched
+        join.written = 0;
+        drop(join);
+
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use super::TupleElem;
+
+/// A helper for testing custom [`TupleElem`] implementations for leaks and double drops
+///
+/// Elements created with [`LeakGuard::track`] get a unique id, and report when they are
+/// dropped, so after driving the `TupleElem` with [`LeakGuard::exercise`] every element
+/// must be accounted for. Dropping an element twice panics right away
+///
+/// ```rust
+/// use vec_utils::LeakGuard;
+///
+/// let guard = LeakGuard::new();
+///
+/// let vec = (0..10).map(|x| guard.track(x)).collect::<Vec<_>>();
+/// guard.exercise(vec, 4);
+///
+/// guard.assert_balanced();
+/// ```
+///
+/// # Panic
+///
+/// If any tracked elements are still alive when the guard is dropped
+#[derive(Default)]
+pub struct LeakGuard {
+    // id -> is the element alive
+    live: Rc<RefCell<Vec<bool>>>,
+}
+
+/// An element tracked by a [`LeakGuard`]
+#[derive(Debug)]
+pub struct Tracked<T> {
+    value: T,
+    id: usize,
+    // this is only released by the first drop, so that a
+    // double drop doesn't release the guard's state twice
+    live: ManuallyDrop<Rc<RefCell<Vec<bool>>>>,
+}
+
+impl LeakGuard {
+    /// Create a new guard with no tracked elements
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a new element
+    pub fn track<T>(&self, value: T) -> Tracked<T> {
+        let mut live = self.live.borrow_mut();
+        let id = live.len();
+        live.push(true);
+
+        Tracked {
+            value,
+            id,
+            live: ManuallyDrop::new(self.live.clone()),
+        }
+    }
+
+    /// The number of tracked elements that are still alive
+    pub fn live(&self) -> usize {
+        self.live.borrow().iter().filter(|&&alive| alive).count()
+    }
+
+    /// The ids of the tracked elements that are still alive
+    pub fn leaked(&self) -> Vec<usize> {
+        let live = self.live.borrow();
+        (0..live.len()).filter(|&id| live[id]).collect()
+    }
+
+    /// Assert that every tracked element was dropped
+    pub fn assert_balanced(&self) {
+        let leaked = self.leaked();
+
+        assert!(
+            leaked.is_empty(),
+            "tracked elements {:?} were leaked",
+            leaked
+        );
+    }
+
+    /// Drive `elem` through the `TupleElem` protocol, reading `reads` elements
+    /// with `next_unchecked` and then calling `drop_rest`
+    ///
+    /// # Panic
+    ///
+    /// If `reads` is larger than the length of `elem`, or if
+    /// `elem` claims to have a length larger than it's capacity
+    pub fn exercise<E: TupleElem>(&self, elem: E, reads: usize) {
+        let len = elem.len();
+
+        assert!(
+            reads <= len,
+            "tried to read {} elements, but there are only {}",
+            reads,
+            len
+        );
+
+        let mut data = elem.into_data();
+
+        assert!(
+            len <= E::capacity(&data),
+            "the length ({}) is larger than the capacity ({})",
+            len,
+            E::capacity(&data)
+        );
+
+        unsafe {
+            for _ in 0..reads {
+                drop(E::next_unchecked(&mut data));
+            }
+
+            E::drop_rest(&mut data, reads);
+        }
+    }
+}
+
+impl Drop for LeakGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            self.assert_balanced();
+        }
+    }
+}
+
+impl<T> Tracked<T> {
+    /// The id of the element, which is unique for the guard that created it
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Stop tracking the element, and get the value back
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+
+        unsafe {
+            this.release();
+            std::ptr::read(&this.value)
+        }
+    }
+
+    /// Mark the element as dropped, and release the guard's state
+    ///
+    /// # Safety
+    ///
+    /// This must only be called once, unless it panics
+    unsafe fn release(&mut self) {
+        let was_alive = std::mem::replace(&mut self.live.borrow_mut()[self.id], false);
+
+        assert!(was_alive, "tracked element {} was dropped twice", self.id);
+
+        ManuallyDrop::drop(&mut self.live);
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        unsafe { self.release() }
+    }
+}
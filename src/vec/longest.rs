@@ -0,0 +1,135 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// A value from either or both of two zipped vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EitherOrBoth<T, U> {
+    /// Both vectors had an element at this position
+    Both(T, U),
+    /// Only the first vector had an element at this position
+    Left(T),
+    /// Only the second vector had an element at this position
+    Right(U),
+}
+
+impl<T, U> EitherOrBoth<T, U> {
+    /// The element from the first vector, if there is one
+    pub fn left(self) -> Option<T> {
+        match self {
+            EitherOrBoth::Both(x, _) | EitherOrBoth::Left(x) => Some(x),
+            EitherOrBoth::Right(_) => None,
+        }
+    }
+
+    /// The element from the second vector, if there is one
+    pub fn right(self) -> Option<U> {
+        match self {
+            EitherOrBoth::Both(_, y) | EitherOrBoth::Right(y) => Some(y),
+            EitherOrBoth::Left(_) => None,
+        }
+    }
+}
+
+/// Owns the unread inputs and the written outputs, only dropped if the function panics
+struct Longest<T, U, V> {
+    a: *mut T,
+    a_len: usize,
+    b: *mut U,
+    b_len: usize,
+    out: *mut V,
+    read: usize,
+    written: usize,
+}
+
+impl<T, U, V> Drop for Longest<T, U, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let a_read = self.read.min(self.a_len);
+            let b_read = self.read.min(self.b_len);
+
+            defer! {
+                cleanup::drop_slice(self.b.add(b_read), self.b_len - b_read);
+            }
+
+            defer! {
+                cleanup::drop_slice(self.a.add(a_read), self.a_len - a_read);
+            }
+
+            cleanup::drop_slice(self.out, self.written);
+        }
+    }
+}
+
+fn into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+pub(crate) fn zip_longest_with<T, U, V, F: FnMut(EitherOrBoth<T, U>) -> V>(
+    a: Vec<T>,
+    b: Vec<U>,
+    mut f: F,
+) -> Vec<V> {
+    let len = a.len().max(b.len());
+
+    let fits_a = Layout::new::<T>() == Layout::new::<V>() && a.capacity() >= len;
+    let fits_b = Layout::new::<U>() == Layout::new::<V>() && b.capacity() >= len;
+
+    // prefer the longer vector, the shorter one only fits if it has spare capacity
+    let (reuse_a, reuse_b) = match (fits_a, fits_b) {
+        (true, true) if b.len() > a.len() => (false, true),
+        (true, _) => (true, false),
+        (false, fits_b) => (false, fits_b),
+    };
+
+    let (out_ptr, out_cap) = match (reuse_a, reuse_b) {
+        (true, _) => (a.as_ptr() as *mut V, a.capacity()),
+        (_, true) => (b.as_ptr() as *mut V, b.capacity()),
+        _ => {
+            let (ptr, _, cap) = into_raw_parts(Vec::<V>::with_capacity(len));
+            (ptr, cap)
+        }
+    };
+
+    let (a_ptr, a_len, a_cap) = into_raw_parts(a);
+    let (b_ptr, b_len, b_cap) = into_raw_parts(b);
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = (!reuse_a).then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = (!reuse_b).then(|| RawAllocGuard::new(b_ptr, b_cap));
+
+        let mut guard = Longest {
+            a: a_ptr,
+            a_len,
+            b: b_ptr,
+            b_len,
+            out: out_ptr,
+            read: 0,
+            written: 0,
+        };
+
+        for i in 0..len {
+            let value = match (i < a_len, i < b_len) {
+                (true, true) => EitherOrBoth::Both(a_ptr.add(i).read(), b_ptr.add(i).read()),
+                (true, false) => EitherOrBoth::Left(a_ptr.add(i).read()),
+                (false, _) => EitherOrBoth::Right(b_ptr.add(i).read()),
+            };
+            guard.read = i + 1;
+
+            // element `i` of the output only overwrites element `i`
+            // of the reused input, which was just read
+            out_ptr.add(i).write(f(value));
+            guard.written = i + 1;
+        }
+
+        std::mem::forget(guard);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
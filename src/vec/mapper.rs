@@ -0,0 +1,98 @@
+use std::marker::PhantomData;
+
+use crate::{RecycleBin, VecExt};
+
+/// Keeps the allocations of a map between runs, so that repeatedly mapping
+/// vectors doesn't allocate once the buffers are large enough, even if the
+/// layouts of `T` and `U` don't match
+///
+/// The output of [`run`](ReusableMapper::run) can be handed back with
+/// [`recycle`](ReusableMapper::recycle), and the allocation of the last input
+/// can be taken with [`take_input`](ReusableMapper::take_input) to build the next input
+///
+/// ```rust
+/// use vec_utils::ReusableMapper;
+///
+/// let mut mapper = ReusableMapper::new();
+///
+/// for frame in 0..3 {
+///     let mut input = mapper.take_input();
+///     input.extend(frame..frame + 4);
+///
+///     let output = mapper.run(input, |x: u8| x as f64);
+///     assert_eq!(output.len(), 4);
+///
+///     mapper.recycle(output);
+/// }
+/// ```
+pub struct ReusableMapper<T, U> {
+    input: Vec<T>,
+    output: Vec<U>,
+    map: PhantomData<fn(T) -> U>,
+}
+
+impl<T, U> Default for ReusableMapper<T, U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, U> ReusableMapper<T, U> {
+    /// Create a mapper without any allocations
+    pub fn new() -> Self {
+        Self {
+            input: Vec::new(),
+            output: Vec::new(),
+            map: PhantomData,
+        }
+    }
+
+    /// Map the input into the cached output allocation, the cached
+    /// allocation is taken from the thread-local [`RecycleBin`] if it's too small
+    ///
+    /// The allocation of the input is kept for [`take_input`](ReusableMapper::take_input)
+    pub fn run<F: FnMut(T) -> U>(&mut self, mut input: Vec<T>, f: F) -> Vec<U> {
+        let mut output = std::mem::take(&mut self.output);
+
+        if output.capacity() < input.len() {
+            if let Some(larger) = RecycleBin::take_local(input.len()) {
+                std::mem::replace(&mut output, larger).retire();
+            }
+        }
+
+        output.clear();
+        output.reserve(input.len());
+        output.extend(input.drain(..).map(f));
+
+        self.keep_input(input);
+
+        output
+    }
+
+    /// Hand an output back to the mapper, so that its allocation can be used by the next run
+    ///
+    /// The elements of `output` are dropped, and if the mapper already has a larger
+    /// allocation then the allocation of `output` is put into the thread-local [`RecycleBin`]
+    pub fn recycle(&mut self, mut output: Vec<U>) {
+        output.clear();
+
+        if output.capacity() > self.output.capacity() {
+            std::mem::swap(&mut self.output, &mut output);
+        }
+
+        output.retire();
+    }
+
+    /// Take the allocation of the last input, the returned vector is empty
+    pub fn take_input(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.input)
+    }
+
+    fn keep_input(&mut self, mut input: Vec<T>) {
+        if input.capacity() > self.input.capacity() {
+            std::mem::swap(&mut self.input, &mut input);
+        }
+
+        input.retire();
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// Owns the unread prefixes of the inputs and the written suffix
+/// of the output, only dropped if the comparison panics
+struct Merge<T> {
+    a: *mut T,
+    a_len: usize,
+    b: *mut T,
+    b_len: usize,
+    out: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for Merge<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let start = self.a_len + self.b_len;
+
+            defer! {
+                cleanup::drop_slice(self.b, self.b_len);
+            }
+
+            defer! {
+                cleanup::drop_slice(self.a, self.a_len);
+            }
+
+            cleanup::drop_slice(self.out.add(start), self.len - start);
+        }
+    }
+}
+
+fn into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+pub(crate) fn merge_with<T, F: FnMut(&T, &T) -> Ordering>(
+    a: Vec<T>,
+    b: Vec<T>,
+    mut cmp: F,
+) -> Vec<T> {
+    let len = a.len().checked_add(b.len()).expect("capacity overflow");
+
+    let fits_a = a.capacity() >= len;
+    let fits_b = b.capacity() >= len;
+
+    // prefer the longer vector, if both have enough capacity
+    let (reuse_a, reuse_b) = match (fits_a, fits_b) {
+        (true, true) if b.len() > a.len() => (false, true),
+        (true, _) => (true, false),
+        (false, fits_b) => (false, fits_b),
+    };
+
+    let (out_ptr, out_cap) = match (reuse_a, reuse_b) {
+        (true, _) => (a.as_ptr() as *mut T, a.capacity()),
+        (_, true) => (b.as_ptr() as *mut T, b.capacity()),
+        _ => {
+            let (ptr, _, cap) = into_raw_parts(Vec::<T>::with_capacity(len));
+            (ptr, cap)
+        }
+    };
+
+    let (a_ptr, a_len, a_cap) = into_raw_parts(a);
+    let (b_ptr, b_len, b_cap) = into_raw_parts(b);
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = (!reuse_a).then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = (!reuse_b).then(|| RawAllocGuard::new(b_ptr, b_cap));
+
+        let mut merge = Merge {
+            a: a_ptr,
+            a_len,
+            b: b_ptr,
+            b_len,
+            out: out_ptr,
+            len,
+        };
+
+        // merge from the back to the front, the next write is always past
+        // the unread elements of the reused input, so they are never clobbered
+        while merge.a_len != 0 && merge.b_len != 0 {
+            let x = a_ptr.add(merge.a_len - 1);
+            let y = b_ptr.add(merge.b_len - 1);
+
+            // on ties the element from `b` goes last, so the merge is stable
+            let src = if cmp(&*x, &*y) == Ordering::Greater {
+                merge.a_len -= 1;
+                x
+            } else {
+                merge.b_len -= 1;
+                y
+            };
+
+            out_ptr.add(merge.a_len + merge.b_len).write(src.read());
+        }
+
+        // the rest of one input is already in place if it was reused
+        for (ptr, rest, reused) in [(a_ptr, merge.a_len, reuse_a), (b_ptr, merge.b_len, reuse_b)] {
+            if !reused {
+                out_ptr.copy_from_nonoverlapping(ptr, rest);
+            }
+        }
+
+        std::mem::forget(merge);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
+
+pub(crate) fn merge_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: Vec<T>, b: Vec<T>, mut f: F) -> Vec<T> {
+    merge_with(a, b, move |x, y| f(x).cmp(&f(y)))
+}
@@ -0,0 +1,23 @@
+use crate::VecExt;
+
+/// Extension methods for `Vec<Option<T>>`
+pub trait VecOptionExt: Sized {
+    /// The type that the options store
+    type T;
+
+    /// Map the values in a vector of options, the `None`s are kept as is
+    ///
+    /// Will try and reuse the allocation if the allocation layouts match, i.e. if
+    /// `std::alloc::Layout::<Option<T>>::new() == std::alloc::Layout::<Option<U>>::new()`
+    /// then the allocation will be reused (this is often true for types with a niche,
+    /// like references, boxes, and `NonZero*` integers)
+    fn map_option<U, F: FnMut(Self::T) -> U>(self, f: F) -> Vec<Option<U>>;
+}
+
+impl<T> VecOptionExt for Vec<Option<T>> {
+    type T = T;
+
+    fn map_option<U, F: FnMut(T) -> U>(self, mut f: F) -> Vec<Option<U>> {
+        self.map(move |option| option.map(&mut f))
+    }
+}
@@ -0,0 +1,86 @@
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// What [`VecExt::reduce_pairs`](crate::VecExt::reduce_pairs) does with the
+/// last element of an odd length vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OddPolicy {
+    /// Move the last element to the output as is
+    Keep,
+    /// Drop the last element
+    Drop,
+}
+
+/// Owns the written prefix and the unread suffix of the vector
+struct Pairs<T> {
+    ptr: *mut T,
+    written: usize,
+    read: usize,
+    len: usize,
+}
+
+impl<T> Drop for Pairs<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let (ptr, read, len) = (self.ptr, self.read, self.len);
+
+            defer! {
+                cleanup::drop_slice(ptr.add(read), len - read);
+            }
+
+            cleanup::drop_slice(self.ptr, self.written);
+        }
+    }
+}
+
+pub(crate) fn reduce_pairs<T, F: FnMut(T, T) -> T>(
+    vec: Vec<T>,
+    odd: OddPolicy,
+    mut f: F,
+) -> Vec<T> {
+    let mut vec = ManuallyDrop::new(vec);
+    let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    unsafe {
+        let alloc = RawAllocGuard::new(ptr, cap);
+
+        let mut pairs = Pairs {
+            ptr,
+            written: 0,
+            read: 0,
+            len,
+        };
+
+        // the output is always behind the input, so only read elements are overwritten
+        while pairs.read + 1 < len {
+            let a = ptr.add(pairs.read).read();
+            let b = ptr.add(pairs.read + 1).read();
+            pairs.read += 2;
+
+            ptr.add(pairs.written).write(f(a, b));
+            pairs.written += 1;
+        }
+
+        if pairs.read < len {
+            match odd {
+                OddPolicy::Keep => {
+                    ptr.add(pairs.written).copy_from(ptr.add(pairs.read), 1);
+                    pairs.written += 1;
+                }
+                OddPolicy::Drop => {
+                    let last = ptr.add(pairs.read).read();
+                    pairs.read += 1;
+                    drop(last);
+                }
+            }
+        }
+
+        let written = pairs.written;
+        std::mem::forget(pairs);
+        std::mem::forget(alloc);
+
+        Vec::from_raw_parts(ptr, written, cap)
+    }
+}
@@ -0,0 +1,88 @@
+use std::alloc::Layout;
+use std::fmt;
+use std::mem::ManuallyDrop;
+
+use crate::raw::{self, ScaleError};
+
+/// Extension methods for vectors of arrays
+pub trait VecArrayExt: Sized {
+    /// The type of the elements of the arrays
+    type T;
+
+    /// Regroup the elements into arrays of `M` elements, this only changes the
+    /// length and capacity of the vector, the elements are not moved
+    ///
+    /// This succeeds if both the elements and the allocation can be evenly divided
+    /// into arrays of `M` elements, see [`scale_capacity`](crate::scale_capacity).
+    /// Otherwise the original vector is returned in the error. Zero-sized arrays
+    /// are never repartitioned
+    ///
+    /// ```rust
+    /// use vec_utils::VecArrayExt;
+    ///
+    /// let frames = vec![[1, 2], [3, 4], [5, 6]];
+    /// let frames = frames.repartition::<3>().unwrap();
+    /// assert_eq!(frames, [[1, 2, 3], [4, 5, 6]]);
+    ///
+    /// let frames = frames.repartition::<4>().unwrap_err().into_vec();
+    /// assert_eq!(frames, [[1, 2, 3], [4, 5, 6]]);
+    /// ```
+    fn repartition<const M: usize>(self) -> Result<Vec<[Self::T; M]>, RepartitionError<Self>>;
+}
+
+impl<T, const N: usize> VecArrayExt for Vec<[T; N]> {
+    type T = T;
+
+    fn repartition<const M: usize>(self) -> Result<Vec<[T; M]>, RepartitionError<Self>> {
+        let from = Layout::new::<[T; N]>();
+        let to = Layout::new::<[T; M]>();
+
+        let scaled = raw::scale(self.len(), from, to)
+            .and_then(|len| Ok((len, raw::scale(self.capacity(), from, to)?)));
+
+        let (len, cap) = match scaled {
+            Ok(scaled) => scaled,
+            Err(error) => return Err(RepartitionError { vec: self, error }),
+        };
+
+        let mut vec = ManuallyDrop::new(self);
+
+        // the arrays have the same alignment, and the same elements fill the same bytes
+        unsafe { Ok(Vec::from_raw_parts(vec.as_mut_ptr().cast(), len, cap)) }
+    }
+}
+
+/// The error returned by [`VecArrayExt::repartition`], which holds the original vector
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RepartitionError<V> {
+    vec: V,
+    error: ScaleError,
+}
+
+impl<V> RepartitionError<V> {
+    /// Why the vector couldn't be repartitioned
+    pub fn error(&self) -> ScaleError {
+        self.error
+    }
+
+    /// Get back the original vector
+    pub fn into_vec(self) -> V {
+        self.vec
+    }
+}
+
+impl<V> fmt::Debug for RepartitionError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RepartitionError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V> fmt::Display for RepartitionError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't repartition the vector: {}", self.error)
+    }
+}
+
+impl<V> std::error::Error for RepartitionError<V> {}
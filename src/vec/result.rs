@@ -0,0 +1,26 @@
+use crate::VecExt;
+
+/// Extension methods for `Vec<Result<T, E>>`
+pub trait VecResultExt: Sized {
+    /// The type of the successful values
+    type T;
+
+    /// The type of the errors
+    type E;
+
+    /// Map the errors of a vector of results, the successful values are moved as is
+    ///
+    /// Will try and reuse the allocation if the allocation layouts match, i.e. if
+    /// `std::alloc::Layout::<Result<T, E>>::new() == std::alloc::Layout::<Result<T, E2>>::new()`
+    /// then the allocation will be reused
+    fn map_err_in_place<E2, F: FnMut(Self::E) -> E2>(self, f: F) -> Vec<Result<Self::T, E2>>;
+}
+
+impl<T, E> VecResultExt for Vec<Result<T, E>> {
+    type T = T;
+    type E = E;
+
+    fn map_err_in_place<E2, F: FnMut(E) -> E2>(self, mut f: F) -> Vec<Result<T, E2>> {
+        self.map(move |result| result.map_err(&mut f))
+    }
+}
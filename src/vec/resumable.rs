@@ -0,0 +1,226 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+use std::ops::ControlFlow;
+
+use super::{Input, Output};
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+use crate::RecycleBin;
+
+/// A map over a vector that can be run a few elements at a time,
+/// and finished later
+///
+/// Like `VecExt::map`, the allocation is reused if the layouts of `T` and `U` match
+///
+/// ```rust
+/// use std::ops::ControlFlow;
+/// use vec_utils::ResumableMap;
+///
+/// let mut map = ResumableMap::new(vec![1.0f32, 2.0, 3.0], f32::to_bits);
+///
+/// assert!(map.run_for(2).is_continue());
+/// assert_eq!(map.remaining(), 1);
+///
+/// match map.run_for(2) {
+///     ControlFlow::Break(vec) => assert_eq!(vec, [1.0f32, 2.0, 3.0].map(f32::to_bits)),
+///     ControlFlow::Continue(()) => unreachable!(),
+/// }
+/// ```
+pub struct ResumableMap<T, U, F> {
+    state: MapState<T, U>,
+    f: F,
+}
+
+/// The reuse state of a map that is run one element at a time
+pub(super) enum MapState<T, U> {
+    InPlace(InPlace<T, U>),
+    Fresh(std::vec::IntoIter<T>, Vec<U>),
+    Finished,
+    // the mapping function panicked, and the rest of the input was dropped
+    Poisoned,
+}
+
+/// Poisons the state if it's dropped, because the element
+/// that was being mapped was already read from the input
+struct Poison<'a, T, U>(&'a mut MapState<T, U>);
+
+impl<T, U> Drop for Poison<'_, T, U> {
+    fn drop(&mut self) {
+        *self.0 = MapState::Poisoned;
+    }
+}
+
+pub(super) struct InPlace<T, U> {
+    input: Input<T>,
+    output: Output<U>,
+    len: usize,
+    read: usize,
+    written: usize,
+}
+
+impl<T, U> Drop for InPlace<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            // the output was taken, so the input won't free the allocation
+            let _alloc = RawAllocGuard::new(self.input.start, self.input.cap);
+            let (output, written) = (self.output.start, self.written);
+
+            defer! {
+                cleanup::drop_slice(output, written);
+            }
+
+            self.input.drop_rest(self.read);
+        }
+    }
+}
+
+impl<T, U> MapState<T, U> {
+    pub(super) fn new(vec: Vec<T>) -> Self {
+        if Layout::new::<T>() == Layout::new::<U>() {
+            let len = vec.len();
+            let mut input = Input::from(vec);
+            let output = unsafe { input.take_output() };
+
+            MapState::InPlace(InPlace {
+                input,
+                output,
+                len,
+                read: 0,
+                written: 0,
+            })
+        } else {
+            let output =
+                RecycleBin::take_local(vec.len()).unwrap_or_else(|| Vec::with_capacity(vec.len()));
+
+            MapState::Fresh(vec.into_iter(), output)
+        }
+    }
+
+    /// The number of elements that haven't been read yet
+    pub(super) fn remaining(&self) -> usize {
+        match self {
+            MapState::InPlace(state) => state.len - state.read,
+            MapState::Fresh(input, _) => input.len(),
+            MapState::Finished | MapState::Poisoned => 0,
+        }
+    }
+
+    /// If the mapping function panicked
+    pub(super) fn is_poisoned(&self) -> bool {
+        matches!(self, MapState::Poisoned)
+    }
+
+    /// Run `f`, and poison the state if it panics
+    pub(super) fn poison_on_panic<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let poison = Poison(self);
+        let value = f();
+        std::mem::forget(poison);
+        value
+    }
+
+    /// Read the next element of the input
+    pub(super) fn next(&mut self) -> Option<T> {
+        match self {
+            MapState::InPlace(state) if state.read < state.len => unsafe {
+                let value = state.input.next_unchecked();
+                state.read += 1;
+                Some(value)
+            },
+            MapState::InPlace(_) | MapState::Finished | MapState::Poisoned => None,
+            MapState::Fresh(input, _) => input.next(),
+        }
+    }
+
+    /// Write the next element of the output
+    ///
+    /// # Safety
+    ///
+    /// This must be called at most once after each call to `next` that returned `Some`
+    pub(super) unsafe fn push(&mut self, value: U) {
+        match self {
+            MapState::InPlace(state) => {
+                state.output.write(value);
+                state.written += 1;
+            }
+            MapState::Fresh(_, output) => output.push(value),
+            MapState::Finished | MapState::Poisoned => unreachable!(),
+        }
+    }
+
+    /// Take the output, once all of the elements are mapped,
+    /// afterwards this returns an empty vector
+    ///
+    /// # Safety
+    ///
+    /// `push` must be called once for each element of the input
+    pub(super) unsafe fn finish(&mut self) -> Vec<U> {
+        match std::mem::replace(self, MapState::Finished) {
+            MapState::InPlace(state) => {
+                let state = ManuallyDrop::new(state);
+                debug_assert_eq!(state.written, state.len);
+
+                Vec::from_raw_parts(state.output.start, state.len, state.input.cap)
+            }
+            MapState::Fresh(_, output) => output,
+            MapState::Finished | MapState::Poisoned => Vec::new(),
+        }
+    }
+}
+
+impl<T, U, F: FnMut(T) -> U> ResumableMap<T, U, F> {
+    /// Prepare to map the vector, no elements are mapped until [`ResumableMap::run_for`] is called
+    pub fn new(vec: Vec<T>, f: F) -> Self {
+        Self {
+            state: MapState::new(vec),
+            f,
+        }
+    }
+
+    /// The number of elements that haven't been mapped yet
+    pub fn remaining(&self) -> usize {
+        self.state.remaining()
+    }
+
+    /// Map at most `n` more elements
+    ///
+    /// Once all of the elements are mapped, the output is returned
+    /// in `ControlFlow::Break`, afterwards this always returns an empty vector
+    ///
+    /// If the mapping function panics, the outputs and the rest of the input are dropped
+    ///
+    /// # Panic
+    ///
+    /// If the mapping function panicked during an earlier call
+    pub fn run_for(&mut self, n: usize) -> ControlFlow<Vec<U>> {
+        assert!(
+            !self.state.is_poisoned(),
+            "`ResumableMap` was resumed after the mapping function panicked"
+        );
+
+        for _ in 0..n {
+            let value = match self.state.next() {
+                Some(value) => value,
+                None => break,
+            };
+
+            let f = &mut self.f;
+            let value = self.state.poison_on_panic(move || f(value));
+
+            unsafe { self.state.push(value) }
+        }
+
+        if self.remaining() == 0 {
+            ControlFlow::Break(unsafe { self.state.finish() })
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    /// Map all of the remaining elements, and return the output
+    pub fn finish(mut self) -> Vec<U> {
+        match self.run_for(usize::MAX) {
+            ControlFlow::Break(vec) => vec,
+            ControlFlow::Continue(()) => unreachable!(),
+        }
+    }
+}
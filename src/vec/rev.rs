@@ -0,0 +1,105 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// The unread prefix of an input vector
+struct RevInput<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for RevInput<T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.ptr, self.len) }
+    }
+}
+
+/// The written suffix of the output vector
+struct RevOutput<V> {
+    ptr: *mut V,
+    start: usize,
+    len: usize,
+}
+
+impl<V> Drop for RevOutput<V> {
+    fn drop(&mut self) {
+        // the most recently written element is at the front
+        unsafe { cleanup::drop_slice(self.ptr.add(self.start), self.len - self.start) }
+    }
+}
+
+fn into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+pub(crate) fn zip_with_rev<T, U, V, F: FnMut(T, U) -> V>(a: Vec<T>, b: Vec<U>, mut f: F) -> Vec<V> {
+    let len = a.len().min(b.len());
+
+    let (reuse_a, reuse_b) = if Layout::new::<T>() == Layout::new::<V>() {
+        (true, false)
+    } else {
+        (false, Layout::new::<U>() == Layout::new::<V>())
+    };
+
+    let (out_ptr, out_cap) = match (reuse_a, reuse_b) {
+        (true, _) => (a.as_ptr() as *mut V, a.capacity()),
+        (_, true) => (b.as_ptr() as *mut V, b.capacity()),
+        _ => {
+            let (ptr, _, cap) = into_raw_parts(Vec::<V>::with_capacity(len));
+            (ptr, cap)
+        }
+    };
+
+    let (a_ptr, a_len, a_cap) = into_raw_parts(a);
+    let (b_ptr, b_len, b_cap) = into_raw_parts(b);
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = (!reuse_a).then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = (!reuse_b).then(|| RawAllocGuard::new(b_ptr, b_cap));
+
+        let mut a = RevInput { ptr: a_ptr, len };
+        let mut b = RevInput { ptr: b_ptr, len };
+
+        // the excess elements are at the back, so they go first
+        {
+            let _b_tail = RevInput {
+                ptr: b_ptr.add(len),
+                len: b_len - len,
+            };
+            cleanup::drop_slice(a_ptr.add(len), a_len - len);
+        }
+
+        let mut output = RevOutput {
+            ptr: out_ptr,
+            start: len,
+            len,
+        };
+
+        while let Some(i) = output.start.checked_sub(1) {
+            a.len = i;
+            b.len = i;
+
+            let value = f(a_ptr.add(i).read(), b_ptr.add(i).read());
+
+            out_ptr.add(i).write(value);
+            output.start = i;
+        }
+
+        std::mem::forget(output);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
+
+pub(crate) fn map_rev<T, U, F: FnMut(T) -> U>(vec: Vec<T>, mut f: F) -> Vec<U> {
+    let len = vec.len();
+
+    // a `Vec<()>` never allocates, so this is free
+    zip_with_rev(vec, vec![(); len], move |x, ()| f(x))
+}
@@ -0,0 +1,222 @@
+use std::alloc::Layout;
+use std::mem::{size_of, ManuallyDrop};
+
+use crate::cleanup;
+use crate::raw::{self, RawAllocGuard};
+
+/// Extension methods for run-length encoded vectors
+pub trait VecRunsExt: Sized {
+    /// The type of the values in the runs
+    type T;
+
+    /// Expand each `(value, count)` run into `count` copies of `value`
+    ///
+    /// The allocation is reused if the alignments match, it has enough bytes for
+    /// the output, and the output of each run doesn't reach the runs before it.
+    /// Otherwise the output is allocated once. The output is filled from the back
+    /// to the front, so the values are cloned in reverse order
+    ///
+    /// # Panic
+    ///
+    /// If the total length overflows
+    fn rle_decode(self) -> Vec<Self::T>
+    where
+        Self::T: Clone;
+}
+
+impl<T> VecRunsExt for Vec<(T, usize)> {
+    type T = T;
+
+    fn rle_decode(self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        rle_decode(self)
+    }
+}
+
+/// Owns the unread elements of the input and the written outputs
+struct Runs<T, U> {
+    input: *mut T,
+    read: usize,
+    len: usize,
+    output: *mut U,
+    written: usize,
+}
+
+impl<T, U> Drop for Runs<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let (input, read, len) = (self.input, self.read, self.len);
+
+            defer! {
+                cleanup::drop_slice(input.add(read), len - read);
+            }
+
+            cleanup::drop_slice(self.output, self.written);
+        }
+    }
+}
+
+pub(crate) fn rle_encode<T: PartialEq>(vec: Vec<T>) -> Vec<(T, usize)> {
+    let mut vec = ManuallyDrop::new(vec);
+    let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    let out_cap = raw::scale(cap, Layout::new::<T>(), Layout::new::<(T, usize)>()).ok();
+
+    unsafe {
+        let alloc = RawAllocGuard::new(ptr, cap);
+
+        let mut runs = Runs {
+            input: ptr,
+            read: 0,
+            len,
+            output: ptr as *mut (T, usize),
+            written: 0,
+        };
+
+        // only set once a run doesn't fit in place
+        let mut spilled = None::<Vec<(T, usize)>>;
+
+        while runs.read < len {
+            let start = runs.read;
+            let value = ptr.add(start).read();
+            runs.read += 1;
+
+            while runs.read < len && *ptr.add(runs.read) == value {
+                runs.read += 1;
+                ptr.add(runs.read - 1).drop_in_place();
+            }
+
+            let run = (value, runs.read - start);
+
+            // the run must end before the unread elements start
+            let fits = out_cap.is_some()
+                && (runs.written + 1) * size_of::<(T, usize)>() <= runs.read * size_of::<T>();
+
+            match spilled {
+                None if fits => {
+                    runs.output.add(runs.written).write(run);
+                    runs.written += 1;
+                }
+                Some(ref mut output) => output.push(run),
+                None => {
+                    let mut output =
+                        Vec::<(T, usize)>::with_capacity(runs.written + 1 + (len - runs.read));
+                    output
+                        .as_mut_ptr()
+                        .copy_from_nonoverlapping(runs.output, runs.written);
+                    output.set_len(runs.written);
+                    runs.written = 0;
+
+                    output.push(run);
+                    spilled = Some(output);
+                }
+            }
+        }
+
+        let written = runs.written;
+        std::mem::forget(runs);
+
+        match (spilled, out_cap) {
+            // frees the input allocation
+            (Some(output), _) => output,
+            (None, Some(out_cap)) => {
+                std::mem::forget(alloc);
+                Vec::from_raw_parts(ptr as *mut (T, usize), written, out_cap)
+            }
+            // a run that can't be written in place is always spilled,
+            // so the input was empty
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+/// Owns the unread prefix of the input and the written suffix of the output
+struct Expand<T, U> {
+    input: *mut T,
+    unread: usize,
+    output: *mut U,
+    start: usize,
+    len: usize,
+}
+
+impl<T, U> Drop for Expand<T, U> {
+    fn drop(&mut self) {
+        unsafe {
+            let (input, unread) = (self.input, self.unread);
+
+            defer! {
+                cleanup::drop_slice(input, unread);
+            }
+
+            cleanup::drop_slice(self.output.add(self.start), self.len - self.start);
+        }
+    }
+}
+
+pub(crate) fn rle_decode<T: Clone>(vec: Vec<(T, usize)>) -> Vec<T> {
+    let mut len = 0usize;
+    let mut in_place = true;
+
+    // the output of run `k` must start after the end of the runs before it
+    for (k, &(_, count)) in vec.iter().enumerate() {
+        in_place &= k * size_of::<(T, usize)>() <= len * size_of::<T>();
+        len = len.checked_add(count).expect("capacity overflow");
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+    let (in_ptr, in_len, in_cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    let reused_cap = raw::scale(in_cap, Layout::new::<(T, usize)>(), Layout::new::<T>())
+        .ok()
+        .filter(|&cap| in_place && cap >= len);
+
+    let (out_ptr, out_cap) = match reused_cap {
+        Some(cap) => (in_ptr as *mut T, cap),
+        None => {
+            let mut output = ManuallyDrop::new(Vec::<T>::with_capacity(len));
+            (output.as_mut_ptr(), output.capacity())
+        }
+    };
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _in_alloc = reused_cap
+            .is_none()
+            .then(|| RawAllocGuard::new(in_ptr, in_cap));
+
+        let mut expand = Expand {
+            input: in_ptr,
+            unread: in_len,
+            output: out_ptr,
+            start: len,
+            len,
+        };
+
+        while let Some(k) = expand.unread.checked_sub(1) {
+            let (value, count) = in_ptr.add(k).read();
+            expand.unread = k;
+
+            let end = expand.start;
+            let start = end - count;
+
+            for i in (start + 1..end).rev() {
+                out_ptr.add(i).write(value.clone());
+                expand.start = i;
+            }
+
+            // drops the value if the count is zero
+            if count != 0 {
+                out_ptr.add(start).write(value);
+                expand.start = start;
+            }
+        }
+
+        std::mem::forget(expand);
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
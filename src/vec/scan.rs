@@ -0,0 +1,31 @@
+use std::ops::Add;
+
+use crate::VecExt;
+
+pub(crate) fn scan_in_place<T, U, Acc, F: FnMut(&Acc, T) -> (Acc, U)>(
+    vec: Vec<T>,
+    init: Acc,
+    mut f: F,
+) -> Vec<U> {
+    let mut acc = init;
+
+    vec.map(move |x| {
+        let (next, output) = f(&acc, x);
+        acc = next;
+        output
+    })
+}
+
+pub(crate) fn prefix_sum<T: Copy + Add<Output = T>>(vec: Vec<T>) -> Vec<T> {
+    let mut acc = None::<T>;
+
+    vec.map(move |x| {
+        let sum = acc.map_or(x, |acc| acc + x);
+        acc = Some(sum);
+        sum
+    })
+}
+
+pub(crate) fn exclusive_prefix_sum<T: Copy + Default + Add<Output = T>>(vec: Vec<T>) -> Vec<T> {
+    scan_in_place(vec, T::default(), |&acc, x| (acc + x, acc))
+}
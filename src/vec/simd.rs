@@ -0,0 +1,79 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+use std::simd::{Simd, SimdElement};
+
+use crate::RawParts;
+
+/// Extension methods for mapping vectors of primitives with explicit SIMD kernels
+pub trait SimdVecExt: Sized {
+    /// The type that the `Vec<T>` stores
+    type T: SimdElement;
+
+    /// Map a vector `LANES` elements at a time with `simd`, and the remaining elements
+    /// that don't fill a whole vector with `scalar`, will try and reuse the allocation
+    /// if the allocation layouts of `T` and `U` match
+    ///
+    /// The lanes are loaded from, and stored to, the reused buffer directly
+    ///
+    /// ```rust
+    /// #![feature(portable_simd)]
+    /// use std::simd::Simd;
+    /// use vec_utils::SimdVecExt;
+    ///
+    /// let vec = vec![1.0f32, 2.0, 3.0, 4.0, 5.0];
+    /// let vec = vec.map_simd::<f32, 4>(|x| x * Simd::splat(2.0), |x| x * 2.0);
+    ///
+    /// assert_eq!(vec, [2.0, 4.0, 6.0, 8.0, 10.0]);
+    /// ```
+    fn map_simd<U: SimdElement, const LANES: usize>(
+        self,
+        simd: impl FnMut(Simd<Self::T, LANES>) -> Simd<U, LANES>,
+        scalar: impl FnMut(Self::T) -> U,
+    ) -> Vec<U>;
+}
+
+impl<T: SimdElement, C: RawParts<Item = T>> SimdVecExt for C {
+    type T = T;
+
+    fn map_simd<U: SimdElement, const LANES: usize>(
+        self,
+        mut simd: impl FnMut(Simd<T, LANES>) -> Simd<U, LANES>,
+        mut scalar: impl FnMut(T) -> U,
+    ) -> Vec<U> {
+        let mut vec = self.into_vec();
+
+        if Layout::new::<T>() != Layout::new::<U>() {
+            let mut output = Vec::with_capacity(vec.len());
+            let chunks = vec.chunks_exact(LANES);
+            let rest = chunks.remainder();
+
+            for chunk in chunks {
+                output.extend_from_slice(simd(Simd::from_slice(chunk)).as_array());
+            }
+
+            output.extend(rest.iter().copied().map(scalar));
+
+            return output;
+        }
+
+        // `SimdElement`s are `Copy` and valid for any bit pattern, so if a
+        // kernel panics, the partially written buffer can be freed as a `Vec<T>`
+        let len = vec.len();
+        let ptr = vec.as_mut_ptr();
+        let split = len - len % LANES;
+
+        unsafe {
+            for i in (0..split).step_by(LANES) {
+                let lanes = Simd::from_array((ptr.add(i) as *const [T; LANES]).read());
+                (ptr.add(i) as *mut [U; LANES]).write(simd(lanes).to_array());
+            }
+
+            for i in split..len {
+                (ptr.add(i) as *mut U).write(scalar(ptr.add(i).read()));
+            }
+
+            let vec = ManuallyDrop::new(vec);
+            Vec::from_raw_parts(ptr as *mut U, len, vec.capacity())
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+
+/// Extension methods for writing into the spare capacity of a `Vec<T>`
+pub trait SpareCapacityExt {
+    /// The type that the `Vec<T>` stores
+    type T;
+
+    /// Create a writer into the spare capacity of the vector
+    ///
+    /// Nothing that is written into the writer is visible in the
+    /// vector until it is committed with [`SpareWriter::commit`]
+    fn spare_writer(&mut self) -> SpareWriter<'_, Self::T>;
+}
+
+impl<T> SpareCapacityExt for Vec<T> {
+    type T = T;
+
+    fn spare_writer(&mut self) -> SpareWriter<'_, T> {
+        SpareWriter {
+            vec: self,
+            written: 0,
+        }
+    }
+}
+
+/// A bounds-checked writer into the spare capacity of a `Vec<T>`,
+/// created by [`SpareCapacityExt::spare_writer`]
+///
+/// If the writer is dropped without calling `commit`, then all of the
+/// values written into it are dropped, and the vector is left unchanged
+pub struct SpareWriter<'a, T> {
+    vec: &'a mut Vec<T>,
+    written: usize,
+}
+
+impl<T> SpareWriter<'_, T> {
+    /// The number of values written into the writer
+    pub fn len(&self) -> usize {
+        self.written
+    }
+
+    /// Returns true if nothing was written into the writer
+    pub fn is_empty(&self) -> bool {
+        self.written == 0
+    }
+
+    /// The number of values that can still be written into the writer
+    pub fn remaining(&self) -> usize {
+        self.vec.capacity() - self.vec.len() - self.written
+    }
+
+    /// The values written into the writer
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.vec.as_ptr().add(self.vec.len()), self.written) }
+    }
+
+    /// The values written into the writer
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe {
+            std::slice::from_raw_parts_mut(self.vec.as_mut_ptr().add(self.vec.len()), self.written)
+        }
+    }
+
+    /// Write a value into the spare capacity
+    ///
+    /// If there is no more spare capacity, the value is returned back
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.remaining() == 0 {
+            return Err(value);
+        }
+
+        unsafe {
+            self.vec
+                .as_mut_ptr()
+                .add(self.vec.len() + self.written)
+                .write(value);
+        }
+
+        self.written += 1;
+
+        Ok(())
+    }
+
+    /// Write values from the iterator until either the iterator
+    /// or the spare capacity runs out, returning the number of values written
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> usize {
+        let start = self.written;
+        let mut iter = iter.into_iter();
+
+        while self.remaining() != 0 {
+            match iter.next() {
+                Some(value) => {
+                    let _ = self.push(value);
+                }
+                None => break,
+            }
+        }
+
+        self.written - start
+    }
+
+    /// Append all of the written values to the vector
+    pub fn commit(self) {
+        let writer = ManuallyDrop::new(self);
+
+        unsafe {
+            let len = writer.vec.len();
+            let vec = std::ptr::read(&writer.vec);
+            vec.set_len(len + writer.written);
+        }
+    }
+}
+
+impl<T> Drop for SpareWriter<'_, T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.vec.as_mut_ptr().add(self.vec.len()), self.written) }
+    }
+}
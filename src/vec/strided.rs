@@ -0,0 +1,80 @@
+use std::mem::ManuallyDrop;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// Owns all of the elements except the one that was moved
+/// out, only dropped if the function panics
+struct Hole<T> {
+    ptr: *mut T,
+    len: usize,
+    hole: usize,
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let (ptr, hole) = (self.ptr, self.hole);
+
+            defer! {
+                cleanup::drop_slice(ptr.add(hole + 1), self.len - hole - 1);
+            }
+
+            cleanup::drop_slice(ptr, hole);
+        }
+    }
+}
+
+pub(crate) fn zip_column_with<T, U, F: FnMut(T, U) -> T>(
+    vec: Vec<T>,
+    stride: usize,
+    col: usize,
+    other: Vec<U>,
+    mut f: F,
+) -> Vec<T> {
+    assert!(
+        col < stride,
+        "the column {} is out of bounds for a stride of {}",
+        col,
+        stride
+    );
+
+    let mut vec = ManuallyDrop::new(vec);
+    let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+    unsafe {
+        let alloc = RawAllocGuard::new(ptr, cap);
+
+        for (index, y) in (col..len).step_by(stride).zip(other) {
+            let hole = Hole {
+                ptr,
+                len,
+                hole: index,
+            };
+
+            let x = ptr.add(index).read();
+            ptr.add(index).write(f(x, y));
+
+            std::mem::forget(hole);
+        }
+
+        std::mem::forget(alloc);
+
+        Vec::from_raw_parts(ptr, len, cap)
+    }
+}
+
+pub(crate) fn map_column<T, F: FnMut(T) -> T>(
+    vec: Vec<T>,
+    stride: usize,
+    col: usize,
+    mut f: F,
+) -> Vec<T> {
+    let rows = match stride {
+        0 => 0,
+        _ => vec.len().saturating_sub(col).div_ceil(stride),
+    };
+
+    // a `Vec<()>` never allocates, so this is free
+    zip_column_with(vec, stride, col, vec![(); rows], move |x, ()| f(x))
+}
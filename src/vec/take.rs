@@ -0,0 +1,22 @@
+use std::alloc::Layout;
+
+use crate::{reuse_config, VecExt};
+
+pub(crate) fn take_map<T, U, F: FnMut(T) -> U>(
+    mut vec: Vec<T>,
+    n: usize,
+    f: F,
+) -> (Vec<U>, Vec<T>) {
+    let n = n.min(vec.len());
+
+    if Layout::new::<T>() == Layout::new::<U>() {
+        // the prefix keeps the allocation, and is mapped in place
+        let rest = vec.split_off(n);
+        (vec.map(f), rest)
+    } else {
+        // the suffix keeps the allocation, `Drain` moves it to the front,
+        // even if `f` panics
+        let output = reuse_config().collect(vec.drain(..n).map(f), n);
+        (output, vec)
+    }
+}
@@ -0,0 +1,176 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+use std::panic::resume_unwind;
+
+use crate::cleanup;
+use crate::raw::RawAllocGuard;
+
+/// A pointer that can be sent to the worker threads, each worker
+/// only touches a disjoint chunk of the elements behind it
+struct SendPtr<T>(*mut T);
+
+impl<T> Clone for SendPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SendPtr<T> {}
+
+unsafe impl<T: Send> Send for SendPtr<T> {}
+unsafe impl<T: Send> Sync for SendPtr<T> {}
+
+/// Owns the unread inputs and the written outputs of a chunk,
+/// only dropped if the function panics
+struct ChunkGuard<T, U, V> {
+    a: *mut T,
+    b: *mut U,
+    out: *mut V,
+    start: usize,
+    read: usize,
+    written: usize,
+    end: usize,
+}
+
+impl<T, U, V> Drop for ChunkGuard<T, U, V> {
+    fn drop(&mut self) {
+        unsafe {
+            defer! {
+                cleanup::drop_slice(self.b.add(self.read), self.end - self.read);
+            }
+
+            defer! {
+                cleanup::drop_slice(self.a.add(self.read), self.end - self.read);
+            }
+
+            cleanup::drop_slice(self.out.add(self.start), self.written - self.start);
+        }
+    }
+}
+
+unsafe fn run_chunk<T, U, V, F: Fn(T, U) -> V>(
+    a: SendPtr<T>,
+    b: SendPtr<U>,
+    out: SendPtr<V>,
+    start: usize,
+    end: usize,
+    f: &F,
+) {
+    let mut guard = ChunkGuard {
+        a: a.0,
+        b: b.0,
+        out: out.0,
+        start,
+        read: start,
+        written: start,
+        end,
+    };
+
+    for i in start..end {
+        let x = guard.a.add(i).read();
+        let y = guard.b.add(i).read();
+        guard.read = i + 1;
+
+        // in place, element `i` of the output only overwrites
+        // element `i` of the input, which was just read
+        guard.out.add(i).write(f(x, y));
+        guard.written = i + 1;
+    }
+
+    std::mem::forget(guard);
+}
+
+fn into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+pub(crate) fn zip_with_threads<T: Send, U: Send, V: Send, F: Fn(T, U) -> V + Sync>(
+    a: Vec<T>,
+    b: Vec<U>,
+    f: F,
+    num_threads: usize,
+) -> Vec<V> {
+    let num_threads = match num_threads {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        n => n,
+    };
+
+    let len = a.len().min(b.len());
+    let chunk = len.div_ceil(num_threads.max(1)).max(1);
+
+    let (reuse_a, reuse_b) = if Layout::new::<T>() == Layout::new::<V>() {
+        (true, false)
+    } else {
+        (false, Layout::new::<U>() == Layout::new::<V>())
+    };
+
+    let (out_ptr, out_cap) = match (reuse_a, reuse_b) {
+        (true, _) => (a.as_ptr() as *mut V, a.capacity()),
+        (_, true) => (b.as_ptr() as *mut V, b.capacity()),
+        _ => {
+            let (ptr, _, cap) = into_raw_parts(Vec::<V>::with_capacity(len));
+            (ptr, cap)
+        }
+    };
+
+    let (a_ptr, a_len, a_cap) = into_raw_parts(a);
+    let (b_ptr, b_len, b_cap) = into_raw_parts(b);
+
+    unsafe {
+        // these run last, after all of the elements are dropped
+        let out_alloc = RawAllocGuard::new(out_ptr, out_cap);
+        let _a_alloc = (!reuse_a).then(|| RawAllocGuard::new(a_ptr, a_cap));
+        let _b_alloc = (!reuse_b).then(|| RawAllocGuard::new(b_ptr, b_cap));
+
+        // the excess elements are never read
+        defer! {
+            cleanup::drop_slice(b_ptr.add(len), b_len - len);
+        }
+
+        defer! {
+            cleanup::drop_slice(a_ptr.add(len), a_len - len);
+        }
+
+        let (a, b, out) = (SendPtr(a_ptr), SendPtr(b_ptr), SendPtr(out_ptr));
+        let f = &f;
+
+        let results = std::thread::scope(|scope| {
+            let handles = (0..len)
+                .step_by(chunk)
+                .map(|start| {
+                    let end = len.min(start + chunk);
+                    let handle = scope.spawn(move || run_chunk(a, b, out, start, end, f));
+                    (start, end, handle)
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|(start, end, handle)| (start, end, handle.join()))
+                .collect::<Vec<_>>()
+        });
+
+        if results.iter().any(|(_, _, result)| result.is_err()) {
+            let mut payload = None;
+
+            // a chunk panicked, so the chunks that finished must be dropped
+            for (start, end, result) in results {
+                match result {
+                    Ok(()) => cleanup::drop_slice(out_ptr.add(start), end - start),
+                    Err(p) => {
+                        payload.get_or_insert(p);
+                    }
+                }
+            }
+
+            if let Some(payload) = payload {
+                resume_unwind(payload)
+            }
+        }
+
+        std::mem::forget(out_alloc);
+
+        Vec::from_raw_parts(out_ptr, len, out_cap)
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::{Cell, UnsafeCell};
+use std::num::Wrapping;
+
+use crate::raw::cast_vec;
+
+/// A wrapper that has the same in-memory representation as the type that it wraps
+///
+/// This allows a vector of wrappers to be converted to and from a vector of the
+/// wrapped values in place, see [`TransparentVecExt`]
+///
+/// # Safety
+///
+/// `Self` must have the same size, alignment, and validity as `Self::Inner`, and
+/// it must be safe to move a value out of a `Self` without running its destructor
+pub unsafe trait Transparent {
+    /// The type that is wrapped
+    type Inner;
+}
+
+// `Cell<T>` is documented to have the same in-memory representation as `T`,
+// and owning the cell means that there are no outstanding shared references
+unsafe impl<T> Transparent for Cell<T> {
+    type Inner = T;
+}
+
+// `UnsafeCell<T>` is documented to have the same in-memory representation as `T`,
+// and owning the cell means that there are no outstanding pointers into it
+unsafe impl<T> Transparent for UnsafeCell<T> {
+    type Inner = T;
+}
+
+// `Wrapping<T>` is `repr(transparent)`, and doesn't add any invariants to `T`
+unsafe impl<T> Transparent for Wrapping<T> {
+    type Inner = T;
+}
+
+/// Extension methods for converting between `Vec<W>` and `Vec<W::Inner>`,
+/// where `W` is a [`Transparent`] wrapper
+///
+/// These reuse the allocation, and don't touch the elements
+///
+/// ```rust
+/// use std::cell::Cell;
+/// use std::num::Wrapping;
+/// use vec_utils::TransparentVecExt;
+///
+/// let cells = vec![1, 2, 3].wrap_transparent::<Cell<u32>>();
+/// cells[0].set(4);
+///
+/// let wrapping = cells.unwrap_transparent().wrap_transparent::<Wrapping<u32>>();
+/// assert_eq!(wrapping, [Wrapping(4), Wrapping(2), Wrapping(3)]);
+/// ```
+pub trait TransparentVecExt: Sized {
+    /// The type that the `Vec<T>` stores
+    type T;
+
+    /// Unwrap all of the elements in place
+    fn unwrap_transparent(self) -> Vec<<Self::T as Transparent>::Inner>
+    where
+        Self::T: Transparent;
+
+    /// Wrap all of the elements in place
+    fn wrap_transparent<W: Transparent<Inner = Self::T>>(self) -> Vec<W>;
+}
+
+impl<T> TransparentVecExt for Vec<T> {
+    type T = T;
+
+    fn unwrap_transparent(self) -> Vec<T::Inner>
+    where
+        T: Transparent,
+    {
+        unsafe { cast_vec(self) }
+    }
+
+    fn wrap_transparent<W: Transparent<Inner = T>>(self) -> Vec<W> {
+        unsafe { cast_vec(self) }
+    }
+}
@@ -0,0 +1,68 @@
+fn checked_len(len: usize, rows: usize, cols: usize) {
+    assert_eq!(
+        rows.checked_mul(cols),
+        Some(len),
+        "a {}x{} matrix doesn't have {} elements",
+        rows,
+        cols,
+        len
+    );
+}
+
+pub(crate) fn transpose_in_place<T>(slice: &mut [T], rows: usize, cols: usize) {
+    let len = slice.len();
+    checked_len(len, rows, cols);
+
+    if rows <= 1 || cols <= 1 {
+        return;
+    }
+
+    // the element at `r * cols + c` moves to `c * rows + r`, this can't
+    // overflow because the destination is always less than `len`
+    let next = |index: usize| (index % cols) * rows + index / cols;
+
+    const BITS: usize = usize::BITS as usize;
+    let mut visited = vec![0usize; len.div_ceil(BITS)];
+
+    // the first and last elements never move
+    for start in 1..len - 1 {
+        if visited[start / BITS] & (1 << (start % BITS)) != 0 {
+            continue;
+        }
+
+        // `slice[start]` always holds the element that needs to be moved next
+        let mut index = next(start);
+
+        while index != start {
+            slice.swap(start, index);
+            visited[index / BITS] |= 1 << (index % BITS);
+            index = next(index);
+        }
+    }
+}
+
+pub(crate) fn transpose_with<T>(vec: &mut Vec<T>, rows: usize, cols: usize, scratch: &mut Vec<T>) {
+    let len = vec.len();
+    checked_len(len, rows, cols);
+
+    scratch.clear();
+    scratch.reserve_exact(len);
+
+    unsafe {
+        let src = vec.as_ptr();
+        let dest = scratch.as_mut_ptr();
+
+        // nothing can panic from here on, so the elements can be moved directly
+        vec.set_len(0);
+
+        for r in 0..rows {
+            for c in 0..cols {
+                dest.add(c * rows + r).write(src.add(r * cols + c).read());
+            }
+        }
+
+        scratch.set_len(len);
+    }
+
+    std::mem::swap(vec, scratch);
+}
@@ -0,0 +1,44 @@
+use crate::{Try, VecExt};
+
+/// Extension methods for `Vec<Box<T>>`
+pub trait VecBoxExt: Sized {
+    /// The type that the boxes store
+    type T;
+
+    /// Map a vector of boxes to another vector by moving each value out of it's
+    /// box, each box is freed right after it's value is moved out.
+    ///
+    /// Will try and reuse the outer allocation if the allocation layouts match, i.e. if
+    /// `std::alloc::Layout::<Box<T>>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused (this is always true if `U = Box<V>` for any sized `V`)
+    fn map_unbox<U, F: FnMut(Self::T) -> U>(self, f: F) -> Vec<U>;
+
+    /// Map a vector of boxes to another vector by moving each value out of it's
+    /// box, each box is freed right after it's value is moved out.
+    ///
+    /// Will try and reuse the outer allocation if the allocation layouts match, i.e. if
+    /// `std::alloc::Layout::<Box<T>>::new() == std::alloc::Layout::<U>::new()`
+    /// then the allocation will be reused
+    ///
+    /// The mapping function can be fallible, and on early return, it will drop all previous values,
+    /// and the rest of the input vector. Thre error will be returned as a `Result`
+    fn try_map_unbox<U, R: Try<Ok = U>, F: FnMut(Self::T) -> R>(
+        self,
+        f: F,
+    ) -> Result<Vec<U>, R::Error>;
+}
+
+impl<T> VecBoxExt for Vec<Box<T>> {
+    type T = T;
+
+    fn map_unbox<U, F: FnMut(T) -> U>(self, mut f: F) -> Vec<U> {
+        self.map(move |bx| f(*bx))
+    }
+
+    fn try_map_unbox<U, R: Try<Ok = U>, F: FnMut(T) -> R>(
+        self,
+        mut f: F,
+    ) -> Result<Vec<U>, R::Error> {
+        self.try_map(move |bx| f(*bx))
+    }
+}
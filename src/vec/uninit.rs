@@ -0,0 +1,94 @@
+use std::mem::{ManuallyDrop, MaybeUninit};
+
+use crate::cleanup;
+
+/// Allocate a vector of `len` uninitialized elements
+pub fn alloc_uninit_vec<T>(len: usize) -> Vec<MaybeUninit<T>> {
+    let mut vec = Vec::with_capacity(len);
+
+    // `MaybeUninit` doesn't need to be initialized
+    unsafe { vec.set_len(len) }
+
+    vec
+}
+
+/// Convert a vector of `MaybeUninit<T>` into a vector of `T`, reusing the allocation
+///
+/// # Safety
+///
+/// Every element of the vector must be initialized
+pub unsafe fn assume_init_vec<T>(vec: Vec<MaybeUninit<T>>) -> Vec<T> {
+    let mut vec = ManuallyDrop::new(vec);
+
+    Vec::from_raw_parts(vec.as_mut_ptr() as *mut T, vec.len(), vec.capacity())
+}
+
+/// A guard that initializes a vector of `MaybeUninit<T>` from front to back
+///
+/// If the guard is dropped before the vector is fully initialized
+/// (for example, due to a panic), then the initialized prefix is dropped
+/// and the allocation is freed
+pub struct PartialInitGuard<T> {
+    vec: Vec<MaybeUninit<T>>,
+    init: usize,
+}
+
+impl<T> PartialInitGuard<T> {
+    /// Create a new guard, treating every element of `vec` as uninitialized
+    pub fn new(vec: Vec<MaybeUninit<T>>) -> Self {
+        Self { vec, init: 0 }
+    }
+
+    /// The number of elements that have been initialized
+    pub fn initialized(&self) -> usize {
+        self.init
+    }
+
+    /// Returns true if every element has been initialized
+    pub fn is_complete(&self) -> bool {
+        self.init == self.vec.len()
+    }
+
+    /// The initialized prefix of the vector
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.vec.as_ptr() as *const T, self.init) }
+    }
+
+    /// Initialize the next element
+    ///
+    /// # Panic
+    ///
+    /// If every element has already been initialized
+    pub fn push(&mut self, value: T) {
+        assert!(
+            !self.is_complete(),
+            "tried to initialize more elements than the vector holds"
+        );
+
+        self.vec[self.init] = MaybeUninit::new(value);
+        self.init += 1;
+    }
+
+    /// Convert the fully initialized vector into a `Vec<T>`, reusing the allocation
+    ///
+    /// # Panic
+    ///
+    /// If not every element has been initialized
+    pub fn finish(self) -> Vec<T> {
+        assert!(
+            self.is_complete(),
+            "tried to finish a partially initialized vector"
+        );
+
+        let mut guard = ManuallyDrop::new(self);
+        let vec = std::mem::take(&mut guard.vec);
+
+        unsafe { assume_init_vec(vec) }
+    }
+}
+
+impl<T> Drop for PartialInitGuard<T> {
+    fn drop(&mut self) {
+        unsafe { cleanup::drop_slice(self.vec.as_mut_ptr() as *mut T, self.init) }
+    }
+}
@@ -0,0 +1,204 @@
+// The cleanup policy is process-wide, so these tests live in their own binary
+// and run one after the other
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use vec_utils::*;
+
+static POLICY: Mutex<()> = Mutex::new(());
+
+fn lock_policy() -> MutexGuard<'static, ()> {
+    POLICY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct Noisy<'a> {
+    dropped: &'a Cell<usize>,
+    panics: bool,
+}
+
+impl Drop for Noisy<'_> {
+    fn drop(&mut self) {
+        self.dropped.set(self.dropped.get() + 1);
+
+        if self.panics {
+            panic!("drop")
+        }
+    }
+}
+
+fn noisy<'a>(dropped: &'a Cell<usize>, panics: &[bool]) -> Vec<Noisy<'a>> {
+    panics
+        .iter()
+        .map(|&panics| Noisy { dropped, panics })
+        .collect()
+}
+
+#[test]
+fn policies() {
+    let _lock = lock_policy();
+    assert_eq!(cleanup_policy(), CleanupPolicy::Abort);
+
+    // all of the excess elements are dropped, even if a destructor panics
+    let dropped = Cell::new(0);
+    let a = noisy(
+        &dropped,
+        &[false, false, false, false, false, false, false, true],
+    );
+    let b = vec![0u8; 3];
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        a.zip_with_rev(b, |x, _| {
+            std::mem::forget(x);
+            (0usize, false)
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 8);
+
+    // leak the remaining elements instead of aborting
+    set_cleanup_policy(CleanupPolicy::Leak);
+    assert_eq!(cleanup_policy(), CleanupPolicy::Leak);
+
+    let dropped = Cell::new(0);
+    let vec = noisy(&dropped, &[false, false, true, false]);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        vec.map(|x| {
+            if x.dropped.get() == 0 {
+                std::mem::forget(x);
+                panic!("map")
+            }
+
+            (0usize, false)
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 0);
+
+    // stop dropping after the first destructor that panics
+    let dropped = Cell::new(0);
+    let a = noisy(&dropped, &[false, false, true, false]);
+    let b = vec![0u8; 1];
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        a.zip_with(b, |x, _| {
+            std::mem::forget(x);
+            (0usize, false)
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 2);
+
+    // drop everything, and resume the first panic
+    set_cleanup_policy(CleanupPolicy::Continue);
+
+    let dropped = Cell::new(0);
+    let a = noisy(&dropped, &[false, true, false, true, false]);
+    let b = vec![0u8; 1];
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        a.zip_with(b, |x, _| {
+            std::mem::forget(x);
+            (0usize, false)
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 4);
+
+    let dropped = Cell::new(0);
+    let a = noisy(&dropped, &[false, true, false, true, false]);
+    let b = vec![0u8; 1];
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        a.zip_with_rev(b, |x, _| {
+            std::mem::forget(x);
+            (0usize, false)
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 4);
+
+    set_cleanup_policy(CleanupPolicy::Abort);
+}
+
+#[test]
+#[cfg(feature = "test-faults")]
+fn injected_faults() {
+    let _lock = lock_policy();
+
+    let drop_panic = Faults {
+        drop_panic: Some(1),
+        ..Faults::default()
+    };
+
+    // the rest of the source of a `ReuseIter` is dropped according to the policy
+    set_cleanup_policy(CleanupPolicy::Leak);
+
+    let dropped = Cell::new(0);
+    let mut iter = noisy(&dropped, &[false; 5]).into_reuse_iter();
+    drop(iter.next());
+
+    let result = catch_unwind(AssertUnwindSafe(|| drop_panic.inject(|| drop(iter))));
+
+    let payload = result.unwrap_err();
+    let fault = payload.downcast_ref::<InjectedFault>();
+    assert_eq!(fault, Some(&InjectedFault::Drop { index: 1 }));
+    assert_eq!(dropped.get(), 3);
+
+    set_cleanup_policy(CleanupPolicy::Continue);
+
+    let dropped = Cell::new(0);
+    let mut iter = noisy(&dropped, &[false; 5]).into_reuse_iter();
+    drop(iter.next());
+
+    let result = catch_unwind(AssertUnwindSafe(|| drop_panic.inject(|| drop(iter))));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 5);
+
+    // the unread input of `map_expand` is leaked while unwinding
+    set_cleanup_policy(CleanupPolicy::Leak);
+
+    let dropped = Cell::new(0);
+    let vec = noisy(&dropped, &[false; 4]);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        vec.map_expand(|x| {
+            if x.dropped.get() == 0 {
+                drop(x);
+                panic!("map_expand")
+            }
+
+            [0u64; 2]
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 1);
+
+    set_cleanup_policy(CleanupPolicy::Abort);
+
+    let dropped = Cell::new(0);
+    let vec = noisy(&dropped, &[false; 4]);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        vec.map_expand(|x| {
+            if x.dropped.get() == 0 {
+                drop(x);
+                panic!("map_expand")
+            }
+
+            [0u64; 2]
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(dropped.get(), 4);
+}
@@ -0,0 +1,977 @@
+// Tests for the optional integrations with other crates
+
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+#[cfg(feature = "bytemuck")]
+mod bytemuck {
+    use bytemuck::TransparentWrapper;
+    use vec_utils::{
+        unwrap_box, unwrap_boxed_slice, unwrap_vec, wrap_box, wrap_boxed_slice, wrap_vec,
+    };
+
+    #[derive(Debug, PartialEq)]
+    #[repr(transparent)]
+    struct Name(String);
+
+    unsafe impl TransparentWrapper<String> for Name {}
+
+    #[test]
+    fn transparent_vec() {
+        let vec = vec![String::from("a"), String::from("b")];
+        let ptr = vec.as_ptr() as usize;
+
+        let names: Vec<Name> = wrap_vec(vec);
+        assert_eq!(names[1], Name(String::from("b")));
+        assert_eq!(names.as_ptr() as usize, ptr);
+
+        let vec = unwrap_vec::<Name, _>(names);
+        assert_eq!(vec, ["a", "b"]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn transparent_box() {
+        let bx = Box::new(String::from("a"));
+        let ptr = &*bx as *const String as usize;
+
+        let name: Box<Name> = wrap_box(bx);
+        assert_eq!(*name, Name(String::from("a")));
+
+        let bx = unwrap_box::<Name, _>(name);
+        assert_eq!(&*bx as *const String as usize, ptr);
+
+        let names: Box<[Name]> = wrap_boxed_slice(vec![String::from("b")].into_boxed_slice());
+        assert_eq!(*names, [Name(String::from("b"))]);
+        assert_eq!(*unwrap_boxed_slice::<Name, _>(names), ["b"]);
+    }
+}
+
+#[cfg(feature = "bytes")]
+mod bytes {
+    use bytes::{Bytes, BytesMut};
+    use vec_utils::{BytesExt, BytesMutExt};
+
+    #[test]
+    fn map_bytes() {
+        let buf = BytesMut::from(&b"hello"[..]);
+        let ptr = buf.as_ptr();
+
+        let buf = buf.map_bytes(|b| b.to_ascii_uppercase());
+
+        assert_eq!(&buf[..], b"HELLO");
+        assert_eq!(buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn zip_with() {
+        let a = BytesMut::from(&[1u8, 2, 3][..]);
+        let mut b = BytesMut::with_capacity(64);
+        b.extend_from_slice(&[10, 20, 30, 40]);
+        let ptr = b.as_ptr();
+
+        let c = a.zip_with(b, |a, b| b - a);
+
+        assert_eq!(&c[..], [9, 18, 27]);
+        assert_eq!(c.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn shared_map_bytes() {
+        let a = Bytes::from(b"hello".to_vec());
+        let b = a.clone();
+
+        let a = a.map_bytes(|b| b.to_ascii_uppercase());
+
+        assert_eq!(&a[..], b"HELLO");
+        assert_eq!(&b[..], b"hello");
+
+        assert_eq!(&b.map_bytes(|b| b + 1)[..], b"ifmmp");
+    }
+}
+
+#[cfg(feature = "ndarray")]
+mod ndarray {
+    use ndarray::{array, s, Array2};
+    use vec_utils::ArrayExt;
+
+    #[test]
+    fn map_reuse() {
+        let a: Array2<f32> = array![[0.0, 1.0], [2.0, 3.0]];
+        let ptr = a.as_ptr();
+
+        let b = a.map_reuse(f32::to_bits);
+
+        assert_eq!(b.as_ptr() as *const f32, ptr);
+        assert_eq!(b, array![[0.0f32, 1.0], [2.0, 3.0]].mapv(f32::to_bits));
+    }
+
+    #[test]
+    fn map_sliced() {
+        let mut a: Array2<i32> = array![[0, 1, 2], [3, 4, 5], [6, 7, 8]];
+        a.slice_collapse(s![1.., ..]);
+
+        assert_eq!(a.map_reuse(|x| x * 2), array![[6, 8, 10], [12, 14, 16]]);
+
+        let a: Array2<i32> = array![[0, 1, 2], [3, 4, 5]];
+        let a = a.reversed_axes();
+
+        assert_eq!(a.map_reuse(|x| x * 2), array![[0, 6], [2, 8], [4, 10]]);
+    }
+
+    #[test]
+    fn zip_with_reuse() {
+        let a: Array2<f32> = array![[0.0, 1.0], [2.0, 3.0]];
+        let b: Array2<f32> = array![[1.0, 1.0], [1.0, 1.0]];
+
+        assert_eq!(
+            a.zip_with_reuse(b, |a, b| a + b),
+            array![[1.0, 2.0], [3.0, 4.0]]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zip_with_reuse_mismatch() {
+        let a: Array2<f32> = array![[0.0, 1.0], [2.0, 3.0]];
+        let b: Array2<f32> = array![[1.0, 1.0]];
+
+        a.zip_with_reuse(b, |a, b| a + b);
+    }
+}
+
+#[cfg(feature = "bumpalo")]
+mod bumpalo {
+    use bumpalo::Bump;
+    use vec_utils::ArenaExt;
+
+    #[test]
+    fn map_into_arena() {
+        let bump = Bump::new();
+
+        let slice = vec![0.0f32, 1.0, 2.0].map_into_arena(&bump, f32::to_bits);
+
+        assert_eq!(slice, [0.0f32, 1.0, 2.0].map(f32::to_bits));
+    }
+
+    #[test]
+    fn zip_with_into_arena() {
+        let bump = Bump::new();
+
+        let slice = vec![1, 2, 3].zip_with_into_arena(vec![10, 20], &bump, |a, b| a + b);
+
+        assert_eq!(slice, [11, 22]);
+    }
+}
+
+#[cfg(feature = "thin-vec")]
+mod thin_vec {
+    use std::cell::Cell;
+
+    use thin_vec::{thin_vec, ThinVec};
+    use vec_utils::{zip_with, ThinVecExt};
+
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn map() {
+        let vec = thin_vec![1u32, 2, 3];
+        let ptr = vec.as_ptr() as usize;
+
+        let vec = vec.map(|x| x as f32);
+        assert_eq!(vec, [1.0, 2.0, 3.0]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        let vec = vec.map(|x| x as u8);
+        assert_eq!(vec, [1, 2, 3]);
+
+        let empty = ThinVec::<u32>::new().map(|x| x as f32);
+        assert!(empty.is_empty());
+        assert_eq!(thin_vec![(); 3].map(|()| ((), ())).len(), 3);
+
+        let result = vec.try_map(|x| if x < 3 { Ok(x) } else { Err(x) });
+        assert_eq!(result, Err(3));
+    }
+
+    #[test]
+    fn zip_with() {
+        let a = thin_vec![1u32, 2, 3];
+        let mut b = ThinVec::with_capacity(8);
+        b.extend([10.0f32, 20.0, 30.0, 40.0]);
+        let ptr = b.as_ptr() as usize;
+
+        let c = a.zip_with(b, |a, b| a as f32 + b);
+        assert_eq!(c, [11.0, 22.0, 33.0]);
+        assert_eq!(c.as_ptr() as usize, ptr);
+
+        let d = thin_vec![1u8, 2].zip_with(c, |a, b| b as u32 - a as u32);
+        assert_eq!(d, [10, 20]);
+
+        let e = thin_vec![1u8, 2].zip_with(thin_vec![3u16], |a, b| a as u64 + b as u64);
+        assert_eq!(e, [4]);
+    }
+
+    #[test]
+    fn zip_with_panic() {
+        let dropped = Cell::new(0);
+
+        for &n in &[0, 2, 4] {
+            dropped.set(0);
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let a = (0..5).map(|_| Counted(&dropped)).collect::<ThinVec<_>>();
+                let b = (0..6).map(|_| Counted(&dropped)).collect::<ThinVec<_>>();
+                let mut i = 0;
+
+                a.zip_with(b, |a, b| {
+                    if i == n {
+                        panic!()
+                    }
+
+                    i += 1;
+                    drop(b);
+                    a
+                })
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(dropped.get(), 11);
+        }
+    }
+
+    #[test]
+    fn tuple_elem() {
+        let a = thin_vec![1.0f32, 2.0, 3.0];
+        let b = vec![10u32, 20];
+        let ptr = b.as_ptr() as usize;
+
+        let c: Vec<u32> = zip_with!((a, b), |a, b| a as u32 + b);
+        assert_eq!(c, [11, 22]);
+        assert_eq!(c.as_ptr() as usize, ptr);
+
+        let c: Vec<f64> = zip_with!((thin_vec![1u8], thin_vec![2u8]), |a, b| (a + b) as f64);
+        assert_eq!(c, [3.0]);
+    }
+}
+
+#[cfg(feature = "futures")]
+mod futures {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use vec_utils::AsyncVecExt;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll<F: Future>(fut: Pin<&mut F>) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(Noop));
+        fut.poll(&mut Context::from_waker(&waker))
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = Box::pin(fut);
+
+        loop {
+            if let Poll::Ready(value) = poll(fut.as_mut()) {
+                return value;
+            }
+        }
+    }
+
+    /// Returns `Pending` once before completing
+    async fn yield_now() {
+        let mut yielded = false;
+
+        std::future::poll_fn(|_| {
+            if std::mem::replace(&mut yielded, true) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn try_map_async() {
+        let vec = vec![1u32, 2, 3];
+        let ptr = vec.as_ptr() as usize;
+
+        let vec = block_on(vec.try_map_async(|x| async move {
+            yield_now().await;
+            Ok::<_, ()>(x as f32)
+        }))
+        .unwrap();
+
+        assert_eq!(vec, [1.0, 2.0, 3.0]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        let vec = block_on(vec.try_map_async(|x| async move { Ok::<_, ()>(x as u8) })).unwrap();
+        assert_eq!(vec, [1, 2, 3]);
+
+        let result = block_on(vec.try_map_async(|x| async move {
+            if x == 2 {
+                Err("two")
+            } else {
+                Ok(x)
+            }
+        }));
+        assert_eq!(result, Err("two"));
+    }
+
+    #[test]
+    fn try_map_async_cancel() {
+        let dropped = Cell::new(0);
+
+        for &polls in &[0, 1, 2, 4] {
+            dropped.set(0);
+
+            let vec = (0..4).map(|_| Counted(&dropped)).collect::<Vec<_>>();
+
+            let mut fut = Box::pin(vec.try_map_async(|x| async move {
+                yield_now().await;
+                Ok::<_, ()>((x, 0usize))
+            }));
+
+            for _ in 0..polls {
+                assert!(poll(fut.as_mut()).is_pending());
+            }
+
+            drop(fut);
+
+            assert_eq!(dropped.get(), 4);
+        }
+
+        dropped.set(0);
+
+        let vec = (0..4).map(|_| Counted(&dropped)).collect::<Vec<_>>();
+        let result = block_on(vec.try_map_async(|x| async move {
+            let _x = x;
+            yield_now().await;
+            Err::<u8, _>(())
+        }));
+
+        assert_eq!(result, Err(()));
+        assert_eq!(dropped.get(), 4);
+    }
+
+    #[test]
+    fn try_map_async_poll_after_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let dropped = Cell::new(0);
+
+        let vec = (0..4).map(|_| Counted(&dropped)).collect::<Vec<_>>();
+        let mut calls = 0;
+        let mut fut = Box::pin(vec.try_map_async(|x| {
+            calls += 1;
+            if calls == 2 {
+                panic!()
+            }
+
+            async move { Ok::<_, ()>(x) }
+        }));
+
+        assert!(catch_unwind(AssertUnwindSafe(|| poll(fut.as_mut()))).is_err());
+        assert_eq!(dropped.get(), 4);
+
+        // the future is poisoned, so it panics instead of resuming
+        assert!(catch_unwind(AssertUnwindSafe(|| poll(fut.as_mut()))).is_err());
+        drop(fut);
+        assert_eq!(dropped.get(), 4);
+
+        // and polling a completed future panics too
+        let vec = vec![1u32, 2];
+        let mut fut = Box::pin(vec.try_map_async(|x| async move { Ok::<_, ()>(x) }));
+        assert_eq!(poll(fut.as_mut()), Poll::Ready(Ok(vec![1, 2])));
+        assert!(catch_unwind(AssertUnwindSafe(|| poll(fut.as_mut()))).is_err());
+    }
+
+    #[test]
+    fn reuse_stream() {
+        use vec_utils::VecExt;
+
+        let vec = vec![1u32, 2, 3, 4];
+        let ptr = vec.as_ptr() as usize;
+
+        let vec = block_on(
+            vec.into_reuse_iter()
+                .filter(|&x| x != 3)
+                .then(|x| async move {
+                    yield_now().await;
+                    x as f32
+                })
+                .collect_reuse(),
+        );
+
+        assert_eq!(vec, [1.0, 2.0, 4.0]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        let vec = block_on(
+            vec.into_reuse_iter()
+                .then(|x| async move { x as u8 })
+                .collect_reuse(),
+        );
+        assert_eq!(vec, [1, 2, 4]);
+
+        let mut stream = Box::pin(
+            vec![1u8, 2]
+                .into_reuse_iter()
+                .then(|x| async move { x + 1 }),
+        );
+        assert_eq!(stream.size_hint(), (2, Some(2)));
+        assert_eq!(block_on(stream.as_mut().next()), Some(2));
+        assert_eq!(block_on(stream.as_mut().next()), Some(3));
+        assert_eq!(block_on(stream.as_mut().next()), None);
+    }
+
+    #[test]
+    fn reuse_stream_is_stream() {
+        use futures_core::Stream;
+        use vec_utils::VecExt;
+
+        fn sum<S: Stream<Item = u32>>(stream: S) -> u32 {
+            let mut stream = Box::pin(stream);
+            let mut total = 0;
+
+            assert_eq!(stream.size_hint(), (0, Some(4)));
+
+            while let Poll::Ready(Some(value)) = poll_stream(stream.as_mut()) {
+                total += value;
+            }
+
+            total
+        }
+
+        fn poll_stream<S: Stream>(stream: Pin<&mut S>) -> Poll<Option<S::Item>> {
+            let waker = Waker::from(Arc::new(Noop));
+            Stream::poll_next(stream, &mut Context::from_waker(&waker))
+        }
+
+        let stream = vec![1u32, 2, 3, 4]
+            .into_reuse_iter()
+            .filter(|&x| x != 3)
+            .then(|x| async move { x * 10 });
+
+        assert_eq!(sum(stream), 70);
+    }
+
+    #[test]
+    fn reuse_stream_cancel() {
+        use vec_utils::VecExt;
+
+        let dropped = Cell::new(0);
+
+        for &polls in &[0, 1, 2, 4] {
+            dropped.set(0);
+
+            let vec = (0..4).map(|_| Counted(&dropped)).collect::<Vec<_>>();
+
+            let mut fut = Box::pin(
+                vec.into_reuse_iter()
+                    .then(|x| async move {
+                        yield_now().await;
+                        (x, 0usize)
+                    })
+                    .collect_reuse(),
+            );
+
+            for _ in 0..polls {
+                assert!(poll(fut.as_mut()).is_pending());
+            }
+
+            drop(fut);
+
+            assert_eq!(dropped.get(), 4);
+        }
+    }
+
+    #[test]
+    fn run_async() {
+        use vec_utils::ResumableMap;
+
+        let vec = (0..10u32).collect::<Vec<_>>();
+        let ptr = vec.as_ptr() as usize;
+
+        let mut fut = Box::pin(ResumableMap::new(vec, |x| x as f32).run_async(4));
+
+        assert!(poll(fut.as_mut()).is_pending());
+        assert!(poll(fut.as_mut()).is_pending());
+
+        let vec = match poll(fut.as_mut()) {
+            Poll::Ready(vec) => vec,
+            Poll::Pending => panic!("the map should be finished"),
+        };
+
+        assert_eq!(vec, (0..10).map(|x| x as f32).collect::<Vec<_>>());
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        let vec = block_on(ResumableMap::new(vec, |x| x as u8).run_async(0));
+        assert_eq!(vec, (0..10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn run_async_cancel() {
+        use vec_utils::ResumableMap;
+
+        let dropped = Cell::new(0);
+
+        for &polls in &[0, 1, 2] {
+            dropped.set(0);
+
+            let vec = (0..10).map(|_| Counted(&dropped)).collect::<Vec<_>>();
+            let mut fut = Box::pin(ResumableMap::new(vec, |x| (x, 0usize)).run_async(4));
+
+            for _ in 0..polls {
+                assert!(poll(fut.as_mut()).is_pending());
+            }
+
+            drop(fut);
+
+            assert_eq!(dropped.get(), 10);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+mod rayon {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rayon::prelude::*;
+    use vec_utils::ParVecExt;
+
+    struct Counted<'a>(&'a AtomicUsize, usize);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn par_map() {
+        let vec = (0..10_000u32).collect::<Vec<_>>();
+        let ptr = vec.as_ptr() as usize;
+
+        let out = vec.par_map(|x| x as f32);
+        assert_eq!(out.as_ptr() as usize, ptr);
+        assert_eq!(out, (0..10_000).map(|x| x as f32).collect::<Vec<_>>());
+
+        let out = out.par_map(|x| x as u64);
+        assert_eq!(out, (0..10_000).collect::<Vec<_>>());
+
+        let out = Vec::<u32>::new().par_map(|x| x + 1);
+        assert!(out.is_empty());
+
+        let out = vec![(); 5].par_map(|()| ());
+        assert_eq!(out.len(), 5);
+    }
+
+    #[test]
+    fn par_zip_with() {
+        let a = (0..1000u32).collect::<Vec<_>>();
+        let b = (0..1200u32).map(|x| x as u8).collect::<Vec<_>>();
+        let ptr = a.as_ptr() as usize;
+
+        let out = a.par_zip_with(b, |x, y| x + u32::from(y));
+        assert_eq!(out.as_ptr() as usize, ptr);
+        assert_eq!(out.len(), 1000);
+        assert_eq!(out[300], 300 + 44);
+
+        // the right allocation is reused
+        let a = vec![1u8, 2, 3];
+        let b = vec![1.0f32, 2.0];
+        let ptr = b.as_ptr() as usize;
+
+        let out = a.par_zip_with(b, |x, y| f32::from(x) + y);
+        assert_eq!(out, [2.0, 4.0]);
+        assert_eq!(out.as_ptr() as usize, ptr);
+    }
+
+    #[test]
+    fn reuse_par_iter() {
+        let vec = (0..100u32).collect::<Vec<_>>();
+        let ptr = vec.as_ptr() as usize;
+
+        let out = vec
+            .into_reuse_par_iter()
+            .zip(0..100u32)
+            .map(|(x, y)| x * y)
+            .map(|x| x as i32)
+            .collect_reuse();
+        assert_eq!(out.as_ptr() as usize, ptr);
+        assert_eq!(out, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+        // the rest of rayon is available too
+        let evens: Vec<u32> = (0..100u32)
+            .collect::<Vec<_>>()
+            .into_reuse_par_iter()
+            .filter(|x| x % 4 == 0)
+            .collect();
+        assert_eq!(evens.len(), 25);
+
+        let mut chunks = Vec::new();
+        vec![1u32, 2, 3]
+            .into_reuse_par_iter()
+            .collect_into_vec(&mut chunks);
+        assert_eq!(chunks, [1, 2, 3]);
+    }
+
+    #[test]
+    fn par_map_panic() {
+        let dropped = AtomicUsize::new(0);
+
+        for &reuse in &[true, false] {
+            dropped.store(0, Ordering::Relaxed);
+
+            let vec = (0..1000).map(|i| Counted(&dropped, i)).collect::<Vec<_>>();
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                if reuse {
+                    drop(vec.par_map(|x| {
+                        if x.1 == 500 {
+                            panic!()
+                        }
+
+                        x
+                    }))
+                } else {
+                    drop(vec.par_map(|x| {
+                        if x.1 == 500 {
+                            panic!()
+                        }
+
+                        (x, 0u8)
+                    }))
+                }
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(dropped.load(Ordering::Relaxed), 1000);
+        }
+
+        dropped.store(0, Ordering::Relaxed);
+
+        let a = (0..1000).map(|i| Counted(&dropped, i)).collect::<Vec<_>>();
+        let b = (0..900).map(|i| Counted(&dropped, i)).collect::<Vec<_>>();
+        let out = a.par_zip_with(b, |x, _| x);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1000);
+        drop(out);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1900);
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use std::simd::num::{SimdFloat, SimdUint};
+    use std::simd::Simd;
+    use vec_utils::SimdVecExt;
+
+    #[test]
+    fn map_simd() {
+        let vec = (0..11u32).collect::<Vec<_>>();
+        let ptr = vec.as_ptr() as usize;
+
+        let vec =
+            vec.map_simd::<f32, 4>(|x| x.cast::<f32>() * Simd::splat(0.5), |x| x as f32 * 0.5);
+
+        assert_eq!(vec, (0..11).map(|x| x as f32 * 0.5).collect::<Vec<_>>());
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        let vec = vec.map_simd::<u8, 8>(|x| x.cast(), |x| x as u8);
+        assert_eq!(vec, [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5]);
+
+        let vec = vec.map_simd::<u8, 16>(|x| x + Simd::splat(1), |x| x + 1);
+        assert_eq!(vec, [1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6]);
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde {
+    use serde::de::DeserializeSeed;
+    use vec_utils::ReuseVec;
+
+    fn from_str<T: serde::de::DeserializeOwned>(
+        seed: ReuseVec<T>,
+        s: &str,
+    ) -> serde_json::Result<Vec<T>> {
+        seed.deserialize(&mut serde_json::Deserializer::from_str(s))
+    }
+
+    #[test]
+    fn reuse_vec() {
+        let donor = vec![String::from("old"); 4];
+        let ptr = donor.as_ptr() as usize;
+
+        let vec = from_str(ReuseVec::new(donor), r#"["a", "b"]"#).unwrap();
+
+        assert_eq!(vec, ["a", "b"]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+
+        assert!(from_str(ReuseVec::new(vec), r#"["a", 1]"#).is_err());
+    }
+
+    #[test]
+    fn reuse_vec_from_alloc() {
+        let donor = vec![[0u32; 2]; 4];
+        let ptr = donor.as_ptr() as usize;
+
+        let vec = from_str(ReuseVec::<f32>::from_alloc(donor), "[1.5, 2.5, 3.5]").unwrap();
+
+        assert_eq!(vec, [1.5, 2.5, 3.5]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+        assert_eq!(vec.capacity(), 8);
+
+        // `u8` and `u64` have different alignments, so a new vector is allocated
+        let vec = from_str(ReuseVec::<u64>::from_alloc(vec![0u8; 16]), "[1, 2]").unwrap();
+        assert_eq!(vec, [1, 2]);
+    }
+}
+
+#[cfg(feature = "test-faults")]
+mod test_faults {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use vec_utils::{zip_with, Faults, InjectedFault, LeakGuard, VecExt};
+
+    fn inject<R>(faults: Faults, f: impl FnOnce() -> R) -> InjectedFault {
+        let payload = match catch_unwind(AssertUnwindSafe(|| faults.inject(f))) {
+            Ok(_) => panic!("no fault was injected"),
+            Err(payload) => payload,
+        };
+
+        *payload.downcast::<InjectedFault>().unwrap()
+    }
+
+    fn closure_panic(n: usize) -> Faults {
+        Faults {
+            closure_panic: Some(n),
+            ..Faults::default()
+        }
+    }
+
+    #[test]
+    fn closure_panic_map() {
+        let guard = LeakGuard::new();
+
+        for n in 0..4 {
+            let vec = (0..4).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let fault = inject(closure_panic(n), || vec.map(|x| guard.track(*x as u32)));
+            assert_eq!(fault, InjectedFault::Closure { index: n });
+            guard.assert_balanced();
+
+            let vec = (0..4).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let fault = inject(closure_panic(n), || vec.map(|x| *x as u64));
+            assert_eq!(fault, InjectedFault::Closure { index: n });
+            guard.assert_balanced();
+        }
+    }
+
+    #[test]
+    fn closure_panic_zip() {
+        let guard = LeakGuard::new();
+
+        for n in 0..3 {
+            let a = (0..4).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let b = (0..3).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let fault = inject(closure_panic(n), || a.zip_with(b, |a, _| a));
+            assert_eq!(fault, InjectedFault::Closure { index: n });
+            guard.assert_balanced();
+
+            let a = (0..4).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let b = (0..3).map(|x| guard.track(x as u8)).collect::<Vec<_>>();
+            let c = (0..5).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let fault = inject(closure_panic(n), || {
+                let vec: Vec<_> = zip_with!((a, b, c), |a, _b, _c| a);
+                vec
+            });
+            assert_eq!(fault, InjectedFault::Closure { index: n });
+            guard.assert_balanced();
+        }
+    }
+
+    #[test]
+    fn drop_panic() {
+        let guard = LeakGuard::new();
+
+        for n in 0..3 {
+            let faults = Faults {
+                drop_panic: Some(n),
+                ..Faults::default()
+            };
+
+            let vec = (0..5).map(|x| guard.track(x)).collect::<Vec<_>>();
+            let fault = inject(faults, || {
+                vec.try_map(|x| if *x < 2 { Ok(x) } else { Err(()) })
+            });
+            assert_eq!(fault, InjectedFault::Drop { index: n });
+            guard.assert_balanced();
+        }
+    }
+
+    #[test]
+    fn restore() {
+        let outer = closure_panic(1);
+
+        let fault = inject(outer, || {
+            let vec = Faults::default().inject(|| vec![1, 2, 3].map(|x| x + 1));
+            assert_eq!(vec, [2, 3, 4]);
+
+            vec![1, 2, 3].map(|x| x * 2)
+        });
+        assert_eq!(fault, InjectedFault::Closure { index: 1 });
+
+        assert_eq!(vec![1, 2, 3].map(|x| x * 2), [2, 4, 6]);
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod tracing {
+    use std::fmt::Debug;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+    use vec_utils::VecExt;
+
+    /// Records the name and fields of each span and event as a line of text
+    #[derive(Default, Clone)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    struct Line<'a>(&'a mut String);
+
+    impl Visit for Line<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Recorder {
+        fn push(&self, name: &str, f: impl FnOnce(&mut Line<'_>)) {
+            let mut line = name.to_string();
+            f(&mut Line(&mut line));
+            self.lines.lock().unwrap().push(line);
+        }
+
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.lines.lock().unwrap())
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.push(span.metadata().name(), |line| span.record(line));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            self.push("record", |line| values.record(line));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            self.push("event", |line| event.record(line));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn reuse_spans() {
+        let recorder = Recorder::default();
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let vec = Vec::<u32>::with_capacity(4);
+            vec.map(|x| x as f32);
+            assert_eq!(
+                recorder.take(),
+                [
+                    "map len=0",
+                    "record capacities=[4]",
+                    "record reused=Some(0)",
+                    "record capacity=4",
+                    "record rejections=[]",
+                ]
+            );
+
+            // the output is allocated
+            vec![1u32, 2, 3].map(u64::from);
+            let lines = recorder.take();
+            assert_eq!(
+                lines[..4],
+                [
+                    "map len=3",
+                    "record capacities=[3]",
+                    "record reused=None",
+                    "record capacity=3",
+                ]
+            );
+            assert!(lines[4].starts_with("record rejections=[(0, LayoutMismatch {"));
+            assert_eq!(lines[5], "event message=fallback capacity=3 recycled=false");
+            assert_eq!(lines.len(), 6);
+
+            vec![1u8, 2].zip_with(vec![1u32; 4], |x, y| u32::from(x) + y);
+            let lines = recorder.take();
+            assert_eq!(
+                lines[..4],
+                [
+                    "zip_with len=2",
+                    "record capacities=[2, 4]",
+                    "record reused=Some(1)",
+                    "record capacity=4",
+                ]
+            );
+            assert!(lines[4].starts_with("record rejections=[(0, LayoutMismatch {"));
+            assert_eq!(lines.len(), 5);
+
+            vec![1u32, 2, 3]
+                .into_reuse_iter()
+                .with_capacity_hint(2)
+                .map(|x| x as i32)
+                .collect_reuse();
+            assert_eq!(
+                recorder.take(),
+                [
+                    "collect_reuse len=2 hint=Some(2)",
+                    "record capacities=[3]",
+                    "record reused=Some(0)",
+                    "record capacity=3",
+                    "record rejections=[]",
+                ]
+            );
+        });
+    }
+}
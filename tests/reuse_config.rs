@@ -0,0 +1,172 @@
+// The reuse config is process-wide, so these tests live in their own binary
+
+use vec_utils::*;
+
+#[test]
+fn process_wide() {
+    assert_eq!(reuse_config(), ReuseConfig::default());
+
+    let config = ReuseConfig {
+        max_waste: Some(4),
+        shrink_on_finish: true,
+        fallback: FallbackStrategy::Allocate,
+        small_input: 2,
+    };
+
+    set_reuse_config(config);
+    assert_eq!(reuse_config(), config);
+
+    let mut vec = Vec::<u32>::with_capacity(16);
+    vec.extend([1, 2, 3]);
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.map(|x| x as f32);
+    assert_eq!(out, [1.0, 2.0, 3.0]);
+    assert_ne!(out.as_ptr() as usize, ptr);
+    assert_eq!(out.capacity(), 3);
+
+    let vec = vec![1u32, 2, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.into_reuse_iter().map(|x| x * 2).collect_reuse();
+    assert_eq!(out, [2, 4, 6]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    // the fallible-allocation variants allocate fallibly instead
+    let mut vec = Vec::<u32>::with_capacity(16);
+    vec.extend([1, 2, 3]);
+    let ptr = vec.as_ptr() as usize;
+
+    let plan = ReusePlan::for_map::<_, f32>(&vec);
+    let rejection = Rejection::Config {
+        len: 3,
+        capacity: 16,
+    };
+    assert_eq!(plan.reused(), None);
+    assert_eq!(plan.rejections(), [(0, rejection)]);
+
+    let out = vec.try_map_falloc(|x| x as f32).unwrap();
+    assert_eq!(out, [1.0, 2.0, 3.0]);
+    assert_ne!(out.as_ptr() as usize, ptr);
+
+    let out = vec![1u32].try_zip_with_falloc(vec![2u32], |x, y| x + y);
+    assert_eq!(out, Ok(vec![3]));
+
+    // the allocation that isn't reused is handed back
+    let mut vec = Vec::<u32>::with_capacity(16);
+    vec.extend([1, 2, 3]);
+    let ptr = vec.as_ptr() as usize;
+
+    let (out, spare) = vec.map_recycle(|x| x as f32);
+    assert_eq!(out, [1.0, 2.0, 3.0]);
+    let spare = spare.unwrap().into_vec::<u32>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+
+    let mut other = Vec::<u32>::with_capacity(32);
+    other.extend([1, 2]);
+    let ptr = other.as_ptr() as usize;
+
+    let mut vec = Vec::<u32>::with_capacity(16);
+    vec.extend([1, 2, 3]);
+
+    let plan = ReusePlan::for_zip::<_, _, u32>(&vec, &other);
+    assert_eq!(plan.reused(), None);
+
+    let (out, spare) = vec.zip_with_recycle(other, |x, y| x + y);
+    assert_eq!(out, [2, 4]);
+    let spare = spare.unwrap().into_vec::<u32>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+
+    // the macros respect the config too
+    set_reuse_config(ReuseConfig {
+        fallback: FallbackStrategy::Allocate,
+        small_input: 4,
+        ..ReuseConfig::default()
+    });
+
+    let binned = Vec::<u32>::with_capacity(3);
+    let binned_ptr = binned.as_ptr() as usize;
+    RecycleBin::with_local(|bin| bin.deposit(binned));
+
+    let a = vec![1u32, 2, 3];
+    let b = vec![4u32, 5, 6];
+    let (a_ptr, b_ptr) = (a.as_ptr() as usize, b.as_ptr() as usize);
+
+    let out = zip_with!((a, b), |x, y| x + y);
+    assert_eq!(out, [5, 7, 9]);
+    let out_ptr = out.as_ptr() as usize;
+    assert!(![a_ptr, b_ptr, binned_ptr].contains(&out_ptr));
+
+    let (a, b) = (vec![1u32, 2, 3], vec![4u32]);
+    let out: Result<Vec<u32>, ()> = try_zip_with!((a, b), |x, y| Ok(x + y));
+    assert_eq!(out, Ok(vec![5]));
+    assert_eq!(RecycleBin::with_local(|bin| bin.len()), 1);
+
+    // inputs that are long enough are still reused
+    let (a, b) = (vec![1u32, 2, 3, 4], vec![1u32; 4]);
+    let ptr = a.as_ptr() as usize;
+
+    let out = zip_with!((a, b), |x, y| x + y);
+    assert_eq!(out, [2, 3, 4, 5]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let vec = vec![1u32, 2, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.flat_map_reuse(|x| [x, x]);
+    assert_eq!(out, [1, 1, 2, 2, 3, 3]);
+    assert_ne!(out.as_ptr() as usize, ptr);
+    assert_eq!(RecycleBin::with_local(|bin| bin.len()), 1);
+
+    let vec = vec![1u32, 2, 3, 4];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.flat_map_reuse(|x| Some(x).filter(|x| x % 2 == 0));
+    assert_eq!(out, [2, 4]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    RecycleBin::with_local(RecycleBin::clear);
+
+    set_reuse_config(ReuseConfig::default());
+    assert_eq!(reuse_config(), ReuseConfig::default());
+}
+
+#[test]
+fn filter_map_with_config() {
+    let config = ReuseConfig {
+        max_waste: Some(4),
+        ..ReuseConfig::default()
+    };
+
+    let mut vec = Vec::<u32>::with_capacity(16);
+    vec.extend([1, 2, 3, 4, 5, 6]);
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.try_filter_map_with_config(config, |x| {
+        Ok::<_, ()>(Some(x as f32).filter(|_| x % 2 == 0))
+    });
+    let out = out.unwrap();
+    assert_eq!(out, [2.0, 4.0, 6.0]);
+    assert_ne!(out.as_ptr() as usize, ptr);
+
+    let mut vec = Vec::<u32>::with_capacity(8);
+    vec.extend([1, 2, 3, 4, 5, 6]);
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec
+        .try_filter_map_with_config(config, |x| {
+            Ok::<_, ()>(Some(x as f32).filter(|_| x % 2 == 0))
+        })
+        .unwrap();
+    assert_eq!(out, [2.0, 4.0, 6.0]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+    assert_eq!(out.capacity(), 8);
+
+    let config = ReuseConfig {
+        shrink_on_finish: true,
+        ..config
+    };
+
+    let out = out.try_filter_map_with_config(config, |x| Ok::<_, ()>(Some(x as u32)));
+    assert_eq!(out.map(|out| out.capacity()), Ok(3));
+}
@@ -18,6 +18,12 @@ mod drop_counter {
         }
     }
 
+    impl<'a, T: Debug + Any + PartialEq> PartialEq for OnDrop<'a, T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.3 == other.3
+        }
+    }
+
     impl DropCounter {
         pub fn new() -> Self {
             DropCounter(RwLock::default())
@@ -36,7 +42,7 @@ mod drop_counter {
 
         pub fn create<T: Debug + Any>(&self, value: T) -> OnDrop<'_, T> {
             let len = self.init();
-            OnDrop(&self, len, TypeId::of::<T>(), value)
+            OnDrop(self, len, TypeId::of::<T>(), value)
         }
     }
 
@@ -431,7 +437,1807 @@ mod tuple {
 
         unsafe {
             assert!(!DOUBLE_DROP);
-            assert_eq!(DROP_COUNT, 0);
+            let drop_count = DROP_COUNT;
+            assert_eq!(drop_count, 0);
+        }
+    }
+}
+
+mod raw_parts {
+    use super::*;
+
+    #[test]
+    fn boxed_slice_try_map() {
+        let dr = DropCounter::new();
+
+        let bx = (0..10).map(|x| dr.create(x)).collect::<Box<[_]>>();
+
+        let mut counter = 0;
+
+        let err = bx.try_map(|x| {
+            counter += 1;
+
+            if counter == 3 {
+                None
+            } else {
+                Some(dr.create(*x.get() as f32))
+            }
+        });
+
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn boxed_slice_round_trip() {
+        let dr = DropCounter::new();
+
+        let mut vec = Vec::with_capacity(20);
+        vec.extend((0..10).map(|x| dr.create(x)));
+
+        let bx = Box::<[_]>::from_vec(vec);
+
+        assert_eq!(bx.len(), 10);
+
+        drop(bx.into_vec());
+    }
+}
+
+mod unbox {
+    use super::*;
+
+    #[test]
+    fn map_unbox() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| Box::new(dr.create(x))).collect::<Vec<_>>();
+
+        vec.map_unbox(|x| Box::new(dr.create(*x.get())));
+    }
+
+    #[test]
+    fn try_map_unbox() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| Box::new(dr.create(x))).collect::<Vec<_>>();
+
+        let mut counter = 0;
+
+        let err = vec.try_map_unbox(|x| {
+            counter += 1;
+
+            if counter == 3 {
+                None
+            } else {
+                Some(x)
+            }
+        });
+
+        assert!(err.is_err());
+    }
+}
+
+mod flatten {
+    use super::*;
+
+    #[test]
+    fn flatten_reuse() {
+        let dr = DropCounter::new();
+
+        let mut big = Vec::with_capacity(32);
+        big.extend((10..15).map(|x| dr.create(x)));
+
+        let vec = vec![
+            (0..5).map(|x| dr.create(x)).collect::<Vec<_>>(),
+            big,
+            (20..25).map(|x| dr.create(x)).collect::<Vec<_>>(),
+        ];
+
+        let vec = vec.flatten_reuse();
+
+        assert_eq!(vec.len(), 15);
+        assert_eq!(*vec[5].get(), 10);
+        assert_eq!(*vec[10].get(), 20);
+    }
+}
+
+mod in_place {
+    use super::*;
+
+    #[test]
+    fn map_range() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        vec.map_range(2..8, |x| dr.create(*x.get() * 2));
+
+        assert_eq!(*vec[3].get(), 6);
+    }
+
+    #[test]
+    fn map_range_panic() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_range(2..8, |x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() * 2)
+            })
+        }));
+
+        assert!(res.is_err());
+        assert_eq!(vec.len(), 9);
+        assert_eq!(*vec[4].get(), 8);
+        assert_eq!(*vec[5].get(), 6);
+    }
+}
+
+mod splice {
+    use super::*;
+
+    #[test]
+    fn splice_map() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let replacement = (20..30).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let vec = vec.splice_map(2..4, replacement, |x| dr.create(*x.get() * 2));
+
+        assert_eq!(vec.len(), 18);
+        assert_eq!(*vec[2].get(), 20);
+        assert_eq!(*vec[12].get(), 8);
+    }
+
+    #[test]
+    fn splice_map_diff() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let replacement = (20..30).map(|x| dr.create(x as f32)).collect::<Vec<_>>();
+
+        let vec = vec.splice_map(2..4, replacement, |x| dr.create(*x.get() as f32));
+
+        assert_eq!(vec.len(), 18);
+    }
+
+    #[test]
+    fn splice_map_panic() {
+        // panic while mapping the prefix, and while mapping the tail
+        for &(start, end, panic_at) in &[(2, 4, 1), (2, 4, 7), (2, 8, 9), (4, 5, 0)] {
+            let dr = DropCounter::new();
+
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let replacement = (20..23).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.splice_map(start..end, replacement, |x| {
+                    if *x.get() == panic_at {
+                        panic!()
+                    }
+
+                    dr.create(*x.get() * 2)
+                })
+            }));
+
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn splice_map_grow() {
+        let dr = DropCounter::new();
+
+        let vec = (0..4).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let replacement = (20..30).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let vec = vec.splice_map(1..2, replacement, |x| dr.create(*x.get() * 2));
+
+        let values = vec.iter().map(|x| *x.get()).collect::<Vec<_>>();
+        assert_eq!(values, [0, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 4, 6]);
+    }
+}
+
+mod retain {
+    use super::*;
+
+    #[test]
+    fn retain_map() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        vec.retain_map(|x| {
+            if x.get() % 2 == 0 {
+                Some(dr.create(x.get() * 2))
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(vec.len(), 5);
+    }
+
+    #[test]
+    fn retain_map_panic() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.retain_map(|x| match x.get() {
+                7 => panic!(),
+                x if x % 2 == 0 => None,
+                _ => Some(x),
+            })
+        }));
+
+        assert!(res.is_err());
+
+        let values = vec.iter().map(|x| *x.get()).collect::<Vec<_>>();
+        assert_eq!(values, [1, 3, 5, 8, 9]);
+    }
+}
+
+mod reuse_iter {
+    use super::*;
+
+    #[test]
+    fn collect_reuse() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let out = vec
+            .into_reuse_iter()
+            .filter(|x| x.get() % 2 == 0)
+            .zip(0..3)
+            .map(|(x, _)| x)
+            .collect_reuse();
+
+        assert_eq!(out.len(), 3);
+    }
+
+    #[test]
+    fn collect_reuse_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.into_reuse_iter()
+                .map(|x| {
+                    if *x.get() == 5 {
+                        panic!()
+                    }
+
+                    dr.create(*x.get() + 1)
+                })
+                .collect_reuse()
+        }));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn try_collect_reuse_err() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let res = vec
+            .into_reuse_iter()
+            .map(|x| if *x.get() == 5 { Err(*x.get()) } else { Ok(x) })
+            .map_err(|x| x * 2)
+            .try_collect_reuse();
+
+        assert_eq!(res.err(), Some(10));
+    }
+
+    #[test]
+    fn zip_eq() {
+        let dr = DropCounter::new();
+
+        for &len in &[3, 10, 12] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let other = (0..len).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let res = vec
+                .into_reuse_iter()
+                .zip_eq(other)
+                .map(|x| x.map(|(x, _)| x))
+                .try_collect_reuse();
+
+            assert_eq!(res.is_ok(), len == 10);
+        }
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let out = vec
+            .into_reuse_iter()
+            .zip_eq(0..12)
+            .or_else(|_| Ok::<_, ()>((dr.create(10), 0)))
+            .map(|x| x.map(|(x, _)| x))
+            .try_collect_reuse()
+            .ok()
+            .unwrap();
+
+        assert_eq!(out.len(), 11);
+    }
+
+    #[test]
+    fn reductions() {
+        let dr = DropCounter::new();
+
+        let sum = (0..10)
+            .map(|x| dr.create(x))
+            .collect::<Vec<_>>()
+            .into_reuse_iter()
+            .map(|x| *x.get())
+            .sum::<i32>();
+        assert_eq!(sum, 45);
+
+        let max = (0..10)
+            .map(|x| dr.create(x))
+            .collect::<Vec<_>>()
+            .into_reuse_iter()
+            .filter(|x| x.get() % 3 == 0)
+            .max_by_key(|x| *x.get());
+        assert_eq!(max.map(|x| *x.get()), Some(9));
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            (0..10)
+                .map(|x| dr.create(x))
+                .collect::<Vec<_>>()
+                .into_reuse_iter()
+                .reduce(|a, b| {
+                    if *b.get() == 5 {
+                        panic!()
+                    }
+
+                    dr.create(a.get() + b.get())
+                })
+        }));
+        assert!(res.is_err());
+
+        // stops early, so the rest of the elements are dropped with the pipeline
+        let found = (0..10)
+            .map(|x| dr.create(x))
+            .collect::<Vec<_>>()
+            .into_reuse_iter()
+            .any(|x| *x.get() == 3);
+        assert!(found);
+    }
+
+    #[test]
+    fn try_for_each() {
+        let dr = DropCounter::new();
+
+        let mut sink = Vec::new();
+
+        let res = (0..10)
+            .map(|x| dr.create(x))
+            .collect::<Vec<_>>()
+            .into_reuse_iter()
+            .try_for_each(|x| {
+                if *x.get() == 4 {
+                    return Err(*x.get());
+                }
+
+                sink.push(x);
+                Ok(())
+            });
+
+        assert_eq!(res, Err(4));
+        assert_eq!(sink.len(), 4);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            (0..10)
+                .map(|x| dr.create(x))
+                .collect::<Vec<_>>()
+                .into_reuse_iter()
+                .for_each(|x| {
+                    if *x.get() == 4 {
+                        panic!()
+                    }
+                })
+        }));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn search() {
+        let dr = DropCounter::new();
+
+        let new = || {
+            (0..10)
+                .map(|x| dr.create(x))
+                .collect::<Vec<_>>()
+                .into_reuse_iter()
+        };
+
+        let found = new().find(|x| *x.get() == 3);
+        assert_eq!(found.map(|x| *x.get()), Some(3));
+
+        let pos = new()
+            .filter(|x| x.get() % 2 == 1)
+            .position(|x| *x.get() == 5);
+        assert_eq!(pos, Some(2));
+
+        assert!(!new().all(|x| *x.get() < 5));
+
+        // the rest of the pipeline is still collected into the allocation
+        let mut iter = new().map(|x| dr.create(x.get() * 2));
+        let found = iter.by_ref().find(|x| *x.get() == 6);
+        assert_eq!(found.map(|x| *x.get()), Some(6));
+
+        let rest = iter.collect_reuse();
+        assert_eq!(rest.len(), 6);
+        assert_eq!(*rest[0].get(), 8);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            new().any(|x| {
+                if *x.get() == 4 {
+                    panic!()
+                }
+
+                false
+            })
+        }));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn partition_result() {
+        let dr = DropCounter::new();
+
+        let new = || {
+            (0..10)
+                .map(|x| dr.create(x))
+                .collect::<Vec<_>>()
+                .into_reuse_iter()
+        };
+
+        let (values, errors) = new()
+            .map(|x| if x.get() % 3 == 0 { Ok(x) } else { Err(x) })
+            .partition_result();
+        assert_eq!((values.len(), errors.len()), (4, 6));
+
+        let (values, errors) = new()
+            .map(|x| {
+                if x.get() % 3 == 0 {
+                    Ok(*x.get())
+                } else {
+                    Err(x)
+                }
+            })
+            .partition_result();
+        assert_eq!((values.len(), errors.len()), (4, 6));
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            new()
+                .map(|x| match *x.get() {
+                    7 => panic!(),
+                    n if n % 2 == 0 => Ok(x),
+                    n => Err(n),
+                })
+                .partition_result()
+        }));
+        assert!(res.is_err());
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            new()
+                .map(|x| match *x.get() {
+                    7 => panic!(),
+                    n if n % 2 == 0 => Ok(n),
+                    _ => Err(x),
+                })
+                .partition_result()
+        }));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn split_tail() {
+        let dr = DropCounter::new();
+
+        let new = || {
+            (0..10)
+                .map(|x| dr.create(x))
+                .collect::<Vec<_>>()
+                .into_reuse_iter()
+        };
+
+        let mut iter = new();
+        iter.next();
+        let mut tail = iter.split_tail(4);
+        assert_eq!(tail.len(), 5);
+        assert_eq!(tail.next().map(|x| *x.get()), Some(5));
+        drop(tail);
+
+        let head = iter.map(|x| dr.create(x.get() * 2)).collect_reuse();
+        assert_eq!(head.len(), 4);
+
+        let mut iter = new();
+        drop(iter.split_tail(3));
+        drop(iter);
+
+        let mut iter = new();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            iter.split_tail(5).for_each(|x| {
+                if *x.get() == 7 {
+                    panic!()
+                }
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(iter.count(), 5);
+    }
+
+    #[test]
+    fn dyn_pipeline() {
+        let dr = DropCounter::new();
+
+        let mut pipeline = DynPipeline::new()
+            .filter(|x: &drop_counter::OnDrop<'_, i32>| *x.get() != 2)
+            .zip((0..).map(|x| dr.create(x)))
+            .map(|(x, y)| {
+                if *x.get() == 7 {
+                    panic!()
+                }
+
+                dr.create(x.get() + y.get())
+            });
+
+        let out = pipeline.run((0..5).map(|x| dr.create(x)).collect());
+        assert_eq!(out.len(), 4);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pipeline.run((5..10).map(|x| dr.create(x)).collect())
+        }));
+        assert!(res.is_err());
+
+        let mut pipeline = DynPipeline::new().zip(0..2);
+        let out = pipeline.run((0..5).map(|x| dr.create(x)).collect());
+        assert_eq!(out.len(), 2);
+    }
+
+    #[test]
+    fn partial() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let mut iter = vec.into_reuse_iter();
+        iter.next();
+        iter.next();
+    }
+}
+
+mod recycle {
+    use super::*;
+
+    #[test]
+    fn deposit() {
+        let dr = DropCounter::new();
+
+        let mut bin = RecycleBin::new();
+
+        bin.deposit((0..10).map(|x| dr.create(x)).collect::<Vec<_>>());
+
+        let mut vec = bin.take(10).unwrap();
+        vec.extend((0..10).map(|x| dr.create(x)));
+    }
+
+    #[test]
+    fn retire_in_destructor() {
+        struct RetireOnDrop(Vec<u64>);
+
+        impl Drop for RetireOnDrop {
+            fn drop(&mut self) {
+                std::mem::take(&mut self.0).retire();
+            }
+        }
+
+        let vec = (0..4).map(|x| RetireOnDrop(vec![x; 4])).collect::<Vec<_>>();
+        vec.retire();
+
+        RecycleBin::with_local(|bin| assert_eq!(bin.len(), 5));
+        RecycleBin::with_local(RecycleBin::clear);
+    }
+}
+
+mod spare {
+    use super::*;
+
+    #[test]
+    fn spare_writer() {
+        let dr = DropCounter::new();
+
+        let mut vec = Vec::with_capacity(10);
+        vec.push(dr.create(0));
+
+        let mut writer = vec.spare_writer();
+        writer.extend((1..5).map(|x| dr.create(x)));
+        writer.commit();
+
+        let mut writer = vec.spare_writer();
+        writer.extend((5..8).map(|x| dr.create(x)));
+        drop(writer);
+
+        assert_eq!(vec.len(), 5);
+    }
+}
+
+mod uninit {
+    use super::*;
+
+    #[test]
+    fn partial_init_guard() {
+        let dr = DropCounter::new();
+
+        let mut guard = PartialInitGuard::new(alloc_uninit_vec(10));
+        for i in 0..4 {
+            guard.push(dr.create(i));
+        }
+        drop(guard);
+
+        let mut guard = PartialInitGuard::new(alloc_uninit_vec(3));
+        for i in 0..3 {
+            guard.push(dr.create(i));
+        }
+        assert_eq!(guard.finish().len(), 3);
+    }
+}
+
+mod columns {
+    use super::*;
+
+    #[test]
+    fn split_and_gather() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10)
+            .map(|x| (dr.create(x), dr.create(x as f32)))
+            .collect::<Vec<_>>();
+
+        let (a, b) = <(_, _)>::into_columns(vec);
+        let mut a = a;
+        a.reserve(10);
+
+        let vec = <(_, _)>::from_columns((a, b));
+
+        assert_eq!(vec.len(), 10);
+    }
+}
+
+mod transpose {
+    use super::*;
+
+    #[test]
+    fn transpose() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..35).map(|x| dr.create(x)).collect::<Vec<_>>();
+        vec.transpose_in_place(5, 7);
+
+        let mut scratch = vec![dr.create(100)];
+        vec.transpose_with(7, 5, &mut scratch);
+
+        assert_eq!(
+            vec.iter().map(|x| *x.get()).collect::<Vec<_>>(),
+            (0..35).collect::<Vec<_>>()
+        );
+    }
+}
+
+mod interleave {
+    use super::*;
+
+    #[test]
+    fn interleave() {
+        let dr = DropCounter::new();
+
+        let a = (0..4).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let mut b = (0..7).map(|x| dr.create(x)).collect::<Vec<_>>();
+        b.reserve(4);
+
+        let c = a.interleave(b);
+        let d = (0..3).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        assert_eq!(c.interleave(d).len(), 14);
+    }
+}
+
+mod exact {
+    use super::*;
+
+    #[test]
+    fn map_into_boxed_slice() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        vec.reserve(10);
+
+        let slice = vec.map_into_boxed_slice(|x| dr.create(*x.get() as u32));
+
+        assert_eq!(slice.len(), 10);
+    }
+
+    #[test]
+    fn map_into_boxed_slice_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_into_boxed_slice(|x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u32)
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+    #[test]
+    fn map_rev_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_rev(|x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u32)
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_rev(|x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u8)
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn zip_with_rev_panic() {
+        let dr = DropCounter::new();
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..7).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.zip_with_rev(b, |x, y| {
+                if *y.get() == 3 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u32)
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let a = (0..7).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+        let b = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = a.zip_with_rev(b, |x, y| dr.create((*x.get(), *y.get())));
+
+        assert_eq!(vec.len(), 7);
+    }
+
+    #[test]
+    fn take_map_panic() {
+        let dr = DropCounter::new();
+
+        for &n in &[0, 3, 7, 10, 20] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let (head, rest) = vec.take_map(n, |x| dr.create(*x.get() as u32));
+            assert_eq!(head.len() + rest.len(), 10);
+
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let (head, rest) = vec.take_map(n, |x| dr.create(*x.get() as u8));
+            assert_eq!(head.len() + rest.len(), 10);
+        }
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.take_map(7, |x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u8)
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn resumable_map() {
+        let dr = DropCounter::new();
+
+        // dropped while suspended
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let mut map = ResumableMap::new(vec, |x| dr.create(*x.get() as u32));
+        assert!(map.run_for(4).is_continue());
+        drop(map);
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let mut map = ResumableMap::new(vec, |x| dr.create(*x.get() as u8));
+        assert!(map.run_for(4).is_continue());
+        drop(map);
+
+        // panics in the middle of a run
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let mut map = ResumableMap::new(vec, |x| {
+            if *x.get() == 5 {
+                panic!()
+            }
+
+            dr.create(*x.get() as u32)
+        });
+        assert!(map.run_for(3).is_continue());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.run_for(5)));
+
+        assert!(result.is_err());
+        drop(map);
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let map = ResumableMap::new(vec, |x| dr.create(*x.get() as u32));
+        assert_eq!(map.finish().len(), 10);
+    }
+
+    #[test]
+    fn resumable_map_resume_after_panic() {
+        let dr = DropCounter::new();
+
+        for &n in &[0, 1, 9] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let mut map = ResumableMap::new(vec, |x| {
+                if *x.get() == n {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u32)
+            });
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.run_for(20)));
+            assert!(result.is_err());
+            assert_eq!(map.remaining(), 0);
+
+            // the map is poisoned, so it can't be resumed
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.run_for(1)));
+            assert!(result.is_err());
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.finish()));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn zip_with_threads() {
+        let dr = DropCounter::new();
+
+        let a = (0..20).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..25).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+        let vec = a.zip_with_threads(b, |x, y| dr.create(*x.get() + i32::from(*y.get())), 3);
+
+        assert_eq!(vec.len(), 20);
+
+        let a = (0..20).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..25).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.zip_with_threads(
+                b,
+                |x, y| {
+                    if *x.get() == 13 {
+                        panic!()
+                    }
+
+                    dr.create((*x.get(), *y.get()))
+                },
+                4,
+            )
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn zip_longest_with() {
+        use vec_utils::EitherOrBoth;
+
+        let dr = DropCounter::new();
+
+        let a = (0..7).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..10).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+        let vec = a.zip_longest_with(b, |x| dr.create(x.right().map(|y| *y.get())));
+
+        assert_eq!(vec.len(), 10);
+
+        for &n in &[2, 8] {
+            let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let b = (0..5).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                a.zip_longest_with(b, |x| {
+                    if let EitherOrBoth::Left(x) | EitherOrBoth::Both(x, _) = &x {
+                        if *x.get() == n {
+                            panic!()
+                        }
+                    }
+
+                    dr.create(x.left().map(|x| *x.get() as u32))
+                })
+            }));
+
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn merge_with_panic() {
+        let dr = DropCounter::new();
+
+        for &(a_cap, b_cap) in &[(0, 0), (20, 0), (0, 20)] {
+            let mut a = Vec::with_capacity(a_cap);
+            a.extend((0..10).map(|x| dr.create(x * 2)));
+            let mut b = Vec::with_capacity(b_cap);
+            b.extend((0..7).map(|x| dr.create(x * 3)));
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                a.merge_with(b, |x, y| {
+                    if *x.get() == 10 {
+                        panic!()
+                    }
+
+                    x.get().cmp(y.get())
+                })
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..7).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = a.merge_by_key(b, |x| *x.get());
+
+        assert_eq!(vec.len(), 17);
+    }
+
+    #[test]
+    fn intersperse_map_panic() {
+        let dr = DropCounter::new();
+
+        for &n in &[0, 4, 9] {
+            let vec = (0..10).map(|x| dr.create((x, x))).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.intersperse_map(
+                    || dr.create(-1),
+                    |x| {
+                        if x.get().0 == n {
+                            panic!()
+                        }
+
+                        dr.create(x.get().0)
+                    },
+                )
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let mut count = 0;
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.intersperse_map(
+                || {
+                    count += 1;
+                    if count == 3 {
+                        panic!()
+                    }
+
+                    dr.create(-1)
+                },
+                |x| dr.create(*x.get()),
+            )
+        }));
+
+        assert!(result.is_err());
+
+        let vec = (0..10).map(|x| dr.create((x, x))).collect::<Vec<_>>();
+        let vec = vec.intersperse_map(|| dr.create(-1), |x| dr.create(x.get().0));
+        assert_eq!(vec.len(), 19);
+    }
+
+    #[test]
+    fn zip_column_with_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let other = (0..4).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.zip_column_with(3, 1, other, |x, y| {
+                if *y.get() == 2 {
+                    panic!()
+                }
+
+                dr.create(*x.get() * 2)
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec.map_column(4, 3, |x| dr.create(*x.get() + 1));
+        assert_eq!(vec.len(), 10);
+    }
+
+    #[test]
+    fn gather_map_panic() {
+        let dr = DropCounter::new();
+        let indices = [7, 2, 9, 1, 0, 3, 4];
+
+        for &n in &[7, 9, 0, 4] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.gather_map(&indices, |x| {
+                    if *x.get() == n {
+                        panic!()
+                    }
+
+                    dr.create(*x.get() as u32)
+                })
+            }));
+
+            assert!(result.is_err());
+
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.gather_map(&indices, |x| {
+                    if *x.get() == n {
+                        panic!()
+                    }
+
+                    dr.create(*x.get() as u8)
+                })
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec
+            .gather_map(&indices, |x| dr.create(*x.get() as u32))
+            .unwrap();
+        assert_eq!(vec.len(), 7);
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        assert!(vec.gather_map(&[1, 1], |x| x).is_err());
+    }
+
+    #[test]
+    fn join_with_panic() {
+        let dr = DropCounter::new();
+
+        for &n in &[0, 2, 4] {
+            let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let b = (0..12)
+                .rev()
+                .map(|x| dr.create(x as u8))
+                .collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                a.join_with(
+                    b,
+                    |x| *x.get() % 5,
+                    |y| i32::from(*y.get()),
+                    |x, y| {
+                        if *x.get() == n {
+                            panic!()
+                        }
+
+                        dr.create((*x.get(), *y.get()))
+                    },
+                )
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..6).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+        let vec = a.join_with(
+            b,
+            |x| *x.get(),
+            |y| i32::from(*y.get()),
+            |x, _| dr.create(*x.get() as u32),
+        );
+        assert_eq!(vec.len(), 6);
+    }
+
+    #[test]
+    fn cartesian_with_panic() {
+        let dr = DropCounter::new();
+
+        for &(a_cap, b_cap) in &[(0, 0), (20, 0), (0, 20)] {
+            for &n in &[0, 2, 4] {
+                let mut a = Vec::with_capacity(a_cap);
+                a.extend((0..5).map(|x| dr.create(x)));
+                let mut b = Vec::with_capacity(b_cap);
+                b.extend((0..4).map(|x| dr.create(x)));
+
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    a.cartesian_with(b, |x, y| {
+                        if *x.get() == n && *y.get() == 1 {
+                            panic!()
+                        }
+
+                        dr.create(x.get() * y.get())
+                    })
+                }));
+
+                assert!(result.is_err());
+            }
+
+            let mut a = Vec::with_capacity(a_cap);
+            a.extend((0..5).map(|x| dr.create(x)));
+            let mut b = Vec::with_capacity(b_cap);
+            b.extend((0..4).map(|x| dr.create(x)));
+
+            let vec = a.cartesian_with(b, |x, y| dr.create(x.get() * y.get()));
+            assert_eq!(vec.len(), 20);
+        }
+    }
+
+    #[test]
+    fn rle_panic() {
+        use vec_utils::VecRunsExt;
+
+        let dr = DropCounter::new();
+
+        let vec = [1, 1, 1, 1, 2, 2, 3, 4, 4, 4, 4, 4]
+            .iter()
+            .map(|&x| dr.create(x))
+            .collect::<Vec<_>>();
+        let runs = vec.rle_encode();
+        assert_eq!(runs.len(), 4);
+
+        let vec = runs.rle_decode();
+        assert_eq!(vec.len(), 12);
+
+        // panics when cloned or compared if the flag is set
+        #[derive(Debug)]
+        struct Bomb(u32, bool);
+
+        impl Clone for Bomb {
+            fn clone(&self) -> Self {
+                assert!(!self.1);
+                Bomb(self.0, false)
+            }
+        }
+
+        impl PartialEq for Bomb {
+            fn eq(&self, other: &Self) -> bool {
+                assert!(!self.1 && !other.1);
+                self.0 == other.0
+            }
+        }
+
+        for &capacity in &[3, 20] {
+            let mut runs = Vec::with_capacity(capacity);
+            runs.push((dr.create(Bomb(1, true)), 3));
+            runs.push((dr.create(Bomb(2, false)), 0));
+            runs.push((dr.create(Bomb(3, false)), 4));
+
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| runs.rle_decode()));
+            assert!(result.is_err());
+        }
+
+        for &(n, bomb) in &[(1, 5), (12, 7)] {
+            let vec = (0..n)
+                .chain(0..12)
+                .map(|x| dr.create(Bomb(x / 3, x == bomb)))
+                .collect::<Vec<_>>();
+
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| vec.rle_encode()));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn reduce_pairs_panic() {
+        let dr = DropCounter::new();
+
+        for &len in &[0usize, 1, 9, 10] {
+            let vec = (0..len).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let vec = vec.reduce_pairs(OddPolicy::Keep, |a, b| dr.create(a.get() + b.get()));
+            assert_eq!(vec.len(), len.div_ceil(2));
+
+            let vec = (0..len).map(|x| dr.create(x)).collect::<Vec<_>>();
+            let vec = vec.reduce_pairs(OddPolicy::Drop, |a, b| dr.create(a.get() + b.get()));
+            assert_eq!(vec.len(), len / 2);
+        }
+
+        let vec = (0..9).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.reduce_pairs(OddPolicy::Drop, |a, b| {
+                if *a.get() == 4 {
+                    panic!()
+                }
+
+                dr.create(a.get() + b.get())
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn histogram_panic() {
+        let dr = DropCounter::new();
+
+        for &cap in &[10, 40] {
+            let mut vec = Vec::with_capacity(cap);
+            vec.extend((0..10).map(|x| dr.create(x)));
+            let counts = vec.histogram(5, |x| *x.get() as usize / 2);
+            assert_eq!(counts, [2; 5]);
+
+            let mut vec = Vec::with_capacity(cap);
+            vec.extend((0..10).map(|x| dr.create(x)));
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.histogram(3, |x| *x.get() as usize / 2)
+            }));
+
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn chunk_exact_map_panic() {
+        let dr = DropCounter::new();
+
+        // the chunks start at 0, 3 and 6, and the tail has 2 elements
+        for &n in &[0, 6, 2] {
+            let vec = (0..11).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.chunk_exact_map(
+                    |[a, b, c]: [_; 3]| {
+                        if *a.get() == n {
+                            panic!()
+                        }
+
+                        dr.create(a.get() + b.get() + c.get())
+                    },
+                    |tail| {
+                        if tail.len() == n as usize {
+                            panic!()
+                        }
+
+                        Some(dr.create(tail.len() as i32))
+                    },
+                )
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let vec = (0..11).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec.chunk_exact_map(|[a, _]| a, |tail| tail.into_iter().next());
+        assert_eq!(vec.len(), 6);
+    }
+
+    #[test]
+    fn group_adjacent_map_panic() {
+        let dr = DropCounter::new();
+
+        // the runs are [0, 1, 2], [3, 4, 5], [6, 7, 8], [9]
+        for &(run, partial) in &[(0, false), (2, false), (1, true), (3, true)] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.group_adjacent_map(
+                    |x| *x.get() / 3,
+                    |k, mut drain| {
+                        let first = drain.next().unwrap();
+
+                        if k == run {
+                            if partial {
+                                drop(first);
+                            }
+
+                            panic!()
+                        }
+
+                        first
+                    },
+                )
+            }));
+
+            assert!(result.is_err());
+        }
+
+        // the outputs are larger than the inputs
+        for &run in &[0, 2] {
+            let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.group_adjacent_map(
+                    |x| *x.get() / 3,
+                    |k, drain| {
+                        if k == run {
+                            panic!()
+                        }
+
+                        (drain.collect::<Vec<_>>(), k)
+                    },
+                )
+            }));
+
+            assert!(result.is_err());
+        }
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.group_adjacent_map(
+                |x| {
+                    if *x.get() == 7 {
+                        panic!()
+                    }
+
+                    *x.get() / 3
+                },
+                |_, mut drain| drain.next_back().unwrap(),
+            )
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn feed_map_panic() {
+        use vec_utils::FeedMap;
+
+        let dr = DropCounter::new();
+
+        for &n in &[1, 6] {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let mut feed = FeedMap::new(|x: drop_counter::OnDrop<'_, i32>| {
+                    if *x.get() == n {
+                        panic!()
+                    }
+
+                    dr.create(x.get() * 2)
+                });
+
+                feed.feed((0..4).map(|x| dr.create(x)).collect());
+                feed.feed((4..8).map(|x| dr.create(x)).collect());
+                feed.finish()
+            }));
+
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn reusable_mapper_panic() {
+        let dr = DropCounter::new();
+
+        let mut mapper = ReusableMapper::new();
+
+        let output = mapper.run((0..4).map(|x| dr.create(x)).collect(), |x| {
+            dr.create(*x.get() as u64)
+        });
+        mapper.recycle(output);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mapper.run((0..4).map(|x| dr.create(x)).collect(), |x| {
+                if *x.get() == 2 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u64)
+            })
+        }));
+        assert!(res.is_err());
+
+        let output = mapper.run((0..4).map(|x| dr.create(x)).collect(), |x| {
+            dr.create(*x.get() as u64)
+        });
+        assert_eq!(output.len(), 4);
+    }
+
+    #[test]
+    fn zip_with_recycle_panic() {
+        let dr = DropCounter::new();
+
+        for &(n, reuse) in &[(0, true), (2, true), (2, false), (4, false)] {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let left = (0..6).map(|x| dr.create(x)).collect::<Vec<_>>();
+                let right = (0..5).map(|x| dr.create(x as u8)).collect::<Vec<_>>();
+
+                let check = |x: &drop_counter::OnDrop<'_, i32>| {
+                    if *x.get() == n {
+                        panic!()
+                    }
+                };
+
+                if reuse {
+                    let (vec, _) = left.zip_with_recycle(right, |x, _| {
+                        check(&x);
+                        x
+                    });
+                    vec
+                } else {
+                    let (vec, _) = right.zip_with_recycle(left, |_, x| {
+                        check(&x);
+                        dr.create(*x.get() as u64)
+                    });
+                    vec.map(|x| dr.create(*x.get() as i32))
+                }
+            }));
+
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn try_filter_map_panic() {
+        let dr = DropCounter::new();
+
+        for &n in &[0, 3, 5] {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let vec = (0..6).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+                vec.try_filter_map(|x| -> Result<_, ()> {
+                    if *x.get() == n {
+                        panic!()
+                    }
+
+                    Ok(Some(x).filter(|x| *x.get() != 1))
+                })
+            }));
+
+            assert!(res.is_err());
+
+            let vec = (0..6).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+            let res = vec.try_filter_map(|x| {
+                if *x.get() == n {
+                    return Err(x);
+                }
+
+                Ok(Some(dr.create([*x.get() as u64; 4])).filter(|_| *x.get() != 1))
+            });
+
+            assert!(res.is_err());
+        }
+
+        let vec = (0..6).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec
+            .try_filter_map(|x| Ok::<_, ()>(Some(x).filter(|x| *x.get() != 1)))
+            .unwrap();
+        assert_eq!(vec.len(), 5);
+    }
+
+    #[test]
+    fn flat_map_reuse_panic() {
+        let dr = DropCounter::new();
+
+        let dr = &dr;
+
+        // panic before and after the unread input is spilled
+        for &(n, k) in &[(0, 1), (2, 1), (1, 3), (3, 3), (4, 2)] {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let vec = (0..5).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+                vec.flat_map_reuse(|x| {
+                    let value = *x.get();
+
+                    (0..k).map(move |i| {
+                        if value == n && i + 1 == k {
+                            panic!()
+                        }
+
+                        dr.create(value)
+                    })
+                })
+            }));
+
+            assert!(res.is_err());
+        }
+
+        let vec = (0..5).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec.flat_map_reuse(|x| vec![dr.create(*x.get()), x]);
+        assert_eq!(vec.len(), 10);
+    }
+
+    #[test]
+    fn map_into_shared_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_into_shared(|x| {
+                if *x.get() == 5 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as u32)
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let shared = vec.map_into_rc(|x| dr.create(*x.get() as u32));
+
+        assert_eq!(shared.len(), 10);
+    }
+    #[test]
+    fn zip_with_into_slice() {
+        let dr = DropCounter::new();
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..8).map(|x| dr.create(x as f32)).collect::<Vec<_>>();
+
+        let mut out: [std::mem::MaybeUninit<_>; 5] =
+            std::array::from_fn(|_| std::mem::MaybeUninit::uninit());
+        let written = a.zip_with_into_slice(b, &mut out, |x, y| dr.create((*x.get(), *y.get())));
+
+        assert_eq!(written, 5);
+
+        for x in &mut out {
+            unsafe { x.assume_init_drop() }
+        }
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..8).map(|x| dr.create(x as f32)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.zip_with_into_slice(b, &mut out, |x, y| {
+                if *x.get() == 3 {
+                    panic!()
+                }
+
+                dr.create((*x.get(), *y.get()))
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+}
+
+mod array {
+    use super::*;
+
+    #[test]
+    fn map_array_panic() {
+        let dr = DropCounter::new();
+
+        let array = [0, 1, 2, 3, 4].map(|x| dr.create(x));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map_array(array, |x| {
+                if *x.get() == 2 {
+                    panic!()
+                }
+
+                dr.create(*x.get() as f32)
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn zip_arrays_panic() {
+        let dr = DropCounter::new();
+
+        let a = [0, 1, 2, 3, 4].map(|x| dr.create(x));
+        let b = [0, 1, 2, 3, 4].map(|x| dr.create(x as f32));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            zip_arrays(a, b, |x, y| {
+                if *x.get() == 3 {
+                    panic!()
+                }
+
+                dr.create((*x.get(), *y.get()))
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let a = [0, 1, 2].map(|x| dr.create(x));
+        let b = [0, 1, 2].map(|x| dr.create(x as f32));
+
+        assert_eq!(zip_arrays(a, b, |x, _| x).len(), 3);
+    }
+}
+
+mod zip_with_swap {
+    use super::*;
+
+    #[test]
+    fn zip_with_swap_panic() {
+        let dr = DropCounter::new();
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..7).map(|x| dr.create(x as f32)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.zip_with_swap(b, |x, y| {
+                if *x.get() == 4 {
+                    panic!()
+                }
+
+                (x, y)
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let a = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let b = (0..7).map(|x| dr.create(x as f32)).collect::<Vec<_>>();
+
+        let (a, b) = a.zip_with_swap(b, |x, y| (dr.create(*x.get() + 1), y));
+
+        assert_eq!(a.len(), 10);
+        assert_eq!(b.len(), 7);
+    }
+}
+
+mod map_expand {
+    use super::*;
+
+    #[test]
+    fn map_expand_panic() {
+        let dr = DropCounter::new();
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        vec.reserve(20);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_expand(|x| {
+                if *x.get() == 4 {
+                    panic!()
+                }
+
+                [dr.create(*x.get() as i64), dr.create(*x.get() as i64)]
+            })
+        }));
+
+        assert!(result.is_err());
+
+        let mut vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        vec.reserve(20);
+
+        let vec = vec.map_expand(|x| [dr.create(*x.get() as u32), dr.create(*x.get() as u32)]);
+
+        assert_eq!(vec.len(), 20);
+    }
+    #[test]
+    fn map_expand_forward_panic() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.map_expand(|x| {
+                if *x.get() == 4 {
+                    panic!()
+                }
+
+                [dr.create(*x.get() as u32)]
+            })
+        }));
+
+        assert!(result.is_err());
+    }
+}
+
+mod map_ref {
+    use super::*;
+
+    #[test]
+    fn map_ref() {
+        let dr = DropCounter::new();
+
+        let vec = (0..10).map(|x| dr.create(x)).collect::<Vec<_>>();
+        let vec = vec.map_ref(|x| dr.create(*x.get() as f32));
+
+        let err = vec
+            .try_map_ref(|x| {
+                if *x.get() == 5.0 {
+                    None
+                } else {
+                    Some(dr.create(*x.get() as u32))
+                }
+            })
+            .is_err();
+
+        assert!(err);
+    }
+}
+
+#[cfg(debug_assertions)]
+mod validation {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "read past the end of the input")]
+    fn read_past_end() {
+        let mut input = Input::from(vec![1, 2]);
+
+        unsafe {
+            input.next_unchecked();
+            input.next_unchecked();
+            input.next_unchecked();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the output was taken twice")]
+    fn take_output_twice() {
+        let mut input = Input::from(vec![1, 2]);
+
+        unsafe {
+            let _ = input.take_output::<u32>();
+            let _ = input.take_output::<u32>();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "read from the input after it was dropped")]
+    fn read_after_drop() {
+        let dr = DropCounter::new();
+
+        let mut input = Input::from(vec![dr.create(1), dr.create(2)]);
+
+        unsafe {
+            input.next_unchecked();
+            input.drop_rest(1);
+            input.next_unchecked();
         }
     }
 }
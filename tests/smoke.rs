@@ -4,7 +4,7 @@ use vec_utils::{try_zip_with, zip_with, VecExt};
 fn map() {
     let vec = vec![0.0f32, 1.0, 2.0, 3.0];
 
-    let vec: Vec<u32> = vec.map(move |x| unsafe { std::mem::transmute(x) });
+    let vec: Vec<u32> = vec.map(f32::to_bits);
 
     assert_eq!(
         vec,
@@ -21,7 +21,7 @@ fn map() {
 fn map_combin() {
     let vec = vec![0.0f32, 1.0, 2.0, 3.0];
 
-    let vec: Vec<u32> = zip_with!(vec, |x| unsafe { std::mem::transmute(x) });
+    let vec: Vec<u32> = zip_with!(vec, |x| x.to_bits());
 
     assert_eq!(
         vec,
@@ -146,3 +146,2420 @@ fn try_zip() {
 
     assert_eq!(vec, Err(()));
 }
+
+#[test]
+fn cstring_map_bytes() {
+    use std::ffi::CString;
+    use vec_utils::CStringExt;
+
+    let s = CString::new("hello").unwrap();
+    let ptr = s.as_ptr();
+
+    let s = s.map_bytes(|b| b.to_ascii_uppercase()).unwrap();
+
+    assert_eq!(s.as_bytes(), b"HELLO");
+    assert_eq!(s.as_ptr(), ptr);
+
+    let s = CString::new("hello").unwrap();
+    assert!(s.map_bytes(|b| if b == b'l' { 0 } else { b }).is_err());
+
+    let s = CString::new("hello").unwrap();
+    assert_eq!(
+        s.map_into_string(|b| b.to_ascii_uppercase()).unwrap(),
+        "HELLO"
+    );
+}
+
+#[test]
+fn into_cstring() {
+    use vec_utils::ByteVecExt;
+
+    let mut bytes = Vec::with_capacity(6);
+    bytes.extend_from_slice(b"hello");
+    let ptr = bytes.as_ptr();
+
+    let s = bytes.into_cstring().unwrap();
+    assert_eq!(s.as_bytes_with_nul(), b"hello\0");
+    assert_eq!(s.as_ptr() as *const u8, ptr);
+
+    let s = b"hello\0".to_vec().into_cstring().unwrap();
+    assert_eq!(s.as_bytes_with_nul(), b"hello\0");
+
+    assert!(b"he\0llo".to_vec().into_cstring().is_err());
+}
+
+#[test]
+fn into_sorted_map() {
+    use std::collections::BinaryHeap;
+    use vec_utils::BinaryHeapExt;
+
+    let heap = vec![3u32, 1, 4, 1, 5, 9, 2, 6]
+        .into_iter()
+        .collect::<BinaryHeap<_>>();
+
+    let vec = heap.into_sorted_map(|x| x as f32);
+
+    assert_eq!(vec, [1.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 9.0]);
+}
+
+#[test]
+fn cow_map() {
+    use std::borrow::Cow;
+    use vec_utils::CowExt;
+
+    let vec = vec![0.0f32, 1.0, 2.0, 3.0];
+    let ptr = vec.as_ptr();
+
+    let cow: Cow<[f32]> = Cow::Owned(vec);
+    let vec = cow.map(f32::to_bits);
+
+    assert_eq!(vec.as_ptr() as *const f32, ptr);
+    assert_eq!(vec, [0.0f32, 1.0, 2.0, 3.0].map(f32::to_bits));
+
+    let array = [0.0f32, 1.0, 2.0, 3.0];
+    let cow = Cow::Borrowed(&array[..]).map_in_place(f32::to_bits);
+
+    assert_eq!(*cow, array.map(f32::to_bits));
+}
+
+#[test]
+fn boxed_slice() {
+    let bx: Box<[f32]> = vec![0.0f32, 1.0, 2.0, 3.0].into_boxed_slice();
+    let ptr = bx.as_ptr();
+
+    let vec = bx.map(f32::to_bits);
+
+    assert_eq!(vec.as_ptr() as *const f32, ptr);
+    assert_eq!(vec, [0.0f32, 1.0, 2.0, 3.0].map(f32::to_bits));
+
+    let a: Box<[f32]> = vec![0.0f32, 1.0, 2.0, 3.0].into_boxed_slice();
+    let b = vec![0.0f32, 1.0, 2.0, 3.0];
+
+    let vec: Vec<f32> = zip_with!((a, b), |a, b| a + b);
+
+    assert_eq!(vec, [0.0, 2.0, 4.0, 6.0]);
+}
+
+#[test]
+fn map_unbox() {
+    use vec_utils::VecBoxExt;
+
+    let vec: Vec<Box<u32>> = (0..4).map(Box::new).collect();
+    let ptr = vec.as_ptr();
+
+    let vec: Vec<usize> = vec.map_unbox(|x| x as usize * 2);
+
+    assert_eq!(vec.as_ptr() as *const Box<u32>, ptr);
+    assert_eq!(vec, [0, 2, 4, 6]);
+
+    let vec: Vec<Box<u32>> = (0..4).map(Box::new).collect();
+    let vec: Result<Vec<u64>, _> = vec.try_map_unbox(|x| if x < 3 { Ok(x.into()) } else { Err(x) });
+
+    assert_eq!(vec, Err(3));
+}
+
+#[test]
+fn flatten_reuse() {
+    use vec_utils::VecVecExt;
+
+    let mut big = Vec::with_capacity(16);
+    big.extend_from_slice(&[3, 4]);
+    let ptr = big.as_ptr();
+
+    let vec = vec![vec![0, 1, 2], big, vec![], vec![5, 6]];
+    let vec = vec.flatten_reuse();
+
+    assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(vec.as_ptr(), ptr);
+
+    let vec = vec![vec![0, 1], vec![2, 3], vec![4]];
+    let vec = vec.flatten_reuse();
+
+    assert_eq!(vec, [0, 1, 2, 3, 4]);
+    assert_eq!(vec.capacity(), 5);
+
+    assert!(Vec::<Vec<i32>>::new().flatten_reuse().is_empty());
+}
+
+#[test]
+fn map_range() {
+    let mut vec = vec![0, 1, 2, 3, 4, 5];
+
+    vec.map_range(1..4, |x| x * 10);
+    assert_eq!(vec, [0, 10, 20, 30, 4, 5]);
+
+    vec.map_range(4.., |x| -x);
+    assert_eq!(vec, [0, 10, 20, 30, -4, -5]);
+
+    let mut bx: Box<[i32]> = vec.into_boxed_slice();
+    bx.map_range(..=0, |x| x + 1);
+    assert_eq!(*bx, [1, 10, 20, 30, -4, -5]);
+}
+
+#[test]
+#[should_panic]
+fn map_range_out_of_bounds() {
+    let mut vec = vec![0, 1, 2];
+
+    vec.map_range(1..4, |x| x);
+}
+
+#[test]
+fn splice_map() {
+    let vec = vec![0.0f32, 1.0, 2.0, 3.0, 4.0];
+    let ptr = vec.as_ptr();
+
+    let vec = vec.splice_map(1..3, vec![10, 11], f32::to_bits);
+
+    assert_eq!(vec.as_ptr() as *const f32, ptr);
+    assert_eq!(
+        vec,
+        [0.0f32.to_bits(), 10, 11, 3.0f32.to_bits(), 4.0f32.to_bits()]
+    );
+
+    let vec = vec![0u8, 1, 2, 3, 4];
+    let vec = vec.splice_map(3.., vec![10u64], u64::from);
+
+    assert_eq!(vec, [0, 1, 2, 10]);
+
+    // the replacement is longer than the spare capacity
+    let mut vec = Vec::with_capacity(4);
+    vec.extend(0..4u32);
+    let vec = vec.splice_map(1..2, vec![10, 11, 12], |x| x as i32 * 2);
+
+    assert_eq!(vec, [0, 10, 11, 12, 4, 6]);
+
+    // the replacement fits in the removed range
+    let vec = vec.splice_map(..4, vec![1], |x| x + 1);
+
+    assert_eq!(vec, [1, 5, 7]);
+    assert_eq!(vec.capacity(), 6);
+}
+
+#[test]
+fn retain_map() {
+    let mut vec = (0..10).collect::<Vec<i32>>();
+
+    vec.retain_map(|x| if x % 3 == 0 { None } else { Some(x * 2) });
+
+    assert_eq!(vec, [2, 4, 8, 10, 14, 16]);
+}
+
+#[test]
+fn collect_with() {
+    use vec_utils::CollectWith;
+
+    let donor = Vec::<u64>::with_capacity(8);
+
+    let vec = (0..16u32).map(|x| x as f32).collect_with(donor);
+
+    // `f32` and `u64` have different alignment, so the donor can't be reused
+    assert_eq!(vec.len(), 16);
+
+    let donor = vec![0u32; 8];
+    let vec = (0..16u16).map(|x| [x, x]).collect_with(donor);
+
+    assert_eq!(vec.len(), 16);
+
+    let donor = vec![(0u32, 0u32); 8];
+    let vec = (0..16u32).map(|x| x * 2).collect_with(donor);
+
+    assert_eq!(vec.len(), 16);
+    assert_eq!(vec[15], 30);
+
+    let donor = vec![[0u64; 2]; 4];
+    let ptr_2 = donor.as_ptr();
+    let vec = (0..8u64).collect_with(donor);
+
+    assert_eq!(vec.as_ptr() as *const [u64; 2], ptr_2);
+    assert_eq!(vec.capacity(), 8);
+}
+
+#[test]
+fn try_collect_with() {
+    use vec_utils::{CollectWith, ScaleError};
+
+    let donor = vec![[0u32; 3]; 4];
+    let ptr = donor.as_ptr() as usize;
+    let vec = (0..6u32).try_collect_with(donor).unwrap();
+
+    assert_eq!(vec, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(vec.capacity(), 12);
+
+    assert_eq!(
+        (0..4u64).try_collect_with(vec![0u32; 4]),
+        Err(ScaleError::AlignMismatch { from: 4, to: 8 })
+    );
+    assert_eq!(
+        (0..4u16)
+            .map(|x| [x; 3])
+            .try_collect_with(Vec::<u16>::with_capacity(4)),
+        Err(ScaleError::Uneven { bytes: 8, size: 6 })
+    );
+    assert_eq!(
+        (0..4u8).map(|_| ()).try_collect_with(vec![0u8; 4]),
+        Err(ScaleError::ZeroSized)
+    );
+}
+
+#[test]
+fn scale_capacity() {
+    use vec_utils::{scale_capacity, ScaleError};
+
+    assert_eq!(scale_capacity::<[u8; 4], u8>(3), Ok(12));
+    assert_eq!(scale_capacity::<u8, [u8; 4]>(12), Ok(3));
+    assert_eq!(
+        scale_capacity::<[u8; 4], u8>(usize::MAX / 2),
+        Err(ScaleError::Overflow {
+            capacity: usize::MAX / 2,
+            size: 4
+        })
+    );
+    assert_eq!(
+        scale_capacity::<[u8; 2], u8>(isize::MAX as usize / 2 + 1),
+        Err(ScaleError::Overflow {
+            capacity: isize::MAX as usize / 2 + 1,
+            size: 2
+        })
+    );
+}
+
+#[test]
+fn reuse_iter() {
+    let vec = (0..10).map(|x| x as f32).collect::<Vec<_>>();
+    let ptr = vec.as_ptr();
+
+    let out = vec
+        .into_reuse_iter()
+        .filter(|x| *x > 2.0)
+        .map(f32::to_bits)
+        .zip(0..5)
+        .map(|(x, y)| x + y)
+        .collect_reuse();
+
+    assert_eq!(out.as_ptr() as *const f32, ptr);
+    assert_eq!(
+        out,
+        (0..5)
+            .map(|x| (x as f32 + 3.0).to_bits() + x)
+            .collect::<Vec<_>>()
+    );
+
+    let sum: u32 = vec![1u32, 2, 3].into_reuse_iter().map(|x| x * 2).sum();
+    assert_eq!(sum, 12);
+
+    let out = vec![(); 10]
+        .into_reuse_iter()
+        .filter(|_| true)
+        .collect_reuse();
+    assert_eq!(out.len(), 10);
+}
+
+#[test]
+fn reuse_iter_capacity_hint() {
+    let vec = (0..100u32).collect::<Vec<_>>();
+
+    let out = vec
+        .into_reuse_iter()
+        .filter(|x| x % 10 == 0)
+        .with_capacity_hint(10)
+        .map(|x| x / 10)
+        .collect_reuse();
+    assert_eq!(out, (0..10).collect::<Vec<_>>());
+    assert!(out.capacity() < 100);
+
+    // the hint is a lower bound for the capacity, not the length
+    let out = (0..100u32)
+        .collect::<Vec<_>>()
+        .into_reuse_iter()
+        .with_capacity_hint(3)
+        .collect_reuse();
+    assert_eq!(out.len(), 100);
+
+    let out = (0..100u32)
+        .collect::<Vec<_>>()
+        .into_reuse_iter()
+        .filter(|x| x % 4 == 0)
+        .with_capacity_hint(25)
+        .map(u64::from)
+        .collect_reuse();
+    assert_eq!(out.len(), 25);
+    assert!(out.capacity() >= 25);
+}
+
+#[test]
+fn reuse_iter_map_err() {
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Parse(std::num::ParseIntError),
+        Negative(i32),
+    }
+
+    impl From<std::num::ParseIntError> for Error {
+        fn from(err: std::num::ParseIntError) -> Self {
+            Error::Parse(err)
+        }
+    }
+
+    fn non_negative(x: i32) -> Result<i32, Error> {
+        if x < 0 {
+            Err(Error::Negative(x))
+        } else {
+            Ok(x)
+        }
+    }
+
+    let parse = |vec: Vec<&str>| {
+        vec.into_reuse_iter()
+            .map(str::parse::<i32>)
+            .err_into::<Error>()
+            .map(|x| x.and_then(non_negative))
+            .map_err(|err| format!("{:?}", err))
+            .try_collect_reuse()
+    };
+
+    assert_eq!(parse(vec!["1", "2", "3"]), Ok(vec![1, 2, 3]));
+    assert_eq!(parse(vec!["1", "-2", "x"]), Err("Negative(-2)".to_string()));
+    assert!(parse(vec!["x"]).unwrap_err().starts_with("Parse("));
+
+    let vec = vec![1u32, 2, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec
+        .into_reuse_iter()
+        .map(|x| x.checked_mul(2).ok_or(()))
+        .try_collect_reuse()
+        .unwrap();
+    assert_eq!(out, [2, 4, 6]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let out = vec![Some(1u8), None].into_reuse_iter().try_collect_reuse();
+    assert_eq!(out, Err(vec_utils::NoneError));
+}
+
+#[test]
+fn reuse_iter_or_else() {
+    let vec = vec!["1", "x", "3", "-", "5"];
+
+    let out = vec
+        .clone()
+        .into_reuse_iter()
+        .map(str::parse::<u32>)
+        .or_else(|_| Ok::<_, ()>(0))
+        .try_collect_reuse();
+    assert_eq!(out, Ok(vec![1, 0, 3, 0, 5]));
+
+    let out = vec
+        .clone()
+        .into_reuse_iter()
+        .map(str::parse::<u32>)
+        .or_else(Err)
+        .filter(Result::is_ok)
+        .try_collect_reuse();
+    assert_eq!(out, Ok(vec![1, 3, 5]));
+
+    let out = vec
+        .into_reuse_iter()
+        .map(|x| x.parse::<u32>().map_err(|_| x))
+        .or_else(|x| if x == "x" { Ok(2) } else { Err(x) })
+        .try_collect_reuse();
+    assert_eq!(out, Err("-"));
+}
+
+#[test]
+fn reuse_iter_zip_eq() {
+    use vec_utils::LengthMismatch;
+
+    let vec = vec![1u32, 2, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec
+        .into_reuse_iter()
+        .zip_eq(vec![10, 20, 30])
+        .map(|x| x.map(|(x, y)| x + y))
+        .try_collect_reuse()
+        .unwrap();
+    assert_eq!(out, [11, 22, 33]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let out = vec![1u32, 2, 3]
+        .into_reuse_iter()
+        .zip_eq(0..5)
+        .try_collect_reuse();
+    assert_eq!(out, Err(LengthMismatch { left: 3, right: 5 }));
+
+    let out = vec![1u32, 2, 3, 4, 5]
+        .into_reuse_iter()
+        .filter(|x| x % 2 == 1)
+        .zip_eq(0..1)
+        .try_collect_reuse();
+    assert_eq!(out, Err(LengthMismatch { left: 3, right: 1 }));
+
+    // the error can be recovered after the source is exhausted
+    let vec = vec![1u32, 2];
+    let out = vec
+        .into_reuse_iter()
+        .zip_eq(0..4)
+        .or_else(|_| Ok::<_, ()>((0, 0)))
+        .map(|x| x.map(|(x, y)| x + y))
+        .try_collect_reuse()
+        .unwrap();
+    assert_eq!(out, [1, 3, 0]);
+
+    let out = Vec::<u32>::new()
+        .into_reuse_iter()
+        .zip_eq(0..2)
+        .try_collect_reuse();
+    assert_eq!(out, Err(LengthMismatch { left: 0, right: 2 }));
+}
+
+#[test]
+fn reuse_iter_for_each() {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    vec![1u32, 2, 3]
+        .into_reuse_iter()
+        .map(|x| x * 2)
+        .for_each(|x| tx.send(x).unwrap());
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [2, 4, 6]);
+
+    let res = vec!["1", "2", "x", "4"]
+        .into_reuse_iter()
+        .map(str::parse::<u32>)
+        .try_for_each(|x| x.map(|x| tx.send(x).unwrap()));
+    assert!(res.is_err());
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [1, 2]);
+
+    let res = vec![Some(1u8), None]
+        .into_reuse_iter()
+        .try_for_each(|x| x.map(drop));
+    assert_eq!(res, Err(vec_utils::NoneError));
+}
+
+#[test]
+fn reuse_iter_collect_string() {
+    let bytes = b"hello world".to_vec();
+    let ptr = bytes.as_ptr() as usize;
+
+    let string = bytes
+        .into_reuse_iter()
+        .map(|b| b.to_ascii_uppercase())
+        .try_collect_string()
+        .unwrap();
+    assert_eq!(string, "HELLO WORLD");
+    assert_eq!(string.as_ptr() as usize, ptr);
+
+    let err = vec![b'a', 0xff].into_reuse_iter().try_collect_string();
+    assert_eq!(err.unwrap_err().into_bytes(), [b'a', 0xff]);
+
+    let bytes = b"a-b-c".to_vec();
+    let ptr = bytes.as_ptr() as usize;
+
+    let string = bytes
+        .into_reuse_iter()
+        .filter(|&b| b != b'-')
+        .map(char::from)
+        .collect_string();
+    assert_eq!(string, "abc");
+    assert_eq!(string.as_ptr() as usize, ptr);
+
+    // the multi-byte characters don't fit behind the read position
+    let string = vec![b'a', b'b', b'c']
+        .into_reuse_iter()
+        .map(|b| if b == b'b' { '\u{e9}' } else { char::from(b) })
+        .collect_string();
+    assert_eq!(string, "a\u{e9}c");
+
+    // but they fit once enough has been filtered out
+    let string = b"xxa!b!"
+        .to_vec()
+        .into_reuse_iter()
+        .filter(|&b| b != b'x')
+        .map(|b| if b == b'!' { '\u{2764}' } else { char::from(b) })
+        .collect_string();
+    assert_eq!(string, "a\u{2764}b\u{2764}");
+
+    let string = vec![104u32, 105]
+        .into_reuse_iter()
+        .map(|x| char::from_u32(x).unwrap())
+        .collect_string();
+    assert_eq!(string, "hi");
+}
+
+#[test]
+fn reuse_iter_partition_result() {
+    // neither side can reuse the allocation
+    let (values, errors) = vec!["1", "x", "3", "-4", "y"]
+        .into_reuse_iter()
+        .map(|x| x.parse::<u64>().map_err(|_| x.len()))
+        .partition_result();
+    assert_eq!(values, [1, 3]);
+    assert_eq!(errors, [1, 2, 1]);
+
+    let vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    let (values, errors) = vec
+        .into_reuse_iter()
+        .map(|x| if x % 2 == 0 { Ok(x) } else { Err(x as u64) })
+        .partition_result();
+    assert_eq!(values, [2, 4]);
+    assert_eq!(errors, [1, 3, 5]);
+    assert_eq!(values.as_ptr() as usize, ptr);
+
+    // the errors reuse the allocation
+    let vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    let (values, errors) = vec
+        .into_reuse_iter()
+        .map(|x| if x % 2 == 0 { Ok(x as u8) } else { Err(x) })
+        .partition_result();
+    assert_eq!(values, [2, 4]);
+    assert_eq!(errors, [1, 3, 5]);
+    assert_eq!(errors.as_ptr() as usize, ptr);
+}
+
+#[test]
+fn reuse_iter_split_tail() {
+    let vec = (0..10u32).collect::<Vec<_>>();
+    let ptr = vec.as_ptr() as usize;
+
+    let mut iter = vec.into_reuse_iter();
+    let tail = iter.split_tail(8).collect::<Vec<_>>();
+    assert_eq!(tail, [8, 9]);
+
+    let head = iter.map(|x| x * 2).collect_reuse();
+    assert_eq!(head, [0, 2, 4, 6, 8, 10, 12, 14]);
+    assert_eq!(head.as_ptr() as usize, ptr);
+
+    let mut iter = vec![(); 10].into_reuse_iter();
+    assert_eq!(iter.split_tail(4).count(), 6);
+    assert_eq!(iter.count(), 4);
+
+    let mut iter = vec![1, 2].into_reuse_iter();
+    assert_eq!(iter.split_tail(2).next(), None);
+    assert_eq!(iter.collect_reuse(), [1, 2]);
+}
+
+#[test]
+fn dyn_pipeline() {
+    use vec_utils::DynPipeline;
+
+    let vec = vec![1u32, 2, 3, 4, 5, 6];
+    let ptr = vec.as_ptr() as usize;
+
+    let mut pipeline = DynPipeline::new()
+        .filter(|x: &u32| *x > 3)
+        .map(|x| x as f32 / 2.0);
+    let out = pipeline.run(vec);
+    assert_eq!(out, [2.0, 2.5, 3.0]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let mut pipeline = DynPipeline::new().zip(0u8..3).map(|(x, y)| x * y as u32);
+    assert_eq!(pipeline.run(vec![4, 5]), [0, 5]);
+    assert_eq!(pipeline.run(vec![6, 7]), [12]);
+    assert_eq!(pipeline.run(vec![8]), []);
+
+    let mut pipeline = DynPipeline::<u8, _>::default().map(u16::from);
+    assert_eq!(pipeline.run(vec![1, 2]), [1, 2]);
+}
+
+#[test]
+fn recycle_bin() {
+    use vec_utils::RecycleBin;
+
+    let mut bin = RecycleBin::new();
+
+    let vec = vec![0u32; 16];
+    let ptr = vec.as_ptr();
+    bin.deposit(vec);
+
+    assert_eq!(bin.len(), 1);
+    assert!(bin.take::<u64>(4).is_none());
+    assert!(bin.take::<[u32; 3]>(4).is_none());
+    assert!(bin.take::<i32>(17).is_none());
+
+    // too much of the allocation would be unused
+    assert!(bin.take::<u32>(7).is_none());
+
+    let vec = bin.take::<[u32; 2]>(4).unwrap();
+    assert!(bin.is_empty());
+    assert_eq!(vec.as_ptr() as *const u32, ptr);
+    assert_eq!(vec.capacity(), 8);
+
+    // allocations past the limits are freed
+    let mut bin = RecycleBin::with_limits(2, 64);
+
+    bin.deposit(vec![0u8; 80]);
+    assert!(bin.is_empty());
+
+    bin.deposit(vec![0u8; 32]);
+    bin.deposit(vec![0u8; 16]);
+    bin.deposit(vec![0u8; 8]);
+    assert_eq!(bin.len(), 2);
+    assert_eq!(bin.bytes(), 48);
+
+    bin.clear();
+    bin.deposit(vec![0u8; 40]);
+    bin.deposit(vec![0u8; 32]);
+    assert_eq!(bin.len(), 1);
+    assert_eq!(bin.bytes(), 40);
+}
+
+#[test]
+fn recycle_fallback() {
+    let vec = vec![0i32; 8];
+    let ptr = vec.as_ptr();
+    vec.retire();
+
+    // `u8` and `u32` have different layouts, so the thread-local bin is used
+    let vec = vec![0u8; 8].map(u32::from);
+    assert_eq!(vec.as_ptr() as *const i32, ptr);
+}
+
+#[test]
+fn spare_writer() {
+    use vec_utils::SpareCapacityExt;
+
+    let mut vec = Vec::with_capacity(4);
+    vec.push(0);
+
+    let mut writer = vec.spare_writer();
+    assert_eq!(writer.remaining(), 3);
+    assert_eq!(writer.extend(1..), 3);
+    assert_eq!(writer.push(10), Err(10));
+    assert_eq!(writer.as_slice(), [1, 2, 3]);
+    writer.commit();
+
+    assert_eq!(vec, [0, 1, 2, 3]);
+
+    vec.reserve(1);
+    let mut writer = vec.spare_writer();
+    writer.push(4).unwrap();
+    drop(writer);
+
+    assert_eq!(vec, [0, 1, 2, 3]);
+}
+
+#[test]
+fn uninit_vec() {
+    use vec_utils::{alloc_uninit_vec, PartialInitGuard};
+
+    let vec = alloc_uninit_vec::<u32>(4);
+    let ptr = vec.as_ptr() as usize;
+
+    let mut guard = PartialInitGuard::new(vec);
+    for i in 0..4 {
+        guard.push(i * 2);
+    }
+    assert_eq!(guard.as_slice(), [0, 2, 4, 6]);
+
+    let vec = guard.finish();
+
+    assert_eq!(vec, [0, 2, 4, 6]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+}
+
+#[test]
+fn columns() {
+    use vec_utils::{impl_columns, Columns};
+
+    #[derive(Debug, PartialEq)]
+    struct Vertex {
+        position: [f32; 2],
+        color: u32,
+    }
+
+    impl_columns!(Vertex {
+        position: [f32; 2],
+        color: u32
+    });
+
+    let vertices = (0..4)
+        .map(|i| Vertex {
+            position: [i as f32; 2],
+            color: i,
+        })
+        .collect::<Vec<_>>();
+    let ptr = vertices.as_ptr() as usize;
+
+    let (position, color) = Vertex::into_columns(vertices);
+
+    assert_eq!(position.as_ptr() as usize, ptr);
+    assert_eq!(position, [[0.0; 2], [1.0; 2], [2.0; 2], [3.0; 2]]);
+    assert_eq!(color, [0, 1, 2, 3]);
+
+    let pairs = <(u32, u32)>::from_columns((vec![0, 1, 2], vec![3, 4, 5]));
+    assert_eq!(pairs, [(0, 3), (1, 4), (2, 5)]);
+
+    let (a, b) = <(u32, u32)>::into_columns(pairs);
+    assert_eq!(a, [0, 1, 2]);
+    assert_eq!(b, [3, 4, 5]);
+
+    let mut wide = Vec::with_capacity(8);
+    wide.extend_from_slice(&[1u64, 2, 3]);
+    let ptr = wide.as_ptr() as usize;
+
+    let pairs = <(u64, u64)>::from_columns((wide, vec![4, 5, 6]));
+    assert_eq!(pairs.as_ptr() as usize, ptr);
+    assert_eq!(pairs, [(1, 4), (2, 5), (3, 6)]);
+}
+
+#[test]
+fn transpose() {
+    let mut matrix = (0..12).collect::<Vec<_>>();
+    matrix.transpose_in_place(3, 4);
+    assert_eq!(matrix, [0, 4, 8, 1, 5, 9, 2, 6, 10, 3, 7, 11]);
+    matrix.transpose_in_place(4, 3);
+    assert_eq!(matrix, (0..12).collect::<Vec<_>>());
+
+    let mut scratch = Vec::new();
+    matrix.transpose_with(2, 6, &mut scratch);
+    assert_eq!(matrix, [0, 6, 1, 7, 2, 8, 3, 9, 4, 10, 5, 11]);
+    assert!(scratch.is_empty());
+    assert!(scratch.capacity() >= 12);
+
+    let ptr = scratch.as_ptr() as usize;
+    matrix.transpose_with(6, 2, &mut scratch);
+    assert_eq!(matrix, (0..12).collect::<Vec<_>>());
+    assert_eq!(matrix.as_ptr() as usize, ptr);
+
+    // a tall matrix, `index * rows` would be large here
+    let (rows, cols) = (1 << 12, 5);
+    let mut matrix = (0..rows * cols).collect::<Vec<usize>>();
+    matrix.transpose_in_place(rows, cols);
+
+    for (i, &x) in matrix.iter().enumerate() {
+        assert_eq!(x, (i % rows) * cols + i / rows);
+    }
+
+    matrix.transpose_in_place(cols, rows);
+    assert_eq!(matrix, (0..rows * cols).collect::<Vec<_>>());
+}
+
+#[test]
+fn interleave() {
+    let a = vec![0, 2, 4];
+    let b = vec![1, 3, 5, 7, 9];
+
+    assert_eq!(a.clone().interleave(b.clone()), [0, 1, 2, 3, 4, 5, 7, 9]);
+    assert_eq!(b.clone().interleave(a.clone()), [1, 0, 3, 2, 5, 4, 7, 9]);
+
+    let mut b = b;
+    b.reserve(a.len());
+    let ptr = b.as_ptr() as usize;
+
+    let c = a.interleave(b);
+    assert_eq!(c.as_ptr() as usize, ptr);
+    assert_eq!(c, [0, 1, 2, 3, 4, 5, 7, 9]);
+}
+
+#[test]
+fn map_into_boxed_slice() {
+    let mut vec = Vec::with_capacity(10);
+    vec.extend(0u64..4);
+
+    let slice = vec.clone().map_into_boxed_slice(|x| x as f64 / 2.0);
+    assert_eq!(*slice, [0.0, 0.5, 1.0, 1.5]);
+
+    let slice = vec.clone().map_into_boxed_slice(|x| [x as u32; 2]);
+    assert_eq!(*slice, [[0, 0], [1, 1], [2, 2], [3, 3]]);
+
+    let slice = vec.clone().map_into_boxed_slice(|x| x as u8);
+    assert_eq!(*slice, [0, 1, 2, 3]);
+
+    let slice = vec.map_into_boxed_slice(|x| (x, x));
+    assert_eq!(*slice, [(0, 0), (1, 1), (2, 2), (3, 3)]);
+
+    let slice = Vec::<u32>::new().map_into_boxed_slice(|x| x);
+    assert!(slice.is_empty());
+}
+
+#[test]
+fn map_into_shared() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let shared: Arc<[u32]> = vec![0.5f32, 1.0, 1.5].map_into_shared(|x| (x * 2.0) as u32);
+    assert_eq!(*shared, [1, 2, 3]);
+
+    let shared: Rc<[String]> = vec![1, 2, 3].map_into_rc(|x| x.to_string());
+    assert_eq!(*shared, ["1", "2", "3"]);
+}
+
+#[test]
+fn zip_with_into_slice() {
+    use std::mem::MaybeUninit;
+
+    let mut out = [MaybeUninit::<f64>::uninit(); 4];
+
+    let written = vec![1, 2, 3, 4, 5].zip_with_into_slice(
+        vec![0.5, 1.5, 2.5, 3.5, 4.5, 5.5],
+        &mut out,
+        |x, y| f64::from(x) + y,
+    );
+
+    assert_eq!(written, 4);
+    let out = out.map(|x| unsafe { x.assume_init() });
+    assert_eq!(out, [1.5, 3.5, 5.5, 7.5]);
+
+    let mut out = [MaybeUninit::<u8>::uninit(); 4];
+    assert_eq!(
+        vec![1u8].zip_with_into_slice(vec![2u8, 3], &mut out, |x, y| x + y),
+        1
+    );
+    assert_eq!(unsafe { out[0].assume_init() }, 3);
+}
+
+#[test]
+fn arrays() {
+    use vec_utils::{map_array, zip_arrays};
+
+    assert_eq!(map_array([1, 2, 3], |x| x.to_string()), ["1", "2", "3"]);
+    assert_eq!(
+        zip_arrays(["a", "b"], [1, 2], |x, y| x.repeat(y)),
+        ["a", "bb"]
+    );
+}
+
+#[test]
+fn join_map() {
+    use vec_utils::StringVecExt;
+
+    let mut big = String::with_capacity(64);
+    big.push_str("middle");
+    let ptr = big.as_ptr() as usize;
+
+    let strings = vec!["first".to_string(), big, "last".to_string()];
+    let joined = strings.join_map(", ", |mut s| {
+        s.make_ascii_uppercase();
+        s
+    });
+
+    assert_eq!(joined, "FIRST, MIDDLE, LAST");
+    assert_eq!(joined.as_ptr() as usize, ptr);
+
+    let strings = vec!["a".to_string(), String::with_capacity(64), "c".to_string()];
+    let joined = strings.join_map("-", |mut s| {
+        s.push('!');
+        s
+    });
+
+    assert_eq!(joined, "a!-!-c!");
+
+    assert_eq!(Vec::<String>::new().join_map(", ", |s| s), "");
+}
+
+#[test]
+fn zip_with_swap() {
+    let position = vec![0.0f32, 1.0, 2.0];
+    let velocity = vec![1.0f32, -1.0, 0.5, 10.0];
+
+    let (position, velocity) = position.zip_with_swap(velocity, |p, v| {
+        let p = p + v;
+        (p, if p < 1.0 { -v } else { v })
+    });
+
+    assert_eq!(position, [1.0, 0.0, 2.5]);
+    assert_eq!(velocity, [1.0, 1.0, 0.5, 10.0]);
+}
+
+#[test]
+fn map_expand() {
+    let vec = vec![(1u32, 2u32), (3, 4)];
+    let ptr = vec.as_ptr() as usize;
+
+    let halves = vec.map_expand(|(x, y)| [x, y]);
+    assert_eq!(halves, [1, 2, 3, 4]);
+    assert_eq!(halves.as_ptr() as usize, ptr);
+
+    let mut vec = Vec::with_capacity(8);
+    vec.extend_from_slice(&[1u32, 2, 3]);
+    let ptr = vec.as_ptr() as usize;
+
+    let pairs = vec.map_expand(|x| [x, x * 10]);
+    assert_eq!(pairs, [1, 10, 2, 20, 3, 30]);
+    assert_eq!(pairs.as_ptr() as usize, ptr);
+
+    let triples = vec![1u8, 2].map_expand(|x| [x as u64; 3]);
+    assert_eq!(triples, [1, 1, 1, 2, 2, 2]);
+}
+
+#[test]
+fn zip_with_patterns() {
+    let pairs = vec![(1, 2), (3, 4), (5, 6)];
+    let scale = vec![10, 20, 30];
+
+    let out = zip_with!((pairs, scale), |(a, b), c| (a + b) * c);
+    assert_eq!(out, [30, 140, 330]);
+
+    let vec = vec![[1u8, 2], [3, 4]];
+    let out = zip_with!(vec, |[x, _]| x);
+    assert_eq!(out, [1, 3]);
+
+    let a = vec![(1, 'a'), (2, 'b')];
+    let b = vec![1, 2];
+    let out: Result<Vec<_>, ()> = try_zip_with!((a, b), |(x, c), y| Ok(format!("{}{}", c, x + y)));
+    assert_eq!(out.unwrap(), ["a2", "b4"]);
+}
+
+#[test]
+fn try_zip_with_question_mark() {
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Parse,
+        Negative,
+    }
+
+    impl From<std::num::ParseIntError> for Error {
+        fn from(_: std::num::ParseIntError) -> Self {
+            Error::Parse
+        }
+    }
+
+    let run = |a: Vec<&str>, b: Vec<i32>| {
+        try_zip_with!((a, b), |a, b| -> Result<i32, Error> {
+            let a = a.parse::<i32>()?;
+
+            if a < 0 {
+                return Err(Error::Negative);
+            }
+
+            Ok(a * b)
+        })
+    };
+
+    assert_eq!(run(vec!["1", "2"], vec![3, 4]), Ok(vec![3, 8]));
+    assert_eq!(run(vec!["1", "x"], vec![3, 4]), Err(Error::Parse));
+    assert_eq!(run(vec!["-1", "2"], vec![3, 4]), Err(Error::Negative));
+
+    let opt = try_zip_with!(vec![1u8, 2], |x| -> Option<u8> { x.checked_mul(100) });
+    assert_eq!(opt.ok(), Some(vec![100, 200]));
+}
+
+#[test]
+fn zip_assign() {
+    use vec_utils::zip_assign;
+
+    let dst = vec![1, 2, 3];
+    let mut src = Vec::with_capacity(100);
+    src.extend_from_slice(&[10, 20, 30]);
+
+    let ptr = dst.as_ptr() as usize;
+
+    zip_assign!((dst, src), |d, s| d + s);
+
+    assert_eq!(dst, [11, 22, 33]);
+    assert_eq!(dst.as_ptr() as usize, ptr);
+
+    zip_assign!(dst, |d| d as f32);
+
+    assert_eq!(dst, [11.0, 22.0, 33.0]);
+    assert_eq!(dst.as_ptr() as usize, ptr);
+}
+
+#[test]
+fn zip_builder() {
+    use vec_utils::ReusePolicy;
+
+    let a = vec![1u32, 2, 3];
+    let mut b = Vec::with_capacity(100);
+    b.extend_from_slice(&[4u32, 5, 6]);
+    let c = vec![7u8, 8, 9];
+
+    let ptr_a = a.as_ptr() as usize;
+
+    let sum = vec_utils::zip(a.clone())
+        .and(b.clone())
+        .and(c.clone())
+        .map(|(a, (b, c))| a + b + u32::from(c));
+    assert_eq!(sum, [12, 15, 18]);
+
+    let sum = vec_utils::zip(a)
+        .and(b)
+        .and(c.clone())
+        .policy(ReusePolicy::First)
+        .map(|(a, (b, c))| a + b + u32::from(c));
+    assert_eq!(sum.as_ptr() as usize, ptr_a);
+
+    let sum = vec_utils::zip(sum)
+        .and(c.clone())
+        .policy(ReusePolicy::Never)
+        .map(|(a, c)| a - u32::from(c));
+    assert_eq!(sum, [5, 7, 9]);
+
+    let donor = Vec::<i32>::with_capacity(3);
+    let ptr = donor.as_ptr() as usize;
+    let result: Result<Vec<u32>, &str> = vec_utils::zip(sum)
+        .and(c)
+        .output_from(donor)
+        .try_map(|(a, c)| a.checked_sub(u32::from(c) - 2).ok_or("underflow"));
+    let result = result.unwrap();
+    assert_eq!(result, [0, 1, 2]);
+    assert_eq!(result.as_ptr() as usize, ptr);
+}
+
+#[test]
+fn prelude() {
+    use vec_utils::prelude::*;
+
+    let vec = vec![1u32, 2, 3].map(|x| x as f32);
+    let vec = zip_with!((vec, vec![1.0f32; 3]), |a, b| a + b);
+    let boxed: Box<[f32]> = vec.into_boxed_slice();
+
+    assert_eq!(*boxed.map(|x| x as u8), [2, 3, 4]);
+}
+
+#[test]
+fn map_ref() {
+    let vec = vec!["a".to_string(), "bb".to_string()];
+    let lens = vec.map_ref(String::len);
+    assert_eq!(lens, [1, 2]);
+
+    let vec = vec!["1".to_string(), "x".to_string()];
+    assert!(vec.try_map_ref(|s| s.parse::<u32>()).is_err());
+}
+
+#[test]
+fn map_inspect() {
+    let mut log = Vec::new();
+
+    let vec = vec![1, 2, 3].map_inspect(|x| x * 10, |i, x, y| log.push((i, *x, *y)));
+    assert_eq!(vec, [10, 20, 30]);
+    assert_eq!(log, [(0, 1, 10), (1, 2, 20), (2, 3, 30)]);
+
+    let mut log = Vec::new();
+
+    let vec = vec![1, 2].zip_inspect(
+        vec![3, 4],
+        |x, y| x + y,
+        |i, (x, y), z| log.push(format!("{}: {} + {} = {}", i, x, y, z)),
+    );
+    assert_eq!(vec, [4, 6]);
+    assert_eq!(log, ["0: 1 + 3 = 4", "1: 2 + 4 = 6"]);
+}
+
+#[test]
+fn none_error() {
+    fn run(vec: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(vec.try_map(|x| x.checked_mul(2))?)
+    }
+
+    assert_eq!(run(vec![1, 2]).unwrap(), [2, 4]);
+
+    let err = run(vec![1, 200]).unwrap_err();
+    assert_eq!(err.to_string(), "expected a `Some` value, but found `None`");
+    assert!(err.is::<vec_utils::NoneError>());
+
+    // infallible pipelines can be mixed in with `?`
+    fn halve(vec: Vec<u8>) -> Result<Vec<u8>, vec_utils::NoneError> {
+        let vec = vec.try_map(|x| x.checked_mul(2))?;
+        let vec = vec.try_map(|x| Ok::<_, std::convert::Infallible>(x / 4))?;
+        Ok(vec)
+    }
+
+    assert_eq!(halve(vec![2, 4]), Ok(vec![1, 2]));
+    assert_eq!(halve(vec![200]), Err(vec_utils::NoneError));
+
+    fn checked(vec: Vec<u8>) -> Result<Vec<u8>, vec_utils::AllocError> {
+        Ok(vec.try_map(|x| Ok::<_, std::convert::Infallible>(x + 1))?)
+    }
+
+    assert_eq!(checked(vec![1]), Ok(vec![2]));
+}
+
+#[test]
+fn free_functions() {
+    use vec_utils::fns;
+
+    let vec = fns::map(vec![1u32, 2, 3], |x| x * 2);
+    let mut vec = fns::zip_with(vec, vec![1, 1, 1], |x, y| x - y);
+    fns::retain_map(&mut vec, |x| if x > 1 { Some(x * 10) } else { None });
+
+    assert_eq!(vec, [30, 50]);
+    assert_eq!(fns::try_map(vec, |x| x.checked_sub(40)).ok(), None);
+}
+
+#[test]
+fn leak_guard() {
+    use vec_utils::LeakGuard;
+
+    let guard = LeakGuard::new();
+
+    let vec = (0..10).map(|x| guard.track(x)).collect::<Vec<_>>();
+    assert_eq!(guard.live(), 10);
+
+    guard.exercise(vec.into_boxed_slice(), 3);
+    guard.assert_balanced();
+
+    let leaked = guard.track(String::from("leak"));
+    assert_eq!(guard.live(), 1);
+    assert_eq!(leaked.into_inner(), "leak");
+    guard.assert_balanced();
+}
+
+#[test]
+#[should_panic(expected = "tracked elements [1] were leaked")]
+fn leak_guard_detects_leaks() {
+    let guard = vec_utils::LeakGuard::new();
+
+    drop(guard.track(0));
+    std::mem::forget(guard.track(1));
+}
+
+#[test]
+#[should_panic(expected = "tracked element 0 was dropped twice")]
+fn leak_guard_detects_double_drops() {
+    let guard = vec_utils::LeakGuard::new();
+
+    let tracked = guard.track(0);
+    let copy = unsafe { std::ptr::read(&tracked) };
+
+    // a leak doesn't hide the double drop
+    std::mem::forget(guard.track(1));
+    assert_eq!(guard.leaked(), [0, 1]);
+
+    drop(tracked);
+    drop(copy);
+}
+
+#[test]
+fn try_map_mut() {
+    let mut balances = vec![10u32, 5, 0, 7];
+
+    let result = balances.try_map_mut(
+        |x| x.checked_sub(1).map(|y| *x = y).ok_or("overdrawn"),
+        |x| *x += 1,
+    );
+    assert_eq!(result, Err("overdrawn"));
+    assert_eq!(balances, [10, 5, 0, 7]);
+
+    let result = balances.try_map_mut(
+        |x| {
+            *x += 1;
+            Ok::<_, ()>(())
+        },
+        |x| *x -= 1,
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!(balances, [11, 6, 1, 8]);
+
+    let mut order = Vec::new();
+    let mut vec = vec![1, 2, 3];
+    let _ = vec.try_map_mut(|x| if *x < 3 { Some(()) } else { None }, |x| order.push(*x));
+    assert_eq!(order, [2, 1]);
+}
+
+#[test]
+fn try_map_falloc() {
+    use vec_utils::AllocError;
+
+    let vec = vec![1u32, 2, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.try_map_falloc(|x| x as f32).unwrap();
+    assert_eq!(vec, [1.0, 2.0, 3.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.try_map_falloc(|x| x as u8).unwrap();
+    assert_eq!(vec, [1, 2, 3]);
+
+    let huge = vec![(); usize::MAX];
+    assert_eq!(
+        huge.try_map_falloc(|()| 0u64),
+        Err(AllocError::CapacityOverflow)
+    );
+
+    let huge = vec![(); 1 << 60];
+    assert!(matches!(
+        huge.try_map_falloc(|()| 0u8),
+        Err(AllocError::OutOfMemory(layout)) if layout.size() == 1 << 60
+    ));
+}
+
+#[test]
+fn try_zip_with_falloc() {
+    use vec_utils::AllocError;
+
+    let vec = vec![1u8, 2, 3]
+        .try_zip_with_falloc(vec![1u16, 2], |x, y| u32::from(x) + u32::from(y))
+        .unwrap();
+    assert_eq!(vec, [2, 4]);
+
+    let huge = vec![(); 1 << 60];
+    assert!(matches!(
+        huge.try_zip_with_falloc(vec![(); 1 << 60], |(), ()| 0u8),
+        Err(AllocError::OutOfMemory(_))
+    ));
+}
+
+#[test]
+fn map_rev() {
+    let vec = vec![1u32, 2, 3, 4];
+    let ptr = vec.as_ptr() as usize;
+    let mut order = Vec::new();
+
+    let vec = vec.map_rev(|x| {
+        order.push(x);
+        x as f32
+    });
+
+    assert_eq!(vec, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(order, [4, 3, 2, 1]);
+
+    let vec = vec.map_rev(|x| x as u8);
+    assert_eq!(vec, [1, 2, 3, 4]);
+}
+
+#[test]
+fn zip_with_rev() {
+    let a = vec![1u8, 2, 3];
+    let b = vec![10u32, 20, 30, 40];
+    let ptr = b.as_ptr() as usize;
+    let mut order = Vec::new();
+
+    let vec = a.zip_with_rev(b, |x, y| {
+        order.push(x);
+        u32::from(x) + y
+    });
+
+    assert_eq!(vec, [11, 22, 33]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(order, [3, 2, 1]);
+
+    let vec = vec![1u8, 2].zip_with_rev(vec![3u16, 4], |x, y| u64::from(x) * u64::from(y));
+    assert_eq!(vec, [3, 8]);
+}
+
+#[test]
+fn take_map() {
+    let vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    let (head, rest) = vec.take_map(2, |x| x as f32);
+    assert_eq!(head, [1.0, 2.0]);
+    assert_eq!(rest, [3, 4, 5]);
+    assert_eq!(head.as_ptr() as usize, ptr);
+
+    let vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    let (head, rest) = vec.take_map(3, |x| x as u8);
+    assert_eq!(head, [1, 2, 3]);
+    assert_eq!(rest, [4, 5]);
+    assert_eq!(rest.as_ptr() as usize, ptr);
+
+    let (head, rest) = rest.take_map(10, |x| x * 2);
+    assert_eq!(head, [8, 10]);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn resumable_map() {
+    use std::ops::ControlFlow;
+    use vec_utils::ResumableMap;
+
+    let vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    let mut map = ResumableMap::new(vec, |x| x as f32 * 2.0);
+
+    assert_eq!(map.run_for(0), ControlFlow::Continue(()));
+    assert_eq!(map.run_for(2), ControlFlow::Continue(()));
+    assert_eq!(map.remaining(), 3);
+    assert_eq!(map.run_for(2), ControlFlow::Continue(()));
+
+    let vec = match map.run_for(2) {
+        ControlFlow::Break(vec) => vec,
+        ControlFlow::Continue(()) => panic!("the map should be finished"),
+    };
+
+    assert_eq!(vec, [2.0, 4.0, 6.0, 8.0, 10.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(map.run_for(2), ControlFlow::Break(Vec::new()));
+
+    let mut map = ResumableMap::new(vec![1u32, 2, 3], |x| x as u8);
+    assert_eq!(map.run_for(1), ControlFlow::Continue(()));
+    assert_eq!(map.finish(), [1, 2, 3]);
+
+    let map = ResumableMap::new(Vec::<u32>::new(), |x| x);
+    assert!(map.finish().is_empty());
+}
+
+#[test]
+fn zip_with_threads() {
+    let a = (0..100u32).collect::<Vec<_>>();
+    let b = (0..90u32).map(|x| x as f32).collect::<Vec<_>>();
+    let ptr = a.as_ptr() as usize;
+
+    let vec = a.zip_with_threads(b, |x, y| x + y as u32, 4);
+
+    assert_eq!(vec, (0..90).map(|x| 2 * x).collect::<Vec<u32>>());
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    for &threads in &[0, 1, 3, 200] {
+        let a = (0..10u8).collect::<Vec<_>>();
+        let b = (0..10u8).collect::<Vec<_>>();
+
+        let vec = a.zip_with_threads(b, |x, y| u64::from(x) * u64::from(y), threads);
+
+        assert_eq!(vec, (0..10).map(|x| x * x).collect::<Vec<u64>>());
+    }
+
+    let vec = Vec::<u8>::new().zip_with_threads(vec![1u8], |x, y| x + y, 4);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn zip_longest_with() {
+    use vec_utils::EitherOrBoth;
+
+    let a = vec![1u32, 2];
+    let b = vec![10u32, 20, 30, 40];
+    let ptr = b.as_ptr() as usize;
+
+    let vec = a.zip_longest_with(b, |x| match x {
+        EitherOrBoth::Both(x, y) => x + y,
+        EitherOrBoth::Left(x) => x,
+        EitherOrBoth::Right(y) => y * 2,
+    });
+
+    assert_eq!(vec, [11, 22, 60, 80]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // the shorter vector is reused if it has enough capacity
+    let mut a = Vec::with_capacity(3);
+    a.push(1.0f32);
+    let ptr = a.as_ptr() as usize;
+
+    let vec = a.zip_longest_with(vec![1u8, 2, 3], |x| {
+        x.left().unwrap_or(0.0) + x.right().map_or(0.0, f32::from)
+    });
+
+    assert_eq!(vec, [2.0, 2.0, 3.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![1u8].zip_longest_with(vec![2u16, 3], |x| x.left().is_some());
+    assert_eq!(vec, [true, false]);
+
+    let vec = Vec::<u8>::new().zip_longest_with(Vec::<u8>::new(), |x| x.left());
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn merge_with() {
+    let mut a = Vec::with_capacity(8);
+    a.extend_from_slice(&[1, 4, 6, 9]);
+    let ptr = a.as_ptr() as usize;
+
+    let vec = a.merge_with(vec![2, 3, 7, 10], |x, y| x.cmp(y));
+    assert_eq!(vec, [1, 2, 3, 4, 6, 7, 9, 10]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // the shorter vector is reused if only it has enough capacity
+    let mut b = Vec::with_capacity(5);
+    b.extend_from_slice(&[(0, 'b'), (5, 'b')]);
+    let ptr = b.as_ptr() as usize;
+
+    let vec = vec![(0, 'a'), (2, 'a'), (5, 'a')].merge_by_key(b, |&(k, _)| k);
+    assert_eq!(vec, [(0, 'a'), (0, 'b'), (2, 'a'), (5, 'a'), (5, 'b')]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![3, 2, 1].merge_with(vec![5, 4], |x, y| y.cmp(x));
+    assert_eq!(vec, [5, 4, 3, 2, 1]);
+
+    let vec = Vec::new().merge_by_key(vec![1, 2], |&x: &i32| x);
+    assert_eq!(vec, [1, 2]);
+}
+
+#[test]
+fn intersperse_map() {
+    // `[u32; 2]` to `u32`, there are enough bytes for `2n - 1` outputs
+    let vec = vec![[1u32, 2], [3, 4], [5, 6]];
+    let ptr = vec.as_ptr() as usize;
+    let mut order = Vec::new();
+
+    let vec = vec.intersperse_map(
+        || 0,
+        |[x, y]| {
+            order.push(x);
+            x + y
+        },
+    );
+
+    assert_eq!(vec, [3, 0, 7, 0, 11]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(order, [5, 3, 1]);
+
+    // not enough bytes, so the output is allocated
+    let vec = vec![1u32, 2, 3].intersperse_map(|| u32::MAX, |x| x * 10);
+    assert_eq!(vec, [10, u32::MAX, 20, u32::MAX, 30]);
+
+    let mut vec = Vec::with_capacity(5);
+    vec.extend_from_slice(&[1u32, 2, 3]);
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.intersperse_map(|| ',', |x| char::from_digit(x, 10).unwrap());
+    assert_eq!(vec.as_ptr() as usize, ptr);
+    assert_eq!(vec.into_iter().collect::<String>(), "1,2,3");
+
+    let vec = Vec::<u8>::new().intersperse_map(|| 0u8, |x| x);
+    assert!(vec.is_empty());
+
+    let vec = vec![String::from("a")].intersperse_map(String::new, |x| x);
+    assert_eq!(vec, ["a"]);
+}
+
+#[test]
+fn map_err_in_place() {
+    use vec_utils::VecResultExt;
+
+    let vec: Vec<Result<u32, u32>> = vec![Ok(1), Err(2), Ok(3)];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.map_err_in_place(|e| e as f32);
+    assert_eq!(vec, [Ok(1), Err(2.0), Ok(3)]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.map_err_in_place(|e| format!("{}", e));
+    assert_eq!(vec, [Ok(1), Err(String::from("2")), Ok(3)]);
+}
+
+#[test]
+fn map_option() {
+    use std::num::NonZeroU32;
+    use vec_utils::VecOptionExt;
+
+    let vec = vec![NonZeroU32::new(1), None, NonZeroU32::new(3)];
+    let ptr = vec.as_ptr() as usize;
+
+    // `Option<NonZeroU32>` and `Option<char>` both use a niche
+    let vec = vec.map_option(|x| char::from_digit(x.get(), 10).unwrap());
+    assert_eq!(vec, [Some('1'), None, Some('3')]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.map_option(u64::from);
+    assert_eq!(vec, [Some(49), None, Some(51)]);
+}
+
+#[test]
+fn map_column() {
+    // rgb pixels
+    let vec = vec![10u8, 20, 30, 40, 50, 60, 70];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.map_column(3, 1, |g| g / 10);
+    assert_eq!(vec, [10, 2, 30, 40, 5, 60, 70]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.zip_column_with(3, 0, vec![1u32, 2, 3, 4], |r, x| r + x as u8);
+    assert_eq!(vec, [11, 2, 30, 42, 5, 60, 73]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // stereo audio, only the first frame of the right channel
+    let vec = vec![0.5f32, 0.25, 1.0, 0.75].zip_column_with(2, 1, vec![2.0], |x, y| x * y);
+    assert_eq!(vec, [0.5, 0.5, 1.0, 0.75]);
+
+    let result = std::panic::catch_unwind(|| vec![1, 2, 3].map_column(2, 2, |x| x));
+    assert!(result.is_err());
+}
+
+#[test]
+fn gather_map() {
+    use vec_utils::GatherError;
+
+    let vec = vec![10u32, 11, 12, 13, 14, 15];
+    let ptr = vec.as_ptr() as usize;
+
+    // a path 0 <- 4 <- 5, a cycle 1 -> 2 -> 1 and a fixed point 3
+    let vec = vec.gather_map(&[4, 2, 1, 3, 5], |x| x as f32).unwrap();
+    assert_eq!(vec, [14.0, 12.0, 11.0, 13.0, 15.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.gather_map(&[4, 0], |x| x as u8).unwrap();
+    assert_eq!(vec, [15, 14]);
+
+    let strings = vec!["a", "b", "c", "d"].map(String::from);
+    let vec = strings.gather_map(&[3, 0, 1, 2], |x| x + "!").unwrap();
+    assert_eq!(vec, ["d!", "a!", "b!", "c!"]);
+
+    assert_eq!(
+        vec![1, 2, 3].gather_map(&[0, 3], |x| x),
+        Err(GatherError::OutOfBounds { index: 3, len: 3 })
+    );
+    assert_eq!(
+        vec![1, 2, 3].gather_map(&[2, 0, 2], |x| x),
+        Err(GatherError::Duplicate(2))
+    );
+
+    let vec = vec![1, 2, 3].gather_map(&[], |x| x).unwrap();
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn join_with() {
+    let users = vec![(1u32, "ann"), (2, "bob"), (3, "cat"), (1, "ann 2")];
+    let ages = vec![(3u64, 30u32), (1, 10), (4, 40), (1, 11)];
+
+    let vec = users.join_with(
+        ages,
+        |&(id, _)| u64::from(id),
+        |&(id, _)| id,
+        |(_, name), (_, age)| (name, age),
+    );
+    assert_eq!(vec, [("ann", 10), ("cat", 30), ("ann 2", 11)]);
+
+    // the shorter vector is indexed, and the output reuses `self`
+    let a = vec![5u32, 1, 2, 5, 3, 4];
+    let ptr = a.as_ptr() as usize;
+
+    let vec = a.join_with(vec![5u8, 4, 5], |&x| x, |&y| u32::from(y), |x, _| x as f32);
+    assert_eq!(vec, [5.0, 5.0, 4.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![1u8].join_with(vec![2u8, 1, 1], |&x| x, |&y| y, |x, y| x + y);
+    assert_eq!(vec, [2]);
+
+    let vec = Vec::<u8>::new().join_with(vec![1u8], |&x| x, |&y| y, |x, y| x + y);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn cartesian_with() {
+    // the capacity of `a` covers the product
+    let mut a = Vec::with_capacity(6);
+    a.extend_from_slice(&[1u32, 2]);
+    let ptr = a.as_ptr() as usize;
+
+    let vec = a.cartesian_with(vec![10u8, 20, 30], |&x, &y| x * u32::from(y));
+    assert_eq!(vec, [10, 20, 30, 20, 40, 60]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // the capacity of `b` covers the product
+    let mut b = Vec::with_capacity(4);
+    b.extend_from_slice(&[String::from("x"), String::from("y")]);
+    let ptr = b.as_ptr() as usize;
+
+    let vec = vec!['a', 'b'].cartesian_with(b, |x, y| format!("{}{}", x, y));
+    assert_eq!(vec, ["ax", "ay", "bx", "by"]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![1u8, 2].cartesian_with(vec![3u8, 4], |&x, &y| (x, y));
+    assert_eq!(vec, [(1, 3), (1, 4), (2, 3), (2, 4)]);
+
+    let vec = vec![1u8, 2].cartesian_with(Vec::<u8>::new(), |&x, &y| x + y);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn rle() {
+    use vec_utils::VecRunsExt;
+
+    // each run is long enough to be written in place, and the
+    // allocation is large enough for 4 runs
+    let vec = vec![1u64, 1, 2, 2, 2, 3, 3, 3];
+    let ptr = vec.as_ptr() as usize;
+
+    let runs = vec.rle_encode();
+    assert_eq!(runs, [(1, 2), (2, 3), (3, 3)]);
+    assert_eq!(runs.as_ptr() as usize, ptr);
+    assert_eq!(runs.capacity(), 4);
+
+    let vec = runs.rle_decode();
+    assert_eq!(vec, [1, 1, 2, 2, 2, 3, 3, 3]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // the first run is too short, so the runs are spilled
+    let runs = vec![1u64, 2, 2, 2, 2, 4].rle_encode();
+    assert_eq!(runs, [(1, 1), (2, 4), (4, 1)]);
+
+    let runs = vec!["a", "a", "b"].map(String::from).rle_encode();
+    assert_eq!(runs, [(String::from("a"), 2), (String::from("b"), 1)]);
+
+    let vec = vec![('x', 0), ('y', 2), ('z', 1)].rle_decode();
+    assert_eq!(vec, ['y', 'y', 'z']);
+
+    assert!(Vec::<u8>::new().rle_encode().is_empty());
+    assert!(Vec::<(u8, usize)>::new().rle_decode().is_empty());
+}
+
+#[test]
+fn reduce_pairs() {
+    use vec_utils::OddPolicy;
+
+    let mut vec = vec![1u32, 2, 3, 4, 5];
+    let ptr = vec.as_ptr() as usize;
+
+    vec = vec.reduce_pairs(OddPolicy::Keep, |a, b| a + b);
+    assert_eq!(vec, [3, 7, 5]);
+
+    while vec.len() > 1 {
+        vec = vec.reduce_pairs(OddPolicy::Keep, |a, b| a + b);
+    }
+
+    assert_eq!(vec, [15]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec!["a", "b", "c", "d", "e"]
+        .map(String::from)
+        .reduce_pairs(OddPolicy::Drop, |a, b| a + &b);
+    assert_eq!(vec, ["ab", "cd"]);
+
+    let vec = vec![1u32, 2, 3].reduce_pairs(OddPolicy::Drop, |a, b| a + b);
+    assert_eq!(vec, [3]);
+
+    let vec = vec![1u32].reduce_pairs(OddPolicy::Drop, |a, b| a + b);
+    assert!(vec.is_empty());
+
+    assert!(Vec::<u8>::new()
+        .reduce_pairs(OddPolicy::Keep, |a, b| a + b)
+        .is_empty());
+}
+
+#[test]
+fn scan_in_place() {
+    let vec = vec![1u32, 2, 3, 4];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.prefix_sum();
+    assert_eq!(vec, [1, 3, 6, 10]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec.exclusive_prefix_sum();
+    assert_eq!(vec, [0, 1, 4, 10]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    // running maximum, and whether each element is a new maximum
+    let vec = vec![3i32, 1, 4, 1, 5].scan_in_place(i32::MIN, |&max, x| (max.max(x), x > max));
+    assert_eq!(vec, [true, false, true, false, true]);
+
+    let vec = vec![1.5f32, 2.0].scan_in_place(0.0f64, |&acc, x| {
+        let acc = acc + f64::from(x);
+        (acc, acc as f32)
+    });
+    assert_eq!(vec, [1.5, 3.5]);
+
+    assert!(Vec::<u8>::new().prefix_sum().is_empty());
+}
+
+#[test]
+fn histogram() {
+    // the counts fit in the spare capacity
+    let mut vec = Vec::with_capacity(10);
+    vec.extend_from_slice(&[1u64, 5, 2, 5, 9, 1, 1]);
+    let ptr = vec.as_ptr() as usize;
+
+    let counts = vec.histogram(3, |&x| x as usize / 4);
+    assert_eq!(counts, [4, 2, 1]);
+    assert_eq!(counts.as_ptr() as usize, ptr);
+
+    let counts = vec!["a", "bb", "a", "ccc"].histogram(4, |s| s.len());
+    assert_eq!(counts, [0, 2, 1, 1]);
+
+    let mut counts = vec![0; 2];
+    vec![1u8, 2, 3].count_into(&mut counts, |&x| usize::from(x % 2));
+    vec![5u8].count_into(&mut counts, |&x| usize::from(x % 2));
+    assert_eq!(counts, [1, 3]);
+
+    let result = std::panic::catch_unwind(|| vec![1u8, 2].histogram(2, |&x| usize::from(x)));
+    assert!(result.is_err());
+}
+
+#[test]
+fn sort_by_cached_key_with() {
+    use vec_utils::SliceSortExt;
+
+    // a retired vector with the same layout as `(u64, usize)`
+    let scratch = Vec::<[u64; 2]>::with_capacity(8);
+    let ptr = scratch.as_ptr() as usize;
+
+    let mut vec = vec![5u32, 3, 8, 1, 3, 9, 0];
+    let scratch = vec.sort_by_cached_key_with(|&x| u64::from(x), scratch);
+    assert_eq!(vec, [0, 1, 3, 3, 5, 8, 9]);
+    assert_eq!(scratch.as_ptr() as usize, ptr);
+    assert!(scratch.is_empty());
+
+    // the sort is stable
+    let mut vec = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+    let scratch = vec.sort_by_cached_key_with(|&(k, _)| k as u64, scratch);
+    assert_eq!(vec, [(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+    assert_eq!(scratch.as_ptr() as usize, ptr);
+
+    // an incompatible scratch is dropped
+    let mut vec = vec!["ccc", "a", "bb"];
+    let scratch = vec.sort_by_cached_key_with(|s| s.len(), vec![0u8; 3]);
+    assert_eq!(vec, ["a", "bb", "ccc"]);
+    assert!(scratch.capacity() >= 3);
+}
+
+#[test]
+fn apply_permutation() {
+    use vec_utils::{RecycleBin, SliceSortExt};
+
+    let mut bin = RecycleBin::new();
+
+    let mut vec = vec!["d", "a", "c", "b", "e"];
+    let mut perm = (0..vec.len()).collect::<Vec<_>>();
+    perm.sort_by_key(|&i| vec[i]);
+
+    vec.apply_permutation(&perm, &mut bin);
+    assert_eq!(vec, ["a", "b", "c", "d", "e"]);
+    assert_eq!(bin.len(), 1);
+
+    // the bookkeeping is reused
+    let mut vec = vec![10, 20, 30];
+    vec.apply_permutation(&[2, 0, 1], &mut bin);
+    assert_eq!(vec, [30, 10, 20]);
+    assert_eq!(bin.len(), 1);
+
+    for perm in [&[0, 0, 1][..], &[0, 3, 1], &[0, 1]] {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.apply_permutation(perm, &mut RecycleBin::new())
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(vec, [30, 10, 20]);
+    }
+}
+
+#[test]
+fn chunk_exact_map() {
+    let vec = vec![1u64, 2, 3, 4, 5, 6, 7];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.chunk_exact_map(|[a, b]| a * 10 + b, |tail| Some(tail[0]));
+    assert_eq!(vec, [12, 34, 56, 7]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![1u8, 2, 3, 4, 5].chunk_exact_map(|[a, b, c]| [a, b, c], |_| None);
+    assert_eq!(vec, [[1, 2, 3]]);
+
+    let vec = vec![1u8, 2, 3, 4].chunk_exact_map::<2, _, _, _>(|_| 0u16, |_| unreachable!());
+    assert_eq!(vec, [0, 0]);
+
+    let vec = vec!["a", "b", "c"]
+        .map(String::from)
+        .chunk_exact_map(|[a, b]| a + &b, |tail| tail.into_iter().next());
+    assert_eq!(vec, ["ab", "c"]);
+}
+
+#[test]
+fn zip_fold() {
+    let a = vec![1.0f32, 2.0, 3.0];
+    let b = vec![1.5f32, 2.0, 2.0];
+    let ptr = a.as_ptr() as usize;
+
+    let (diff, max) = a.zip_fold(b, 0.0f32, |max, x, y| {
+        *max = max.max((x - y).abs());
+        x - y
+    });
+
+    assert_eq!(diff, [-0.5, 0.0, 1.0]);
+    assert_eq!(max, 1.0);
+    assert_eq!(diff.as_ptr() as usize, ptr);
+
+    let (vec, count) = vec![1u8, 2, 3].zip_fold(vec![3u8, 2], 0, |count, x, y| {
+        *count += 1;
+        x == y
+    });
+    assert_eq!(vec, [false, true]);
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn ops() {
+    use vec_utils::ops;
+
+    let a = vec![1.0f32, 2.0, 3.0];
+    let ptr = a.as_ptr() as usize;
+
+    let vec = ops::mul(ops::sub(a, vec![0.5, 0.5, 0.5]), vec![2.0, 2.0, 2.0]);
+    assert_eq!(vec, [1.0, 3.0, 5.0]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = ops::saturating_add(vec![250u8, 1], vec![10, 1]);
+    assert_eq!(vec, [255, 2]);
+
+    let vec = ops::saturating_mul(vec![i8::MIN, 3], vec![2, -3]);
+    assert_eq!(vec, [i8::MIN, -9]);
+
+    let vec = ops::wrapping_add(vec![250u8, 1], vec![10, 1]);
+    assert_eq!(vec, [4, 2]);
+
+    let vec = ops::wrapping_sub(vec![0u16], vec![1]);
+    assert_eq!(vec, [u16::MAX]);
+
+    let vec = ops::wrapping_mul(vec![i32::MAX], vec![2]);
+    assert_eq!(vec, [-2]);
+}
+
+#[test]
+fn group_adjacent_map() {
+    let vec = vec![1u32, 1, 2, 3, 3, 3, 1];
+    let ptr = vec.as_ptr() as usize;
+
+    let vec = vec.group_adjacent_map(|&x| x, |k, drain| k * drain.len() as u32);
+    assert_eq!(vec, [2, 2, 9, 1]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let events = vec![(1u64, 'a'), (2, 'b'), (10, 'c'), (11, 'd'), (30, 'e')];
+    let sessions = events.group_adjacent_map(
+        |&(time, _)| time / 10,
+        |session, drain| (session, drain.map(|(_, name)| name).collect::<String>()),
+    );
+    assert_eq!(
+        sessions,
+        [
+            (0, "ab".to_string()),
+            (1, "cd".to_string()),
+            (3, "e".to_string())
+        ]
+    );
+
+    let vec = Vec::<u8>::new().group_adjacent_map(|&x| x, |_, drain| drain.count());
+    assert!(vec.is_empty());
+
+    // the unused parts of a run are dropped
+    let vec = vec![vec![1], vec![1, 2], vec![3]];
+    let vec = vec.group_adjacent_map(|x| x[0], |_, mut drain| drain.next().unwrap());
+    assert_eq!(vec, [vec![1], vec![3]]);
+}
+
+#[test]
+fn repartition() {
+    use vec_utils::{ScaleError, VecArrayExt};
+
+    let mut frames = Vec::with_capacity(6);
+    frames.extend((0..4u16).map(|x| [2 * x, 2 * x + 1]));
+    let ptr = frames.as_ptr() as usize;
+
+    let frames = frames.repartition::<4>().unwrap();
+    assert_eq!(frames, [[0, 1, 2, 3], [4, 5, 6, 7]]);
+    assert_eq!(frames.capacity(), 3);
+    assert_eq!(frames.as_ptr() as usize, ptr);
+
+    let frames = frames.repartition::<1>().unwrap();
+    assert_eq!(frames.len(), 8);
+    assert_eq!(frames.capacity(), 12);
+    assert_eq!(frames.as_ptr() as usize, ptr);
+
+    let err = frames.repartition::<3>().unwrap_err();
+    assert_eq!(err.error(), ScaleError::Uneven { bytes: 16, size: 6 });
+    let frames = err.into_vec();
+    assert_eq!(frames.len(), 8);
+
+    // the length fits, but the capacity doesn't
+    let err = frames.repartition::<8>().unwrap_err();
+    assert_eq!(
+        err.error(),
+        ScaleError::Uneven {
+            bytes: 24,
+            size: 16
+        }
+    );
+
+    let err = vec![[(); 2]].repartition::<1>().unwrap_err();
+    assert_eq!(err.error(), ScaleError::ZeroSized);
+}
+
+#[test]
+fn reuse_plan() {
+    use vec_utils::{Rejection, ReuseConfig, ReusePlan};
+
+    let a = Vec::<u32>::with_capacity(10);
+    let b = vec![0.0f32; 4];
+
+    let plan = ReusePlan::for_zip::<_, _, u32>(&a, &b);
+    assert_eq!(plan.reused(), Some(0));
+    assert_eq!(plan.capacity(), 10);
+    assert_eq!(
+        plan.rejections(),
+        [(1, Rejection::SmallerCapacity { capacity: 4 })]
+    );
+
+    let ptr = a.as_ptr() as usize;
+    let out = a.zip_with(b, |x, y| x + y as u32);
+    assert_eq!(out.as_ptr() as usize, ptr);
+    assert_eq!(out.capacity(), plan.capacity());
+
+    let a = vec![0u8; 3];
+    let b = vec![0u64; 5];
+
+    let plan = ReusePlan::for_zip::<_, _, u64>(&a, &b);
+    assert_eq!(plan.reused(), Some(1));
+    assert_eq!(plan.capacity(), 5);
+    assert_eq!(plan.rejections().len(), 1);
+
+    let plan = ReusePlan::for_zip::<_, _, u16>(&a, &b);
+    assert_eq!(plan.reused(), None);
+    assert_eq!(plan.capacity(), 3);
+    assert_eq!(plan.rejections().len(), 2);
+
+    let plan = ReusePlan::for_map::<_, i64>(&b);
+    assert_eq!(plan.reused(), Some(0));
+    assert!(plan.rejections().is_empty());
+
+    // the config can decline to reuse an allocation
+    let config = ReuseConfig {
+        small_input: 6,
+        ..ReuseConfig::default()
+    };
+
+    let plan = ReusePlan::for_map_with_config::<_, i64>(&b, config);
+    assert_eq!(plan.reused(), None);
+    assert_eq!(
+        plan.rejections(),
+        [(
+            0,
+            Rejection::Config {
+                len: 5,
+                capacity: 5
+            }
+        )]
+    );
+
+    let mut a = Vec::<u64>::with_capacity(16);
+    a.extend([1, 2, 3]);
+
+    let config = ReuseConfig {
+        max_waste: Some(8),
+        shrink_on_finish: true,
+        ..ReuseConfig::default()
+    };
+
+    let plan = ReusePlan::for_zip_with_config::<_, _, u64>(&a, &b, config);
+    assert_eq!(plan.reused(), Some(1));
+    assert_eq!(plan.capacity(), 3);
+    assert_eq!(
+        plan.rejections(),
+        [(
+            0,
+            Rejection::Config {
+                len: 3,
+                capacity: 16
+            }
+        )]
+    );
+
+    let out = a.zip_with_with_config(b, config, |x, y| x + y);
+    assert_eq!(out.capacity(), plan.capacity());
+}
+
+#[test]
+fn feed_map() {
+    use vec_utils::FeedMap;
+
+    // the first chunk becomes the output
+    let mut first = Vec::<u32>::with_capacity(8);
+    first.extend([1, 2, 3]);
+    let ptr = first.as_ptr() as usize;
+
+    let mut feed = FeedMap::new(|x: u32| x as f32);
+    feed.feed(first);
+    feed.feed(vec![4, 5]);
+    feed.feed(Vec::new());
+    assert_eq!(feed.len(), 5);
+
+    let out = feed.finish();
+    assert_eq!(out, [1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let donor = Vec::<[u16; 4]>::with_capacity(4);
+    let ptr = donor.as_ptr() as usize;
+
+    let mut feed = FeedMap::with_output(donor, u64::from);
+    let chunk = vec![1u32, 2, 3];
+    let chunk_ptr = chunk.as_ptr() as usize;
+    feed.feed(chunk);
+    feed.feed_iter(4..=4);
+    assert!(!feed.is_empty());
+
+    // the chunk is kept as the spare buffer
+    let spare = feed.take_spare();
+    assert!(spare.is_empty());
+    assert_eq!(spare.as_ptr() as usize, chunk_ptr);
+    assert_eq!(feed.take_spare().capacity(), 0);
+
+    let out = feed.finish();
+    assert_eq!(out, [1, 2, 3, 4]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let feed = FeedMap::new(|x: u8| x);
+    assert!(feed.is_empty());
+    assert!(feed.finish().is_empty());
+}
+
+#[test]
+fn reusable_mapper() {
+    use vec_utils::ReusableMapper;
+
+    let mut mapper = ReusableMapper::new();
+
+    let input = vec![1u8, 2, 3];
+    let in_ptr = input.as_ptr() as usize;
+
+    let output = mapper.run(input, |x| x as u64 * 2);
+    assert_eq!(output, [2, 4, 6]);
+    let out_ptr = output.as_ptr() as usize;
+
+    mapper.recycle(output);
+
+    let mut input = mapper.take_input();
+    assert_eq!(input.as_ptr() as usize, in_ptr);
+    assert!(input.is_empty());
+    input.extend_from_slice(&[4, 5]);
+
+    let output = mapper.run(input, |x| x as u64 * 3);
+    assert_eq!(output, [12, 15]);
+    assert_eq!(output.as_ptr() as usize, out_ptr);
+}
+
+#[test]
+fn recycled_alloc() {
+    use vec_utils::RecycledAlloc;
+
+    // the allocation is reused, so there is nothing to recycle
+    let (vec, spare) = vec![1u32, 2].map_recycle(|x| x as f32);
+    assert_eq!(vec, [1.0, 2.0]);
+    assert!(spare.is_none());
+
+    let input = vec![1u8, 2, 3, 4];
+    let ptr = input.as_ptr() as usize;
+
+    let (vec, spare) = input.map_recycle(u32::from);
+    assert_eq!(vec, [1, 2, 3, 4]);
+
+    let spare = spare.unwrap();
+    assert_eq!(spare.layout().size(), 4);
+    let spare = spare.into_vec::<u16>().unwrap_err();
+    let spare = spare.into_vec::<[u8; 2]>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+    assert_eq!(spare.capacity(), 2);
+
+    let spare = RecycledAlloc::new(vec![0u64]).unwrap().into_uninit_box();
+    assert_eq!(*spare.init(1.0f64), 1.0);
+
+    assert!(RecycledAlloc::new(Vec::<u8>::with_capacity(0)).is_none());
+    assert!(RecycledAlloc::new(vec![(); 3]).is_none());
+
+    // the left allocation is reused, the right one is recycled
+    let right = vec![1u8, 2, 3];
+    let ptr = right.as_ptr() as usize;
+    let (vec, spare) = vec![1u32, 2].zip_with_recycle(right, |x, y| x + y as u32);
+    assert_eq!(vec, [2, 4]);
+    let spare = spare.unwrap().into_vec::<u8>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+
+    // the right allocation is reused, the left one is recycled
+    let left = vec![1u8, 2, 3];
+    let ptr = left.as_ptr() as usize;
+    let (vec, spare) = left.zip_with_recycle(vec![1u32, 2], |x, y| x as u32 + y);
+    assert_eq!(vec, [2, 4]);
+    let spare = spare.unwrap().into_vec::<u8>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+
+    // neither allocation is reused, the larger one is recycled
+    let right = vec![1u16, 2, 3];
+    let ptr = right.as_ptr() as usize;
+    let (vec, spare) = vec![1u8, 2].zip_with_recycle(right, |x, y| x as u32 + y as u32);
+    assert_eq!(vec, [2, 4]);
+    let spare = spare.unwrap().into_vec::<u16>().unwrap();
+    assert_eq!(spare.as_ptr() as usize, ptr);
+
+    let res = vec![1u8, 2].try_map_recycle(|x| if x < 2 { Ok(x as u32) } else { Err(x) });
+    assert_eq!(res.unwrap_err(), 2);
+}
+
+#[test]
+fn transparent() {
+    use std::cell::{Cell, UnsafeCell};
+    use std::num::Wrapping;
+    use vec_utils::TransparentVecExt;
+
+    let vec = vec![String::from("a"), String::from("b")];
+    let ptr = vec.as_ptr() as usize;
+
+    let cells = vec.wrap_transparent::<Cell<String>>();
+    cells[1].set(String::from("c"));
+    assert_eq!(cells.as_ptr() as usize, ptr);
+
+    let vec = cells.unwrap_transparent();
+    let cells = vec.wrap_transparent::<UnsafeCell<String>>();
+    let vec = cells.unwrap_transparent();
+    assert_eq!(vec, ["a", "c"]);
+    assert_eq!(vec.as_ptr() as usize, ptr);
+
+    let vec = vec![u8::MAX, 1].wrap_transparent::<Wrapping<u8>>();
+    let vec = vec.map(|x| x + Wrapping(1)).unwrap_transparent();
+    assert_eq!(vec, [0, 2]);
+}
+
+#[test]
+fn reuse_config() {
+    use vec_utils::{FallbackStrategy, RecycleBin, ReuseConfig};
+
+    fn input(cap: usize) -> (Vec<u32>, usize) {
+        let mut vec = Vec::with_capacity(cap);
+        vec.extend([1, 2, 3]);
+        let ptr = vec.as_ptr() as usize;
+        (vec, ptr)
+    }
+
+    // too much unused capacity
+    let config = ReuseConfig {
+        max_waste: Some(8),
+        ..ReuseConfig::default()
+    };
+
+    let (vec, ptr) = input(64);
+    let out = vec.map_with_config(config, |x| x as f32);
+    assert_eq!(out, [1.0, 2.0, 3.0]);
+    assert_ne!(out.as_ptr() as usize, ptr);
+
+    let (vec, ptr) = input(11);
+    let out = vec.map_with_config(config, |x| x as f32);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    // the smaller allocation is reused if the larger one wastes too much
+    let (vec, _) = input(64);
+    let (other, ptr) = input(4);
+    let out = vec.zip_with_with_config(other, config, |x, y| x + y);
+    assert_eq!(out, [2, 4, 6]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    // too short to reuse
+    let config = ReuseConfig {
+        small_input: 4,
+        ..ReuseConfig::default()
+    };
+
+    let (vec, ptr) = input(3);
+    let out = vec.map_with_config(config, |x| x as f32);
+    assert_ne!(out.as_ptr() as usize, ptr);
+
+    let (vec, ptr) = input(3);
+    let out: Result<Vec<u32>, ()> = vec.try_map_with_config(
+        ReuseConfig {
+            small_input: 3,
+            ..config
+        },
+        Ok,
+    );
+    let out = out.unwrap();
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    // shrink the output
+    let config = ReuseConfig {
+        shrink_on_finish: true,
+        ..ReuseConfig::default()
+    };
+
+    let (vec, _) = input(16);
+    let out = vec.map_with_config(config, |x| x as f32);
+    assert_eq!(out.capacity(), 3);
+
+    let (vec, _) = input(16);
+    let out = vec.map_with_config(config, u64::from);
+    assert_eq!(out.capacity(), 3);
+
+    // don't take allocations from the recycle bin
+    let config = ReuseConfig {
+        fallback: FallbackStrategy::Allocate,
+        ..ReuseConfig::default()
+    };
+
+    RecycleBin::with_local(|bin| {
+        bin.clear();
+        bin.deposit(vec![0u64; 6]);
+    });
+
+    let (vec, _) = input(3);
+    let out = vec.map_with_config(config, u64::from);
+    assert_eq!(out, [1, 2, 3]);
+    assert_eq!(RecycleBin::with_local(|bin| bin.len()), 1);
+
+    let (vec, _) = input(3);
+    let out = vec.map_with_config(ReuseConfig::default(), u64::from);
+    assert_eq!(out.capacity(), 6);
+    assert!(RecycleBin::with_local(|bin| bin.is_empty()));
+
+    // the length of the output is predicted from the capacity hint
+    let config = ReuseConfig {
+        max_waste: Some(16),
+        ..ReuseConfig::default()
+    };
+
+    let vec = (0..100u32).collect::<Vec<_>>();
+    let ptr = vec.as_ptr() as usize;
+    let out = vec
+        .into_reuse_iter()
+        .filter(|x| x % 10 == 0)
+        .with_capacity_hint(10)
+        .collect_reuse_with_config(config);
+    assert_eq!(out, (0..10).map(|x| x * 10).collect::<Vec<_>>());
+    assert_ne!(out.as_ptr() as usize, ptr);
+
+    let vec = (0..100u32).collect::<Vec<_>>();
+    let ptr = vec.as_ptr() as usize;
+    let out = vec
+        .into_reuse_iter()
+        .map(|x| x + 1)
+        .collect_reuse_with_config(config);
+    assert_eq!(out.len(), 100);
+    assert_eq!(out.as_ptr() as usize, ptr);
+}
+
+#[test]
+fn try_filter_map() {
+    let vec = vec![1u32, 2, 3, 4, 5, 6];
+    let ptr = vec.as_ptr() as usize;
+
+    let out = vec.try_filter_map(|x| match x {
+        4 => Ok(None),
+        x => Ok::<_, ()>(Some(x as f32)),
+    });
+    let out = out.unwrap();
+    assert_eq!(out, [1.0, 2.0, 3.0, 5.0, 6.0]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    let out = vec![1u8, 2, 3].try_filter_map(|x| match x {
+        2 => Err(x),
+        x => Ok(Some(u64::from(x))),
+    });
+    assert_eq!(out, Err(2));
+
+    let out = vec![1u8, 2, 3].try_filter_map(|x| Some(Some(u64::from(x)).filter(|&x| x != 2)));
+    assert_eq!(out, Ok(vec![1, 3]));
+
+    let out = vec!["a", "b"].try_filter_map(|_| Ok::<Option<&str>, ()>(None));
+    assert_eq!(out, Ok(vec![]));
+}
+
+#[test]
+fn flat_map_reuse() {
+    // the output never catches up with the input
+    let vec = vec![1u32, 2, 0, 3, 0, 0];
+    let ptr = vec.as_ptr() as usize;
+    let out = vec.flat_map_reuse(|x| vec![x as f32; x.min(2) as usize]);
+    assert_eq!(out, [1.0, 2.0, 2.0, 3.0, 3.0]);
+    assert_eq!(out.as_ptr() as usize, ptr);
+
+    // the unread input is spilled, and the output grows
+    let out = vec![1u32, 2, 3, 4].flat_map_reuse(|x| vec![x; x as usize]);
+    assert_eq!(out, [1, 2, 2, 3, 3, 3, 4, 4, 4, 4]);
+
+    let out = vec![3u8, 0, 1].flat_map_reuse(|x| (0..x).map(move |_| x));
+    assert_eq!(out, [3, 3, 3, 1]);
+
+    let out = vec![1u8, 2].flat_map_reuse(|x| vec![u64::from(x); 2]);
+    assert_eq!(out, [1, 1, 2, 2]);
+
+    let out = vec![(); 3].flat_map_reuse(|()| [(), ()]);
+    assert_eq!(out.len(), 6);
+
+    let out = Vec::<u32>::new().flat_map_reuse(|x| [x, x]);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn buf_transform() {
+    use std::io::{self, Read};
+    use vec_utils::BufTransform;
+
+    // returns `Interrupted` before every successful read
+    struct Flaky<'a>(&'a [u8], bool);
+
+    impl Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.1 = !self.1;
+
+            if self.1 {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+
+            self.0.read(buf)
+        }
+    }
+
+    let mut transform = BufTransform::with_capacity(4);
+    let capacity = transform.capacity();
+    let mut output = Vec::new();
+
+    let written = transform
+        .map(&b"hello world"[..], &mut output, |b| b.to_ascii_uppercase())
+        .unwrap();
+
+    assert_eq!(written, 11);
+    assert_eq!(output, b"HELLO WORLD");
+
+    output.clear();
+
+    let written = transform
+        .filter(Flaky(b"a,b,,c,", false), &mut output, |b| b != b',')
+        .unwrap();
+
+    assert_eq!(written, 3);
+    assert_eq!(output, b"abc");
+    assert_eq!(transform.capacity(), capacity);
+
+    let buf = transform.into_inner();
+    let ptr = buf.as_ptr() as usize;
+
+    let mut transform = BufTransform::from_vec(buf);
+    let mut output = Vec::new();
+    transform.map(&b"xyz"[..], &mut output, |b| b - 23).unwrap();
+
+    assert_eq!(output, b"abc");
+    let buf = transform.into_inner();
+    assert_eq!(buf.as_ptr() as usize, ptr);
+
+    let mut transform = BufTransform::new();
+    let mut output = Vec::new();
+    transform.map(&[][..], &mut output, |b| b).unwrap();
+    assert_eq!(transform.capacity(), 8 * 1024);
+}
+
+#[test]
+fn raw_input_output() {
+    use vec_utils::{Input, Output, RawInput, RawOutput};
+
+    // stands in for a foreign function that fills the output
+    unsafe extern "C" fn double(input: &mut RawInput<u32>, output: &mut RawOutput<f32>, n: usize) {
+        for _ in 0..n {
+            let value = input.ptr.read();
+            input.ptr = input.ptr.add(1);
+            input.read += 1;
+
+            output.ptr.write(value as f32 * 2.0);
+            output.ptr = output.ptr.add(1);
+        }
+    }
+
+    let vec = vec![1u32, 2, 3, 4];
+    let ptr = vec.as_ptr() as usize;
+
+    let mut input = Input::from(vec);
+    let output: Output<f32> = unsafe { input.take_output() };
+
+    let mut raw_input = input.into_raw_parts();
+    let mut raw_output = output.into_raw_parts();
+
+    assert_eq!(raw_input.len, 4);
+    assert!(!raw_input.owns_alloc);
+
+    unsafe {
+        double(&mut raw_input, &mut raw_output, 3);
+
+        let mut input = Input::from_raw_parts(raw_input);
+        let output = Output::from_raw_parts(raw_output);
+
+        input.drop_rest(3);
+        let vec = output.into_vec();
+
+        assert_eq!(vec, [2.0, 4.0, 6.0]);
+        assert_eq!(vec.as_ptr() as usize, ptr);
+    }
+}